@@ -5,21 +5,32 @@ pub use crate::location::{Location, LocationSpan};
 pub use crate::token::Token;
 pub use crate::visitor::AstVisitor;
 use shiika_core::names::*;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 #[derive(Debug, PartialEq)]
 pub struct Program {
     pub toplevel_items: Vec<TopLevelItem>,
+    /// `import` statements (only for a program parsed from a single file)
+    pub imports: Vec<Import>,
+    /// Names imported with `import A from "..."` (set by the loader)
+    pub imported_names: ImportedNames,
 }
 
 impl Program {
     pub fn default() -> Program {
         Program {
             toplevel_items: vec![],
+            imports: vec![],
+            imported_names: Default::default(),
         }
     }
 
     pub fn append(&mut self, other: &mut Program) {
         self.toplevel_items.append(&mut other.toplevel_items);
+        self.imports.append(&mut other.imports);
+        self.imported_names
+            .merge(std::mem::take(&mut other.imported_names));
     }
 
     pub fn defs(&self) -> Vec<&Definition> {
@@ -33,6 +44,65 @@ impl Program {
     }
 }
 
+/// `import "./foo.sk"`, `import "./foo.sk" as Foo` or `import A, B from "./foo.sk"`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Import {
+    /// The string literal after `import` (a relative path or a package name)
+    pub path: String,
+    pub kind: ImportKind,
+    /// Location of the path
+    pub locs: LocationSpan,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ImportKind {
+    /// `import "./foo.sk"` (or `require "./foo.sk"`)
+    /// All the toplevel names of the file are visible from the importing file.
+    All,
+    /// `import "./foo.sk" as Foo`
+    /// Toplevel names of the file are visible as `Foo::...`.
+    Namespace(String),
+    /// `import A, B from "./foo.sk"`
+    /// Only `A` and `B` are visible from the importing file.
+    Names(Vec<(String, LocationSpan)>),
+}
+
+impl Import {
+    pub fn is_package(&self) -> bool {
+        !self.path.starts_with("./") && !self.path.starts_with("../")
+    }
+}
+
+/// Names made visible by `import A from "..."`, for each file.
+/// eg. `{"main.sk" => {"A" => ["Foo", "A"]}}` means `A` in main.sk refers
+/// to `Foo::A`.
+#[derive(Debug, PartialEq, Default)]
+pub struct ImportedNames(HashMap<PathBuf, HashMap<String, Vec<String>>>);
+
+impl ImportedNames {
+    pub fn insert(&mut self, file: PathBuf, name: String, fullname: Vec<String>) {
+        self.0.entry(file).or_default().insert(name, fullname);
+    }
+
+    pub fn merge(&mut self, other: ImportedNames) {
+        for (file, names) in other.0 {
+            self.0.entry(file).or_default().extend(names);
+        }
+    }
+
+    /// Returns the full name of `name` if it is imported into the file `locs`
+    /// belongs to.
+    pub fn lookup(&self, locs: &LocationSpan, name: &str) -> Option<&[String]> {
+        let LocationSpan::Just { filepath, .. } = locs else {
+            return None;
+        };
+        self.0
+            .get(filepath.as_ref())
+            .and_then(|names| names.get(name))
+            .map(|v| v.as_slice())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum TopLevelItem {
     Def(Definition),
@@ -47,17 +117,26 @@ pub enum Definition {
         typarams: Vec<AstTyParam>,
         supers: Vec<UnresolvedTypeName>,
         defs: Vec<Definition>,
+        visibility: Visibility,
+        /// Location of the class name
+        locs: LocationSpan,
     },
     ModuleDefinition {
         name: ModuleFirstname,
         typarams: Vec<AstTyParam>,
         defs: Vec<Definition>,
+        visibility: Visibility,
+        /// Location of the module name
+        locs: LocationSpan,
     },
     EnumDefinition {
         name: ClassFirstname,
         typarams: Vec<AstTyParam>,
         cases: Vec<EnumCase>,
         defs: Vec<Definition>,
+        visibility: Visibility,
+        /// Location of the enum name
+        locs: LocationSpan,
     },
     InstanceMethodDefinition {
         sig: AstMethodSignature,
@@ -75,9 +154,41 @@ pub enum Definition {
     ConstDefinition {
         name: String,
         expr: AstExpression,
+        visibility: Visibility,
+        /// Location of the constant name
+        locs: LocationSpan,
     },
 }
 
+impl Definition {
+    /// Set the visibility given with `private` or `public`. Returns false if
+    /// the definition cannot have one (i.e. initializers and requirements.)
+    pub fn set_visibility(&mut self, vis: Visibility) -> bool {
+        match self {
+            Definition::ClassDefinition { visibility, .. }
+            | Definition::ModuleDefinition { visibility, .. }
+            | Definition::EnumDefinition { visibility, .. }
+            | Definition::ConstDefinition { visibility, .. } => *visibility = vis,
+            Definition::InstanceMethodDefinition { sig, .. }
+            | Definition::ClassMethodDefinition { sig, .. } => sig.visibility = vis,
+            Definition::InitializerDefinition(_)
+            | Definition::ClassInitializerDefinition(_)
+            | Definition::MethodRequirementDefinition { .. } => return false,
+        }
+        true
+    }
+}
+
+/// Visibility of a method, a constant or a nested type
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Visibility {
+    #[default]
+    Public,
+    /// Methods can only be called without an explicit receiver (or with
+    /// `self`) and constants can only be referred from inside the namespace
+    Private,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct InitializerDefinition {
     pub sig: AstMethodSignature,
@@ -102,6 +213,8 @@ pub fn find_class_initializer(defs: &[Definition]) -> Option<&InitializerDefinit
 pub struct EnumCase {
     pub name: ClassFirstname,
     pub params: Vec<Param>,
+    /// Location of the case name
+    pub locs: LocationSpan,
 }
 
 impl EnumCase {
@@ -117,6 +230,9 @@ pub struct AstMethodSignature {
     pub typarams: Vec<AstTyParam>,
     pub params: Vec<Param>,
     pub ret_typ: Option<UnresolvedTypeName>,
+    pub visibility: Visibility,
+    /// Location of the method name
+    pub locs: LocationSpan,
 }
 
 /// A type parameter
@@ -124,13 +240,15 @@ pub struct AstMethodSignature {
 pub struct AstTyParam {
     pub name: String,
     pub variance: AstVariance,
+    /// eg. `Comparable<T>` of `T : Comparable<T>`
+    pub upper_bound: Option<UnresolvedTypeName>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum AstVariance {
     Invariant,
-    Covariant,     // eg. `in T`
-    Contravariant, // eg. `out T`
+    Covariant,     // eg. `out T`
+    Contravariant, // eg. `in T`
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -243,6 +361,13 @@ pub enum AstExpressionBody {
     },
     PseudoVariable(Token),
     ArrayLiteral(Vec<AstExpression>),
+    /// `first..last` or `first...last`
+    RangeLiteral {
+        first: Box<AstExpression>,
+        last: Box<AstExpression>,
+        /// true if this is `...` (does not include `last`)
+        exclusive: bool,
+    },
     FloatLiteral {
         value: f64,
     },
@@ -252,6 +377,10 @@ pub enum AstExpressionBody {
     StringLiteral {
         content: String,
     },
+    /// `/source/`
+    RegexLiteral {
+        source: String,
+    },
 }
 
 /// Method call has its own struct
@@ -365,15 +494,48 @@ pub enum AstPattern {
     ExtractorPattern {
         names: Vec<String>,
         params: Vec<AstPattern>,
+        /// Location of the class name
+        locs: LocationSpan,
     },
     VariablePattern(String),
     BooleanLiteralPattern(bool),
     IntegerLiteralPattern(i64),
     FloatLiteralPattern(f64),
     StringLiteralPattern(String),
+    /// `1..5`, `0.0...1.0`, etc.
+    RangePattern {
+        first: Box<AstPattern>,
+        last: Box<AstPattern>,
+        exclusive: bool,
+    },
+    /// `A | B`
+    OrPattern(Vec<AstPattern>),
+    /// `[a, b]`, `[first, *rest]`, `[*init, last]`, etc.
+    ArrayPattern {
+        /// Patterns before `*rest` (or all the patterns if no `*rest`)
+        before: Vec<AstPattern>,
+        /// Name of `*rest` (if any)
+        rest: Option<String>,
+        /// Patterns after `*rest`
+        after: Vec<AstPattern>,
+    },
+    /// `p @ Point(x, _)`
+    BindingPattern {
+        name: String,
+        pattern: Box<AstPattern>,
+    },
 }
 
-pub type AstMatchClause = (AstPattern, Vec<AstExpression>);
+/// A `when` (or `else`) clause of match expression
+#[derive(Debug, PartialEq, Clone)]
+pub struct AstMatchClause {
+    pub pattern: AstPattern,
+    /// `if cond` after the pattern (if any)
+    pub guard: Option<AstExpression>,
+    pub body: Vec<AstExpression>,
+    /// Location of the pattern (or `else`)
+    pub locs: LocationSpan,
+}
 
 impl AstExpression {
     pub fn may_have_paren_wo_args(&self) -> bool {
@@ -403,6 +565,8 @@ impl AstExpression {
             Some(Definition::ConstDefinition {
                 name: names.join("::"),
                 expr: *rhs.clone(),
+                visibility: Visibility::Public,
+                locs: self.locs.clone(),
             })
         } else {
             None
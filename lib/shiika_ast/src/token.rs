@@ -4,7 +4,7 @@ pub enum Token {
     Eof,
     Space,
     Semicolon,
-    Newline, // A comment is always followed by a newline, so treat the combination as a newline
+    Newline, // Comments are not tokens; they are kept by the lexer as trivia
     UpperWord(String),
     LowerWord(String),
     IVar(String),
@@ -15,6 +15,7 @@ pub enum Token {
         head: String,  // Contents before `#{'
         inspect: bool, // true if `\{}', which calls .inspect instead of .to_s
     },
+    Regex(String), // Such as `/a+/` (without the slashes)
     // Symbols
     LParen,      //  (
     RParen,      //  )
@@ -35,10 +36,13 @@ pub enum Token {
     LessThan,    //  <
     GreaterThan, //  >
     LessEq,      //  <=
+    Compare,     //  <=>
     GreaterEq,   //  >=
     Equal,       //  =
     Bang,        //  !
     Dot,         //  .
+    DotDot,      //  ..
+    DotDotDot,   //  ...
     At,          //  @
     Tilde,       //  ~
     Question,    //  ?
@@ -72,7 +76,10 @@ pub enum Token {
     Specialize,   //  <> (used internally)
     // Keywords
     KwRequire,
+    KwImport,
     KwBase,
+    KwPrivate,
+    KwPublic,
     KwClass,
     KwModule,
     KwRequirement,
@@ -144,6 +151,7 @@ impl Token {
             Token::Str(_) => true,
             Token::KeyName(_) => false,
             Token::StrWithInterpolation { .. } => true,
+            Token::Regex(_) => true,
             // Symbols
             Token::LParen => true,       //  (
             Token::RParen => false,      //  )
@@ -164,10 +172,13 @@ impl Token {
             Token::LessThan => false,    //  <
             Token::GreaterThan => false, //  >
             Token::LessEq => false,      //  <=
+            Token::Compare => false,     //  <=>
             Token::GreaterEq => false,   //  >=
             Token::Equal => false,       //  =
             Token::Bang => true,         //  !
             Token::Dot => false,         //  .
+            Token::DotDot => false,      //  ..
+            Token::DotDotDot => false,   //  ...
             Token::At => true,           //  @
             Token::Tilde => true,        //  ~
             Token::Question => false,    //  ?
@@ -201,7 +212,10 @@ impl Token {
             Token::Specialize => false,   //  <>
             // Keywords
             Token::KwRequire => false,
+            Token::KwImport => false,
             Token::KwBase => false,
+            Token::KwPrivate => false,
+            Token::KwPublic => false,
             Token::KwClass => false,
             Token::KwModule => false,
             Token::KwRequirement => false,
@@ -91,7 +91,7 @@ pub trait AstVisitor {
                 self.visit_method_definition(namespace, false, true, &d.sig, &d.body_exprs)?;
             }
 
-            Definition::ConstDefinition { name, expr } => {
+            Definition::ConstDefinition { name, expr, .. } => {
                 self.visit_const_definition(namespace, name, expr)?;
             }
 
@@ -68,12 +68,8 @@ pub fn typarams_to_typaram_refs(
         .iter()
         .enumerate()
         .map(|(i, t)| TyParamRef {
-            kind: kind.clone(),
-            name: t.name.clone(),
-            idx: i,
-            upper_bound: t.upper_bound.clone(),
-            lower_bound: t.lower_bound.clone(),
             as_class,
+            ..t.to_typaram_ref(kind.clone(), i)
         })
         .collect()
 }
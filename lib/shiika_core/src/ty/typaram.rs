@@ -1,4 +1,5 @@
-use crate::ty::LitTy;
+use crate::ty::typaram_ref::parse_bounds;
+use crate::ty::{LitTy, TyParamKind, TyParamRef};
 use nom::IResult;
 use serde::{Deserialize, Serialize};
 
@@ -14,8 +15,8 @@ pub struct TyParam {
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum Variance {
     Invariant,
-    Covariant,     // eg. `in T`
-    Contravariant, // eg. `out T`
+    Covariant,     // eg. `out T`
+    Contravariant, // eg. `in T`
 }
 
 impl TyParam {
@@ -28,6 +29,23 @@ impl TyParam {
         }
     }
 
+    /// Returns true if the upper bound or the lower bound is given.
+    pub fn is_bounded(&self) -> bool {
+        self.upper_bound.base_name != "Object" || self.lower_bound.base_name != "Never"
+    }
+
+    /// Create a reference to this type parameter
+    pub fn to_typaram_ref(&self, kind: TyParamKind, idx: usize) -> TyParamRef {
+        TyParamRef {
+            kind,
+            name: self.name.clone(),
+            idx,
+            upper_bound: self.upper_bound.clone(),
+            lower_bound: self.lower_bound.clone(),
+            as_class: false,
+        }
+    }
+
     /// Returns a serialized string which can be parsed by `deserialize`
     pub fn serialize(&self) -> String {
         let flag = match &self.variance {
@@ -35,7 +53,16 @@ impl TyParam {
             Variance::Covariant => "+",
             Variance::Contravariant => "-",
         };
-        format!("{}{}", flag, &self.name)
+        let bound = if self.is_bounded() {
+            format!(
+                ":{}~{}",
+                self.upper_bound.serialize(),
+                self.lower_bound.serialize()
+            )
+        } else {
+            "".to_string()
+        };
+        format!("{}{}{}", flag, &self.name, bound)
     }
 
     /// nom parser for TyParam
@@ -48,6 +75,12 @@ impl TyParam {
         };
 
         let (s, name) = nom::character::complete::alphanumeric1(s)?;
-        Ok((s, TyParam::new(name.to_string(), variance)))
+        let (s, opt_bounds) = nom::combinator::opt(parse_bounds)(s)?;
+        let mut typaram = TyParam::new(name.to_string(), variance);
+        if let Some((upper_bound, lower_bound)) = opt_bounds {
+            typaram.upper_bound = upper_bound;
+            typaram.lower_bound = lower_bound;
+        }
+        Ok((s, typaram))
     }
 }
@@ -4,7 +4,7 @@ use crate::ty::term_ty::{TermTy, TyBody};
 use nom::{bytes::complete::tag, IResult};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TyParamRef {
     pub kind: TyParamKind,
     pub name: String,
@@ -15,6 +15,19 @@ pub struct TyParamRef {
     pub as_class: bool,
 }
 
+/// Two references to the same type parameter are equal even if one of them
+/// does not know the bounds (eg. `T` in `T : Comparable<T>`.)
+impl PartialEq for TyParamRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.name == other.name
+            && self.idx == other.idx
+            && self.as_class == other.as_class
+    }
+}
+
+impl Eq for TyParamRef {}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub enum TyParamKind {
     /// eg. `class A<B>`
@@ -1,20 +1,36 @@
 mod array;
 mod bool;
+mod channel;
 pub mod class; // pub for WitnessTable
+mod file;
 mod float;
+mod fn_;
 mod int;
+mod listener;
 mod mutable_string;
+mod mutex;
 mod object;
+mod process;
 mod random;
+mod socket;
 mod string;
+mod task;
 pub mod time;
 pub use array::SkArray;
 pub use bool::SkBool;
+pub use channel::SkChannel;
 pub use class::SkClass;
+pub use file::SkFile;
 pub use float::SkFloat;
+pub use fn_::SkFn;
 pub use int::SkInt;
+pub use listener::SkListener;
 pub use mutable_string::SkMutableString;
+pub use mutex::SkMutex;
 pub use object::SkObject;
+pub use process::SkChild;
 pub use random::SkRandom;
+pub use socket::SkSocket;
 pub use string::SkString;
+pub use task::SkTask;
 pub use time::{SkInstant, SkPlainDate, SkPlainDateTime, SkPlainTime, SkTime, SkZone};
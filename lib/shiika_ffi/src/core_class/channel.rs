@@ -0,0 +1,21 @@
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkChannel(*mut ShiikaChannel);
+
+unsafe impl Send for SkChannel {}
+
+// Must match `packages/core/lib/channel.sk`.
+#[repr(C)]
+#[derive(Debug)]
+struct ShiikaChannel {
+    vtable: *const u8,
+    class_obj: *const u8,
+    state: *mut u8, // Opaque here; the actual type is defined in packages/core/ext
+}
+
+impl SkChannel {
+    /// Returns the value of `@_state`.
+    pub fn state(&self) -> *mut u8 {
+        unsafe { (*self.0).state }
+    }
+}
@@ -0,0 +1,26 @@
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkFile(*mut ShiikaFile);
+
+unsafe impl Send for SkFile {}
+
+// Must match `packages/core/lib/file.sk`.
+#[repr(C)]
+#[derive(Debug)]
+struct ShiikaFile {
+    vtable: *const u8,
+    class_obj: *const u8,
+    state: *mut u8, // Opaque here; the actual type is defined in packages/core/ext
+}
+
+impl SkFile {
+    /// Returns the pointer to the object (eg. to register a finalizer.)
+    pub fn as_ptr(&self) -> *const u8 {
+        self.0 as *const u8
+    }
+
+    /// Returns the value of `@_state`.
+    pub fn state(&self) -> *mut u8 {
+        unsafe { (*self.0).state }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::core_class::SkInt;
+
+/// Shiika `Fn` object (the base class of `Fn0`, `Fn1`, ...)
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkFn(*mut ShiikaFn);
+
+unsafe impl Send for SkFn {}
+
+// Must match `packages/core/lib/fn.sk`.
+#[repr(C)]
+#[derive(Debug)]
+struct ShiikaFn {
+    vtable: *const u8,
+    class_obj: *const u8,
+    func: *const u8,
+    captures: *const u8,
+    exit_status: SkInt,
+}
+
+impl SkFn {
+    /// Returns the pointer to the llvm function which implements the lambda.
+    pub fn func(&self) -> *const u8 {
+        unsafe { (*self.0).func }
+    }
+
+    /// Shallow clone
+    pub fn dup(&self) -> SkFn {
+        SkFn(self.0)
+    }
+}
@@ -0,0 +1,26 @@
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkListener(*mut ShiikaListener);
+
+unsafe impl Send for SkListener {}
+
+// Must match `packages/core/lib/listener.sk`.
+#[repr(C)]
+#[derive(Debug)]
+struct ShiikaListener {
+    vtable: *const u8,
+    class_obj: *const u8,
+    state: *mut u8, // Opaque here; the actual type is defined in packages/core/ext
+}
+
+impl SkListener {
+    /// Returns the pointer to the object (eg. to register a finalizer.)
+    pub fn as_ptr(&self) -> *const u8 {
+        self.0 as *const u8
+    }
+
+    /// Returns the value of `@_state`.
+    pub fn state(&self) -> *mut u8 {
+        unsafe { (*self.0).state }
+    }
+}
@@ -0,0 +1,21 @@
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkMutex(*mut ShiikaMutex);
+
+unsafe impl Send for SkMutex {}
+
+// Must match `packages/core/lib/mutex.sk`.
+#[repr(C)]
+#[derive(Debug)]
+struct ShiikaMutex {
+    vtable: *const u8,
+    class_obj: *const u8,
+    state: *mut u8, // Opaque here; the actual type is defined in packages/core/ext
+}
+
+impl SkMutex {
+    /// Returns the value of `@_state`.
+    pub fn state(&self) -> *mut u8 {
+        unsafe { (*self.0).state }
+    }
+}
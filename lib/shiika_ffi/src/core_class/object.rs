@@ -21,6 +21,11 @@ impl SkObject {
     pub fn dup(&self) -> SkObject {
         SkObject(self.0)
     }
+
+    /// Restore an object from the value passed around in the async runtime.
+    pub fn from_raw_u64(value: u64) -> SkObject {
+        SkObject(value as *const ShiikaObject)
+    }
 }
 
 #[repr(C)]
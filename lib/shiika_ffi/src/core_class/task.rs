@@ -0,0 +1,21 @@
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkTask(*mut ShiikaTask);
+
+unsafe impl Send for SkTask {}
+
+// Must match `packages/core/lib/task.sk`.
+#[repr(C)]
+#[derive(Debug)]
+struct ShiikaTask {
+    vtable: *const u8,
+    class_obj: *const u8,
+    state: *mut u8, // Opaque here; the actual type is defined in packages/core/ext
+}
+
+impl SkTask {
+    /// Returns the value of `@_state`.
+    pub fn state(&self) -> *mut u8 {
+        unsafe { (*self.0).state }
+    }
+}
@@ -7,10 +7,14 @@ pub fn compile(args: TokenStream, input: TokenStream) -> TokenStream {
     let method_name = parse_macro_input!(args as syn::LitStr);
     let orig_function_definition = parse_macro_input!(input as syn::ItemFn);
     let orig_function_name = &orig_function_definition.sig.ident;
-    let orig_function_params = &orig_function_definition.sig.inputs;
+    // Iterate rather than interpolating the `Punctuated` as is, because it
+    // may end with a trailing comma
+    let orig_function_params = orig_function_definition.sig.inputs.iter();
 
     // eg. for `fn foo(a: A, b: B)`, create `a, b`
-    let forwarding_args = orig_function_params
+    let forwarding_args = orig_function_definition
+        .sig
+        .inputs
         .iter()
         .filter_map(|arg| match arg {
             syn::FnArg::Typed(pat_type) => {
@@ -46,7 +50,7 @@ pub fn compile(args: TokenStream, input: TokenStream) -> TokenStream {
         //#[allow(improper_ctypes_definitions)]
         pub extern "C" fn #orig_function_name(
             env: &'static mut shiika_ffi::async_::ChiikaEnv,
-            #orig_function_params,
+            #(#orig_function_params,)*
             cont: shiika_ffi::async_::ChiikaCont,
         ) -> shiika_ffi::async_::ContFuture {
             use std::task::Poll;
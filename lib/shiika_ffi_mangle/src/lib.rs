@@ -16,6 +16,7 @@ pub fn mangle_method(method_name: &str) -> String {
         .replace('/', "div_")
         .replace('%', "mod_")
         .replace("==", "eq_")
+        .replace("<=>", "cmp_")
         .replace("<=", "le_")
         .replace(">=", "ge_")
         .replace('<', "lt_")
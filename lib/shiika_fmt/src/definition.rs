@@ -0,0 +1,281 @@
+use crate::expression::{type_name, P_STMT};
+use crate::printer::{begin_line, begin_of, end_line, end_of, indent, max_loc, Printer, Stmt};
+use shiika_ast::{
+    AstMethodSignature, AstTyParam, AstVariance, Definition, EnumCase, Location, Param, Visibility,
+};
+
+impl Printer<'_> {
+    /// Print a definition. Returns the result and the end of the definition
+    /// in the source (if known)
+    pub(crate) fn definition(
+        &mut self,
+        def: &Definition,
+        level: usize,
+    ) -> (String, Option<Location>) {
+        match def {
+            Definition::ClassDefinition {
+                inheritable,
+                name,
+                typarams,
+                supers,
+                defs,
+                visibility,
+                locs,
+            } => {
+                let mut head = visibility_str(visibility).to_string();
+                if *inheritable {
+                    head.push_str("base ");
+                }
+                head.push_str(&format!("class {}{}", name.0, typarams_str(typarams)));
+                if !supers.is_empty() {
+                    let supers = supers.iter().map(type_name).collect::<Vec<_>>();
+                    head.push_str(&format!(" : {}", supers.join(", ")));
+                }
+                let stmts = defs.iter().map(Stmt::Def).collect::<Vec<_>>();
+                let after = end_of(
+                    &supers
+                        .last()
+                        .map(|t| t.locs.clone())
+                        .unwrap_or(locs.clone()),
+                );
+                self.def_body(head, &stmts, after, level)
+            }
+            Definition::ModuleDefinition {
+                name,
+                typarams,
+                defs,
+                visibility,
+                locs,
+            } => {
+                let head = format!(
+                    "{}module {}{}",
+                    visibility_str(visibility),
+                    name.0,
+                    typarams_str(typarams)
+                );
+                let stmts = defs.iter().map(Stmt::Def).collect::<Vec<_>>();
+                self.def_body(head, &stmts, end_of(locs), level)
+            }
+            Definition::EnumDefinition {
+                name,
+                typarams,
+                cases,
+                defs,
+                visibility,
+                locs,
+            } => {
+                let head = format!(
+                    "{}enum {}{}",
+                    visibility_str(visibility),
+                    name.0,
+                    typarams_str(typarams)
+                );
+                let stmts = cases
+                    .iter()
+                    .map(Stmt::Case)
+                    .chain(defs.iter().map(Stmt::Def))
+                    .collect::<Vec<_>>();
+                self.def_body(head, &stmts, end_of(locs), level)
+            }
+            Definition::InstanceMethodDefinition { sig, body_exprs } => {
+                self.method_definition("def ", sig, body_exprs, level)
+            }
+            Definition::ClassMethodDefinition { sig, body_exprs } => {
+                self.method_definition("def self.", sig, body_exprs, level)
+            }
+            Definition::InitializerDefinition(x) => {
+                self.method_definition("def ", &x.sig, &x.body_exprs, level)
+            }
+            Definition::ClassInitializerDefinition(x) => {
+                self.method_definition("def self.", &x.sig, &x.body_exprs, level)
+            }
+            Definition::MethodRequirementDefinition { sig } => (
+                format!("requirement {}", self.signature(sig, level)),
+                signature_end(sig),
+            ),
+            Definition::ConstDefinition {
+                name,
+                expr,
+                visibility,
+                ..
+            } => (
+                format!(
+                    "{}{} = {}",
+                    visibility_str(visibility),
+                    name,
+                    self.expr(expr, level, P_STMT)
+                ),
+                end_of(&expr.locs),
+            ),
+        }
+    }
+
+    fn method_definition(
+        &mut self,
+        keyword: &str,
+        sig: &AstMethodSignature,
+        body_exprs: &[shiika_ast::AstExpression],
+        level: usize,
+    ) -> (String, Option<Location>) {
+        let head = format!(
+            "{}{}{}",
+            visibility_str(&sig.visibility),
+            keyword,
+            self.signature(sig, level)
+        );
+        // Skip the assignments generated from `@a` parameters
+        let n_iparams = sig.params.iter().filter(|p| p.is_iparam).count();
+        let stmts = body_exprs[n_iparams..]
+            .iter()
+            .map(Stmt::Expr)
+            .collect::<Vec<_>>();
+        self.def_body(head, &stmts, signature_end(sig), level)
+    }
+
+    /// Print `head`, `stmts` and `end`
+    fn def_body(
+        &mut self,
+        head: String,
+        stmts: &[Stmt],
+        after: Option<Location>,
+        level: usize,
+    ) -> (String, Option<Location>) {
+        let mut s = head;
+        s.push_str(&self.header_comment(after.as_ref().map(|l| l.line)));
+        s.push('\n');
+        // The header may end with `)`, `>`, etc. which are not in the ast
+        let after = match after {
+            Some(l) if stmts.is_empty() => self.skip_header(&l),
+            _ => after,
+        };
+        let (mut lines, content_end) = self.print_seq(stmts, level + 1, after);
+        let close = content_end.and_then(|l| self.find_keyword(&l, &["end"]));
+        if let Some(close) = &close {
+            self.flush_comments_before_line(&mut lines, level + 1, close.line);
+        }
+        for line in lines {
+            s.push_str(&line);
+            s.push('\n');
+        }
+        s.push_str(&indent(level));
+        s.push_str("end");
+        (s, close)
+    }
+
+    /// `foo<T>(a: Int, b: T = 1) -> Int`
+    fn signature(&mut self, sig: &AstMethodSignature, level: usize) -> String {
+        let mut s = format!("{}{}", sig.name.0, typarams_str(&sig.typarams));
+        if self.is_multiline_params(sig) {
+            // One parameter per line
+            s.push_str("(\n");
+            for p in &sig.params {
+                let mut lines = vec![];
+                if let Some(b) = begin_of(&p.typ.locs) {
+                    self.flush_comments(&mut lines, level + 1, |c| c.location.line < b.line);
+                }
+                for l in lines {
+                    s.push_str(&format!("{}\n", l));
+                }
+                let param = self.param(p, level + 1);
+                let comment = self.header_comment(end_line(&p.typ.locs));
+                s.push_str(&format!("{}{},{}\n", indent(level + 1), param, comment));
+            }
+            s.push_str(&indent(level));
+            s.push(')');
+        } else if !sig.params.is_empty() {
+            let params = sig
+                .params
+                .iter()
+                .map(|p| self.param(p, level))
+                .collect::<Vec<_>>();
+            s.push_str(&format!("({})", params.join(", ")));
+        }
+        if let Some(t) = &sig.ret_typ {
+            s.push_str(&format!(" -> {}", type_name(t)));
+        }
+        s
+    }
+
+    /// Returns true if the parameters are written in multiple lines
+    fn is_multiline_params(&self, sig: &AstMethodSignature) -> bool {
+        let lines = sig
+            .params
+            .iter()
+            .filter_map(|p| begin_line(&p.typ.locs))
+            .collect::<Vec<_>>();
+        match (begin_line(&sig.locs), lines.first()) {
+            (Some(name_line), Some(first)) => {
+                name_line < *first || lines.windows(2).any(|w| w[0] < w[1])
+            }
+            _ => false,
+        }
+    }
+
+    fn param(&mut self, param: &Param, level: usize) -> String {
+        let mut s = format!("{}: {}", param.name, type_name(&param.typ));
+        if let Some(e) = &param.default_expr {
+            s.push_str(&format!(" = {}", self.expr(e, level, P_STMT)));
+        }
+        s
+    }
+
+    pub(crate) fn enum_case(&mut self, case: &EnumCase) -> (String, Option<Location>) {
+        let mut s = case.name.0.clone();
+        if !case.params.is_empty() {
+            let params = case
+                .params
+                .iter()
+                .map(|p| self.param(p, 0))
+                .collect::<Vec<_>>();
+            s.push_str(&format!("({})", params.join(", ")));
+        }
+        let end = case.params.iter().fold(end_of(&case.locs), |acc, p| {
+            max_loc(acc, end_of(&p.typ.locs))
+        });
+        (format!("case {}", s), end)
+    }
+}
+
+/// Returns the end of the method signature in the source
+fn signature_end(sig: &AstMethodSignature) -> Option<Location> {
+    let mut end = end_of(&sig.locs);
+    for p in &sig.params {
+        end = max_loc(end, end_of(&p.typ.locs));
+        if let Some(e) = &p.default_expr {
+            end = max_loc(end, end_of(&e.locs));
+        }
+    }
+    if let Some(t) = &sig.ret_typ {
+        end = max_loc(end, end_of(&t.locs));
+    }
+    end
+}
+
+fn typarams_str(typarams: &[AstTyParam]) -> String {
+    if typarams.is_empty() {
+        return "".to_string();
+    }
+    let typarams = typarams
+        .iter()
+        .map(|t| {
+            let name = match t.variance {
+                AstVariance::Invariant => t.name.clone(),
+                AstVariance::Covariant => format!("out {}", t.name),
+                AstVariance::Contravariant => format!("in {}", t.name),
+            };
+            match &t.upper_bound {
+                Some(bound) => format!("{} : {}", name, type_name(bound)),
+                None => name,
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("<{}>", typarams.join(", "))
+}
+
+/// `private ` or empty
+fn visibility_str(visibility: &Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "",
+        Visibility::Private => "private ",
+    }
+}
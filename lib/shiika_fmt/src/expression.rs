@@ -0,0 +1,898 @@
+use crate::printer::{
+    begin_line, begin_of, end_line, end_of, expr_begin, indent, regex_literal, string_literal,
+    Printer, Stmt,
+};
+use shiika_ast::{
+    AstCallArgs, AstExpression, AstExpressionBody, AstMatchClause, AstMethodCall, AstPattern,
+    BlockParam, LocationSpan, Token, UnresolvedTypeName,
+};
+
+/// Precedence of expressions (larger one binds tighter.) An expression is
+/// wrapped with parentheses when its precedence is lower than required.
+/// (`if`, `match` and `while` are treated as `P_ASSIGN` so that they are
+/// wrapped when used as an operand)
+pub(crate) type Prec = u8;
+pub(crate) const P_STMT: Prec = 0;
+/// Method call without parentheses (`foo 1, 2`)
+const P_CMD: Prec = 1;
+const P_ASSIGN: Prec = 2;
+const P_RANGE: Prec = 3;
+const P_OR: Prec = 4;
+const P_AND: Prec = 5;
+const P_EQ: Prec = 6;
+const P_REL: Prec = 7;
+const P_BITOR: Prec = 8;
+const P_BITAND: Prec = 9;
+const P_SHIFT: Prec = 10;
+const P_ADD: Prec = 11;
+const P_MUL: Prec = 12;
+const P_NEG: Prec = 13;
+const P_NOT: Prec = 14;
+const P_SECONDARY: Prec = 15;
+const P_PRIMARY: Prec = 16;
+
+/// Operators which can be used with `=` (eg. `+=`)
+const ASSIGN_OPS: [&str; 10] = ["+", "-", "*", "/", "%", "<<", ">>", "&", "|", "^"];
+
+/// Returns the precedence of the binary operator and the minimum
+/// precedence of its left and right operands
+fn binary_op(name: &str) -> Option<(Prec, Prec, Prec)> {
+    let x = match name {
+        "==" | "<=>" => (P_EQ, P_REL, P_REL),
+        "<" | ">" | "<=" | ">=" => (P_REL, P_BITOR, P_BITOR),
+        "|" | "^" => (P_BITOR, P_BITOR, P_BITAND),
+        "&" => (P_BITAND, P_BITAND, P_SHIFT),
+        "<<" | ">>" => (P_SHIFT, P_SHIFT, P_ADD),
+        "+" | "-" => (P_ADD, P_ADD, P_MUL),
+        "*" | "/" | "%" => (P_MUL, P_MUL, P_NEG),
+        _ => return None,
+    };
+    Some(x)
+}
+
+/// An item of a list (array literal or method call arguments)
+enum ListItem<'b> {
+    Unnamed(&'b AstExpression),
+    Named(&'b str, &'b AstExpression),
+}
+
+impl ListItem<'_> {
+    fn expr(&self) -> &AstExpression {
+        match self {
+            ListItem::Unnamed(e) | ListItem::Named(_, e) => e,
+        }
+    }
+}
+
+impl Printer<'_> {
+    /// Print `e` wrapping with parentheses if needed
+    pub(crate) fn expr(&mut self, e: &AstExpression, level: usize, min: Prec) -> String {
+        let (s, prec) = self.expr_(e, level, min);
+        if prec < min {
+            format!("({})", s)
+        } else {
+            s
+        }
+    }
+
+    fn expr_(&mut self, e: &AstExpression, level: usize, min: Prec) -> (String, Prec) {
+        match &e.body {
+            AstExpressionBody::LogicalNot { expr } => self.logical_not(e, expr, level),
+            AstExpressionBody::LogicalAnd { left, right } => {
+                let l = self.expr(left, level, P_AND);
+                let r = self.expr(right, level, P_EQ);
+                (format!("{} and {}", l, r), P_AND)
+            }
+            AstExpressionBody::LogicalOr { left, right } => {
+                let l = self.expr(left, level, P_OR);
+                let r = self.expr(right, level, P_AND);
+                (format!("{} or {}", l, r), P_OR)
+            }
+            AstExpressionBody::If {
+                cond_expr,
+                then_exprs,
+                else_exprs,
+            } => self.if_expr(e, cond_expr, then_exprs, else_exprs, level),
+            AstExpressionBody::Match { cond_expr, clauses } => {
+                (self.match_expr(e, cond_expr, clauses, level), P_ASSIGN)
+            }
+            AstExpressionBody::While {
+                cond_expr,
+                body_exprs,
+            } => {
+                let cond = self.expr(cond_expr, level, P_CMD);
+                let comment = self.header_comment(end_line(&cond_expr.locs));
+                let body = self.body(body_exprs, level, end_line(&e.locs));
+                (format!("while {}{}\n{}end", cond, comment, body), P_ASSIGN)
+            }
+            AstExpressionBody::Break => ("break".to_string(), P_SECONDARY),
+            AstExpressionBody::Return { arg } => match arg {
+                Some(arg) => (format!("return {}", self.expr(arg, level, P_ASSIGN)), P_CMD),
+                None => ("return".to_string(), P_SECONDARY),
+            },
+            AstExpressionBody::LVarDecl {
+                name,
+                rhs,
+                readonly,
+            }
+            | AstExpressionBody::IVarDecl {
+                name,
+                rhs,
+                readonly,
+            } => {
+                let kw = if *readonly { "let" } else { "var" };
+                let rhs = self.expr(rhs, level, P_ASSIGN);
+                (format!("{} {} = {}", kw, name, rhs), P_STMT)
+            }
+            AstExpressionBody::LVarAssign { name, rhs }
+            | AstExpressionBody::IVarAssign { name, rhs } => {
+                self.assignment(e, name.clone(), rhs, level)
+            }
+            AstExpressionBody::ConstAssign { names, rhs } => {
+                self.assignment(e, names.join("::"), rhs, level)
+            }
+            AstExpressionBody::MethodCall(x) => self.method_call(e, x, level, min),
+            AstExpressionBody::LambdaInvocation { fn_expr, args } => {
+                let f = self.expr(fn_expr, level, P_STMT);
+                let mut args = args.clone();
+                if args.block.is_none() {
+                    if let Some(AstExpressionBody::LambdaExpr { is_fn: false, .. }) =
+                        args.unnamed.last().map(|x| &x.body)
+                    {
+                        args.block = args.unnamed.pop().map(Box::new);
+                    }
+                }
+                let mut s = format!("({})({})", f, self.call_args(e, &args, level));
+                if let Some(block) = &args.block {
+                    s.push_str(&self.block(block, level, false));
+                }
+                (s, P_PRIMARY)
+            }
+            AstExpressionBody::LambdaExpr {
+                params,
+                exprs,
+                is_fn,
+            } => {
+                if *is_fn {
+                    (self.fn_expr(e, params, exprs, level), P_PRIMARY)
+                } else {
+                    (self.block(e, level, false), P_PRIMARY)
+                }
+            }
+            AstExpressionBody::BareName(s) | AstExpressionBody::IVarRef(s) => {
+                (s.clone(), P_PRIMARY)
+            }
+            AstExpressionBody::CapitalizedName(name) => (name.0.join("::"), P_PRIMARY),
+            AstExpressionBody::SpecializeExpression { base_name, args } => {
+                let args = args
+                    .iter()
+                    .map(|x| self.expr(x, level, P_PRIMARY))
+                    .collect::<Vec<_>>();
+                (
+                    format!("{}<{}>", base_name.0.join("::"), args.join(", ")),
+                    P_PRIMARY,
+                )
+            }
+            AstExpressionBody::PseudoVariable(token) => {
+                let s = match token {
+                    Token::KwSelf => "self",
+                    Token::KwTrue => "true",
+                    Token::KwFalse => "false",
+                    _ => panic!("[BUG] unexpected pseudo variable: {:?}", token),
+                };
+                (s.to_string(), P_PRIMARY)
+            }
+            AstExpressionBody::ArrayLiteral(exprs) => {
+                let items = exprs.iter().map(ListItem::Unnamed).collect::<Vec<_>>();
+                let multiline = self.is_multiline_list(e, &items);
+                let s = self.list(&items, level, P_CMD, multiline);
+                if multiline {
+                    (format!("[\n{}{}]", s, indent(level)), P_PRIMARY)
+                } else {
+                    (format!("[{}]", s), P_PRIMARY)
+                }
+            }
+            AstExpressionBody::RangeLiteral {
+                first,
+                last,
+                exclusive,
+            } => {
+                let dots = if *exclusive { "..." } else { ".." };
+                let first = self.expr(first, level, P_OR);
+                let last = self.expr(last, level, P_OR);
+                (format!("{}{}{}", first, dots, last), P_RANGE)
+            }
+            // Number literals are printed as written (eg. `1.50`)
+            AstExpressionBody::FloatLiteral { value } => (
+                self.literal_src(e)
+                    .unwrap_or_else(|| format!("{:?}", value)),
+                P_PRIMARY,
+            ),
+            AstExpressionBody::DecimalLiteral { value } => (
+                self.literal_src(e).unwrap_or_else(|| value.to_string()),
+                P_PRIMARY,
+            ),
+            AstExpressionBody::StringLiteral { content } => {
+                let s = self
+                    .slice(&e.locs)
+                    .filter(|s| s.len() >= 2 && s.starts_with('"') && s.ends_with('"'))
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| string_literal(content));
+                (s, P_PRIMARY)
+            }
+            AstExpressionBody::RegexLiteral { source } => (regex_literal(source), P_PRIMARY),
+        }
+    }
+
+    fn literal_src(&self, e: &AstExpression) -> Option<String> {
+        self.slice(&e.locs)
+            .filter(|s| s.starts_with(|c: char| c.is_ascii_digit()))
+            .map(|s| s.to_string())
+    }
+
+    /// `not x` or `a != b` (which is parsed as `not (a == b)`)
+    fn logical_not(
+        &mut self,
+        e: &AstExpression,
+        inner: &AstExpression,
+        level: usize,
+    ) -> (String, Prec) {
+        if e.locs == inner.locs {
+            if let AstExpressionBody::MethodCall(x) = &inner.body {
+                if let (Some(recv), "==", [arg]) = (
+                    &x.receiver_expr,
+                    x.method_name.0.as_str(),
+                    x.args.unnamed.as_slice(),
+                ) {
+                    let l = self.expr(recv, level, P_REL);
+                    let r = self.expr(arg, level, P_REL);
+                    return (format!("{} != {}", l, r), P_EQ);
+                }
+            }
+        }
+        let s = self.expr(inner, level, P_SECONDARY);
+        (format!("not {}", s), P_NOT)
+    }
+
+    /// `lhs = rhs` or `lhs op= rhs`
+    fn assignment(
+        &mut self,
+        e: &AstExpression,
+        lhs: String,
+        rhs: &AstExpression,
+        level: usize,
+    ) -> (String, Prec) {
+        if let Some((op, arg)) = op_assign(e, rhs) {
+            let arg = self.expr(arg, level, P_ASSIGN);
+            return (format!("{} {}= {}", lhs, op, arg), P_ASSIGN);
+        }
+        let rhs = self.expr(rhs, level, P_ASSIGN);
+        (format!("{} = {}", lhs, rhs), P_ASSIGN)
+    }
+
+    fn method_call(
+        &mut self,
+        e: &AstExpression,
+        x: &AstMethodCall,
+        level: usize,
+        min: Prec,
+    ) -> (String, Prec) {
+        let name = x.method_name.0.as_str();
+        let args = &x.args;
+        match &x.receiver_expr {
+            Some(recv) if x.type_args.is_empty() && !args.has_block() && args.named.is_empty() => {
+                match args.unnamed.as_slice() {
+                    [] if name == "-@" && begins_before(e, recv) => {
+                        let s = self.expr(recv, level, P_NOT);
+                        return (format!("-{}", s), P_NEG);
+                    }
+                    [_] if name == "+" => {
+                        if let Some(s) = self.interpolated_string(e) {
+                            return (s, P_PRIMARY);
+                        }
+                    }
+                    _ => (),
+                }
+                match (name, args.unnamed.as_slice()) {
+                    ("[]", [idx]) => {
+                        let r = self.expr(recv, level, P_PRIMARY);
+                        let i = self.expr(idx, level, P_ASSIGN);
+                        return (format!("{}[{}]", r, i), P_PRIMARY);
+                    }
+                    ("[]=", [idx, value]) => {
+                        let r = self.expr(recv, level, P_PRIMARY);
+                        let i = self.expr(idx, level, P_ASSIGN);
+                        return self.assignment(e, format!("{}[{}]", r, i), value, level);
+                    }
+                    (_, [value]) if is_setter(name) => {
+                        let r = self.expr(recv, level, P_PRIMARY);
+                        let lhs = format!("{}.{}", r, &name[..name.len() - 1]);
+                        return self.assignment(e, lhs, value, level);
+                    }
+                    (_, [arg]) => {
+                        if let Some((prec, lmin, rmin)) = binary_op(name) {
+                            let l = self.expr(recv, level, lmin);
+                            let r = self.expr(arg, level, rmin);
+                            return (format!("{} {} {}", l, name, r), prec);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+        self.general_call(e, x, level, min)
+    }
+
+    /// `foo(x)`, `a.foo x`, `a.foo{|x| ...}`, etc.
+    fn general_call(
+        &mut self,
+        e: &AstExpression,
+        x: &AstMethodCall,
+        level: usize,
+        min: Prec,
+    ) -> (String, Prec) {
+        let mut s = String::new();
+        if let Some(recv) = &x.receiver_expr {
+            s.push_str(&self.expr(recv, level, P_PRIMARY));
+            if self.dot_on_new_line(e, recv) {
+                s.push('\n');
+                s.push_str(&indent(level + 1));
+            }
+            s.push('.');
+        }
+        s.push_str(&x.method_name.0);
+        if !x.type_args.is_empty() {
+            let tyargs = x
+                .type_args
+                .iter()
+                .map(|t| self.expr(t, level, P_PRIMARY))
+                .collect::<Vec<_>>();
+            s.push_str(&format!("<{}>", tyargs.join(", ")));
+        }
+        let args = &x.args;
+        let has_args = !args.unnamed.is_empty() || !args.named.is_empty();
+        let command = has_args
+            && min <= P_CMD
+            && !self.has_paren(e, x)
+            && !self.is_multiline_list(e, &call_arg_items(args));
+        if command {
+            s.push(' ');
+            s.push_str(&self.call_args(e, args, level));
+        } else if has_args {
+            s.push_str(&format!("({})", self.call_args(e, args, level)));
+        } else if let Some(block) = &args.block {
+            // `foo{ ... }` is not a method call
+            if x.receiver_expr.is_none() && !self.is_do_block(block) {
+                s.push_str("()");
+            }
+        } else if !x.may_have_paren_wo_args {
+            s.push_str("()");
+        }
+        if let Some(block) = &args.block {
+            s.push_str(&self.block(block, level, command));
+        }
+        (s, if command { P_CMD } else { P_PRIMARY })
+    }
+
+    /// Returns true if the arguments of the method call is enclosed in
+    /// parentheses in the source
+    fn has_paren(&self, e: &AstExpression, x: &AstMethodCall) -> bool {
+        if !x.type_args.is_empty() {
+            return true;
+        }
+        let Some(mut rest) = begin_of(&e.locs).and_then(|l| self.src.get(l.pos..)) else {
+            return true;
+        };
+        if x.receiver_expr.is_some() {
+            // `e.locs` begins with the `.`
+            let Some(r) = rest.strip_prefix('.') else {
+                return true;
+            };
+            rest = r.trim_start();
+        }
+        match rest.strip_prefix(x.method_name.0.as_str()) {
+            Some(r) => r.starts_with('('),
+            None => true,
+        }
+    }
+
+    /// Returns true if there is a newline before `.` of the method call
+    fn dot_on_new_line(&self, e: &AstExpression, recv: &AstExpression) -> bool {
+        match (end_of(&recv.locs), begin_of(&e.locs)) {
+            (Some(a), Some(b)) if a.pos <= b.pos => self.src[a.pos..b.pos].contains('\n'),
+            _ => false,
+        }
+    }
+
+    fn call_args(&mut self, e: &AstExpression, args: &AstCallArgs, level: usize) -> String {
+        let items = call_arg_items(args);
+        if self.is_multiline_list(e, &items) {
+            format!(
+                "\n{}{}",
+                self.list(&items, level, P_ASSIGN, true),
+                indent(level)
+            )
+        } else {
+            self.list(&items, level, P_ASSIGN, false)
+        }
+    }
+
+    /// Returns true if the items are written in multiple lines in the source
+    fn is_multiline_list(&self, e: &AstExpression, items: &[ListItem]) -> bool {
+        let Some(first) = items.first().and_then(|x| expr_begin(x.expr())) else {
+            return false;
+        };
+        if begin_of(&e.locs).is_some_and(|b| b.line < first.line) {
+            return true;
+        }
+        items.windows(2).any(
+            |w| match (end_of(&w[0].expr().locs), expr_begin(w[1].expr())) {
+                (Some(a), Some(b)) => a.line < b.line,
+                _ => false,
+            },
+        )
+    }
+
+    /// Print the items separated with `, `, or one item per line (with a
+    /// trailing comma) if `multiline` is true
+    fn list(&mut self, items: &[ListItem], level: usize, min: Prec, multiline: bool) -> String {
+        let mut lines = vec![];
+        for item in items {
+            let l = if multiline { level + 1 } else { level };
+            if multiline {
+                if let Some(begin) = expr_begin(item.expr()) {
+                    self.flush_comments(&mut lines, l, |c| c.location.pos < begin.pos);
+                }
+            }
+            let mut s = match item {
+                ListItem::Unnamed(x) => self.expr(x, l, min),
+                ListItem::Named(name, x) => format!("{}: {}", name, self.expr(x, l, P_ASSIGN)),
+            };
+            if multiline {
+                s = format!("{}{},", indent(l), s);
+                if let Some(c) =
+                    end_line(&item.expr().locs).and_then(|line| self.take_trailing_comment(line))
+                {
+                    s.push_str("  ");
+                    s.push_str(&c.text);
+                }
+            }
+            lines.push(s);
+        }
+        if multiline {
+            lines.into_iter().map(|l| l + "\n").collect()
+        } else {
+            lines.join(", ")
+        }
+    }
+
+    /// Returns the interpolated string literal if `e` is made from it (eg.
+    /// `"a#{b}c"` is parsed as `"a" + b.to_s + "c"`)
+    fn interpolated_string(&self, e: &AstExpression) -> Option<String> {
+        let AstExpressionBody::MethodCall(x) = &e.body else {
+            return None;
+        };
+        // The last part of an interpolated string starts with `}` (which is
+        // not included in the location)
+        let last = self.slice(&x.args.unnamed.first()?.locs)?;
+        if !(last.ends_with('"') && (last.len() == 1 || !last.starts_with('"'))) {
+            return None;
+        }
+        let mut cur = x.receiver_expr.as_deref()?;
+        loop {
+            match &cur.body {
+                AstExpressionBody::MethodCall(x) if x.method_name.0 == "+" => {
+                    cur = x.receiver_expr.as_deref()?;
+                }
+                AstExpressionBody::StringLiteral { .. } => break,
+                _ => return None,
+            }
+        }
+        let begin = begin_of(&cur.locs)?;
+        let end = end_of(&e.locs)?;
+        let s = self.src.get(begin.pos..end.pos)?;
+        s.starts_with('"').then(|| s.to_string())
+    }
+
+    fn is_do_block(&self, block: &AstExpression) -> bool {
+        begin_of(&block.locs).is_some_and(|b| self.keyword_at(b.pos, "do"))
+    }
+
+    /// Print a block (`{|x| ...}` or `do |x| ... end`.) The result includes
+    /// the leading space if needed
+    fn block(&mut self, block: &AstExpression, level: usize, force_do: bool) -> String {
+        let AstExpressionBody::LambdaExpr { params, exprs, .. } = &block.body else {
+            panic!("[BUG] block is not a lambda: {:?}", block);
+        };
+        let params = if params.is_empty() {
+            "".to_string()
+        } else {
+            format!("|{}|", block_params(params))
+        };
+        if force_do || self.is_do_block(block) {
+            let params = if params.is_empty() {
+                params
+            } else {
+                format!(" {}", params)
+            };
+            if let (true, [x]) = (is_single_line(&block.locs), exprs.as_slice()) {
+                return format!(" do{} {} end", params, self.expr(x, level, P_STMT));
+            }
+            let comment = self.header_comment(begin_line(&block.locs));
+            let body = self.body(exprs, level, end_line(&block.locs));
+            return format!(" do{}{}\n{}end", params, comment, body);
+        }
+        if is_single_line(&block.locs) && exprs.len() <= 1 {
+            match exprs.first() {
+                Some(x) => {
+                    let body = self.expr(x, level, P_STMT);
+                    if params.is_empty() {
+                        format!("{{ {} }}", body)
+                    } else {
+                        format!("{{{} {}}}", params, body)
+                    }
+                }
+                None => format!("{{{}}}", params),
+            }
+        } else {
+            let comment = self.header_comment(begin_line(&block.locs));
+            let body = self.body(exprs, level, end_line(&block.locs));
+            format!("{{{}{}\n{}}}", params, comment, body)
+        }
+    }
+
+    /// `fn(x){ ... }`
+    fn fn_expr(
+        &mut self,
+        e: &AstExpression,
+        params: &[BlockParam],
+        exprs: &[AstExpression],
+        level: usize,
+    ) -> String {
+        let head = if params.is_empty() {
+            "fn".to_string()
+        } else {
+            format!("fn({})", block_params(params))
+        };
+        if is_single_line(&e.locs) && exprs.len() <= 1 {
+            match exprs.first() {
+                Some(x) => format!("{}{{ {} }}", head, self.expr(x, level, P_STMT)),
+                None => format!("{}{{}}", head),
+            }
+        } else {
+            let comment = self.header_comment(begin_line(&e.locs));
+            let body = self.body(exprs, level, end_line(&e.locs));
+            format!("{}{{{}\n{}}}", head, comment, body)
+        }
+    }
+
+    /// Print the statements indented by one level. `close_line` is the line
+    /// of the closing keyword (`end`, `}`, etc.); comments before it are
+    /// printed in the body. The result ends with a newline and the
+    /// indentation of `level`.
+    pub(crate) fn body(
+        &mut self,
+        exprs: &[AstExpression],
+        level: usize,
+        close_line: Option<usize>,
+    ) -> String {
+        let stmts = exprs.iter().map(Stmt::Expr).collect::<Vec<_>>();
+        let (mut lines, _) = self.print_seq(&stmts, level + 1, None);
+        if let Some(line) = close_line {
+            self.flush_comments_before_line(&mut lines, level + 1, line);
+        }
+        lines
+            .into_iter()
+            .map(|l| l + "\n")
+            .chain(std::iter::once(indent(level)))
+            .collect()
+    }
+
+    fn if_expr(
+        &mut self,
+        e: &AstExpression,
+        cond: &AstExpression,
+        then_exprs: &[AstExpression],
+        else_exprs: &Option<Vec<AstExpression>>,
+        level: usize,
+    ) -> (String, Prec) {
+        // `foo if bar`
+        if let ([then], None) = (then_exprs, else_exprs) {
+            if expr_begin(then).map(|l| l.pos) == begin_of(&e.locs).map(|l| l.pos) {
+                let unless = match (end_of(&then.locs), expr_begin(cond)) {
+                    (Some(a), Some(b)) if a.pos <= b.pos => {
+                        self.src[a.pos..b.pos].trim() == "unless"
+                    }
+                    _ => false,
+                };
+                let body = self.expr(then, level, P_CMD);
+                let (kw, cond) = if unless {
+                    ("unless", negated(cond))
+                } else {
+                    ("if", cond)
+                };
+                let cond = self.expr(cond, level, P_CMD);
+                return (format!("{} {} {}", body, kw, cond), P_STMT);
+            }
+        }
+
+        let unless = else_exprs.is_none()
+            && begin_of(&e.locs).is_some_and(|b| self.keyword_at(b.pos, "unless"));
+        let (kw, cond) = if unless {
+            ("unless", negated(cond))
+        } else {
+            ("if", cond)
+        };
+        let elsif = self.elsif(e, else_exprs);
+
+        // `if a then b else c end`
+        if is_single_line(&e.locs) && then_exprs.len() == 1 && elsif.is_none() {
+            let cond = self.expr(cond, level, P_CMD);
+            let then = self.expr(&then_exprs[0], level, P_STMT);
+            match else_exprs.as_deref() {
+                None => return (format!("{} {} then {} end", kw, cond, then), P_ASSIGN),
+                Some([x]) => {
+                    let x = self.expr(x, level, P_STMT);
+                    return (
+                        format!("if {} then {} else {} end", cond, then, x),
+                        P_ASSIGN,
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let mut s = format!("{} {}", kw, self.expr(cond, level, P_CMD));
+        s.push_str(&self.header_comment(end_line(&cond.locs)));
+        s.push('\n');
+        let mut then_exprs = then_exprs;
+        let mut then_cond = cond;
+        let mut else_exprs = else_exprs;
+        let mut elsif = elsif;
+        let mut close;
+        loop {
+            close = self.clause_end(then_exprs, then_cond, &["elsif", "else", "end"]);
+            s.push_str(&self.body(then_exprs, level, close));
+            let Some(next) = elsif else { break };
+            let AstExpressionBody::If {
+                cond_expr,
+                then_exprs: t,
+                else_exprs: el,
+            } = &next.body
+            else {
+                unreachable!()
+            };
+            s.push_str(&format!("elsif {}", self.expr(cond_expr, level, P_CMD)));
+            s.push_str(&self.header_comment(end_line(&cond_expr.locs)));
+            s.push('\n');
+            then_exprs = t;
+            then_cond = cond_expr;
+            else_exprs = el;
+            elsif = self.elsif(next, el);
+        }
+        if let Some(exprs) = else_exprs {
+            s.push_str("else");
+            s.push_str(&self.header_comment(close));
+            s.push('\n');
+            s.push_str(&self.body(exprs, level, end_line(&e.locs)));
+        }
+        s.push_str("end");
+        (s, P_ASSIGN)
+    }
+
+    /// Returns the inner `if` if `else_exprs` is from `elsif`
+    fn elsif<'e>(
+        &self,
+        e: &AstExpression,
+        else_exprs: &'e Option<Vec<AstExpression>>,
+    ) -> Option<&'e AstExpression> {
+        match else_exprs.as_deref() {
+            Some([x]) if matches!(x.body, AstExpressionBody::If { .. }) => {
+                let b = begin_of(&e.locs)?;
+                (begin_of(&x.locs)? == b && !self.keyword_at(b.pos, "unless")).then_some(x)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the line of the keyword which ends the clause
+    fn clause_end(
+        &self,
+        exprs: &[AstExpression],
+        head: &AstExpression,
+        keywords: &[&str],
+    ) -> Option<usize> {
+        let last = exprs.last().unwrap_or(head);
+        let after = end_of(&last.locs)?;
+        self.find_keyword(&after, keywords).map(|l| l.line)
+    }
+
+    fn match_expr(
+        &mut self,
+        e: &AstExpression,
+        cond: &AstExpression,
+        clauses: &[AstMatchClause],
+        level: usize,
+    ) -> String {
+        let mut s = format!("match {}", self.expr(cond, level, P_CMD));
+        s.push_str(&self.header_comment(end_line(&cond.locs)));
+        s.push('\n');
+        for (i, clause) in clauses.iter().enumerate() {
+            let clause_begin = begin_of(&clause.locs);
+            if let Some(b) = &clause_begin {
+                let mut lines = vec![];
+                self.flush_comments(&mut lines, level, |c| c.location.pos < b.pos);
+                for l in lines {
+                    s.push_str(&format!("{}\n", l));
+                }
+            }
+            let is_else = matches!(&clause.pattern, AstPattern::VariablePattern(n) if n == "_")
+                && clause_begin.is_some_and(|b| self.keyword_at(b.pos, "else"));
+            let head = if is_else {
+                "else".to_string()
+            } else if let Some(guard) = &clause.guard {
+                let guard = self.expr(guard, level, P_ASSIGN);
+                format!("when {} if {}", pattern(&clause.pattern), guard)
+            } else {
+                format!("when {}", pattern(&clause.pattern))
+            };
+            let inline = match clause.body.as_slice() {
+                [x] => expr_begin(x).map(|l| l.line) == begin_line(&clause.locs),
+                _ => false,
+            };
+            if inline {
+                let body = self.expr(&clause.body[0], level, P_STMT);
+                let sep = if is_else { " " } else { " then " };
+                s.push_str(&format!("{}{}{}", indent(level), head, sep));
+                s.push_str(&body);
+                if let Some(c) =
+                    end_line(&clause.body[0].locs).and_then(|l| self.take_trailing_comment(l))
+                {
+                    s.push_str("  ");
+                    s.push_str(&c.text);
+                }
+                s.push('\n');
+            } else {
+                let close = match clauses.get(i + 1) {
+                    Some(next) => begin_line(&next.locs),
+                    None => end_line(&e.locs),
+                };
+                let comment = self.header_comment(begin_line(&clause.locs));
+                s.push_str(&format!("{}{}{}\n", indent(level), head, comment));
+                s.push_str(&self.body(&clause.body, level, close));
+                // Remove the indentation added by `body`
+                s.truncate(s.len() - indent(level).len());
+            }
+        }
+        if let Some(line) = end_line(&e.locs) {
+            let mut lines = vec![];
+            self.flush_comments_before_line(&mut lines, level + 1, line);
+            for l in lines {
+                s.push_str(&format!("{}\n", l));
+            }
+        }
+        s.push_str(&indent(level));
+        s.push_str("end");
+        s
+    }
+}
+
+fn call_arg_items(args: &AstCallArgs) -> Vec<ListItem<'_>> {
+    args.unnamed
+        .iter()
+        .map(ListItem::Unnamed)
+        .chain(args.named.iter().map(|(n, x)| ListItem::Named(n, x)))
+        .collect()
+}
+
+/// Returns the pattern of `when`
+pub(crate) fn pattern(pat: &AstPattern) -> String {
+    match pat {
+        AstPattern::ExtractorPattern { names, params, .. } => {
+            if params.is_empty() {
+                names.join("::")
+            } else {
+                let params = params.iter().map(pattern).collect::<Vec<_>>();
+                format!("{}({})", names.join("::"), params.join(", "))
+            }
+        }
+        AstPattern::VariablePattern(name) => name.clone(),
+        AstPattern::BooleanLiteralPattern(b) => b.to_string(),
+        AstPattern::IntegerLiteralPattern(i) => i.to_string(),
+        AstPattern::FloatLiteralPattern(f) => format!("{:?}", f),
+        AstPattern::StringLiteralPattern(s) => string_literal(s),
+        AstPattern::RangePattern {
+            first,
+            last,
+            exclusive,
+        } => {
+            let dots = if *exclusive { "..." } else { ".." };
+            format!("{}{}{}", pattern(first), dots, pattern(last))
+        }
+        AstPattern::OrPattern(pats) => {
+            let pats = pats.iter().map(pattern).collect::<Vec<_>>();
+            pats.join(" | ")
+        }
+        AstPattern::ArrayPattern {
+            before,
+            rest,
+            after,
+        } => {
+            let mut items = before.iter().map(pattern).collect::<Vec<_>>();
+            if let Some(name) = rest {
+                items.push(format!("*{}", name));
+            }
+            items.extend(after.iter().map(pattern));
+            format!("[{}]", items.join(", "))
+        }
+        AstPattern::BindingPattern { name, pattern: pat } => {
+            format!("{} @ {}", name, pattern(pat))
+        }
+    }
+}
+
+pub(crate) fn type_name(t: &UnresolvedTypeName) -> String {
+    let name = t.names.join("::");
+    if t.args.is_empty() {
+        name
+    } else {
+        let args = t.args.iter().map(type_name).collect::<Vec<_>>();
+        format!("{}<{}>", name, args.join(", "))
+    }
+}
+
+fn block_params(params: &[BlockParam]) -> String {
+    params
+        .iter()
+        .map(|p| match &p.opt_typ {
+            Some(t) => format!("{}: {}", p.name, type_name(t)),
+            None => p.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the expression negated by `unless` (or `!=`)
+fn negated(cond: &AstExpression) -> &AstExpression {
+    match &cond.body {
+        AstExpressionBody::LogicalNot { expr } if expr.locs == cond.locs => expr,
+        _ => cond,
+    }
+}
+
+/// If `e` is `lhs op= arg`, returns `op` and `arg`. The parser expands it
+/// to `lhs = lhs op arg` where the inner `lhs` has the same location.
+fn op_assign<'e>(
+    e: &AstExpression,
+    rhs: &'e AstExpression,
+) -> Option<(&'e str, &'e AstExpression)> {
+    let AstExpressionBody::MethodCall(x) = &rhs.body else {
+        return None;
+    };
+    let name = x.method_name.0.as_str();
+    if !ASSIGN_OPS.contains(&name) || x.args.unnamed.len() != 1 {
+        return None;
+    }
+    let recv = x.receiver_expr.as_deref()?;
+    let begin = begin_of(&e.locs)?;
+    (begin_of(&recv.locs)? == begin).then(|| (name, &x.args.unnamed[0]))
+}
+
+/// Returns true if `e` begins before `child` (eg. unary minus)
+fn begins_before(e: &AstExpression, child: &AstExpression) -> bool {
+    match (begin_of(&e.locs), expr_begin(child)) {
+        (Some(a), Some(b)) => a.pos < b.pos,
+        _ => false,
+    }
+}
+
+fn is_setter(name: &str) -> bool {
+    name.ends_with('=')
+        && name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name != "=="
+}
+
+fn is_single_line(locs: &LocationSpan) -> bool {
+    matches!(locs, LocationSpan::Just { begin, end, .. } if begin.line == end.line)
+}
@@ -0,0 +1,21 @@
+//! Code formatter of Shiika (`shiika fmt`.)
+//!
+//! The source is parsed into `shiika_ast::Program` and printed again in the
+//! canonical style. Comments are not in the ast; they are taken from the
+//! lexer and put back before the nearest statement.
+//!
+//! Some syntax sugars (eg. `unless`, `elsif`, `+=`) are expanded by the
+//! parser. They are restored by looking at the locations and the source
+//! text so that the meaning of the program does not change.
+mod definition;
+mod expression;
+mod printer;
+pub use shiika_parser::Error;
+use shiika_parser::{Parser, SourceFile};
+
+/// Returns the formatted source code of `file`
+pub fn format(file: &SourceFile) -> Result<String, Error> {
+    let (program, comments) = Parser::parse_with_comments(file)?;
+    let mut printer = printer::Printer::new(&file.content, comments);
+    Ok(printer.print_program(&program))
+}
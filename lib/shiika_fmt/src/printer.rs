@@ -0,0 +1,360 @@
+use shiika_ast::{
+    AstExpression, AstExpressionBody, Definition, EnumCase, Import, Location, LocationSpan,
+    Program, TopLevelItem,
+};
+use shiika_parser::lexer::Comment;
+
+pub(crate) const INDENT: &str = "  ";
+
+pub(crate) fn indent(level: usize) -> String {
+    INDENT.repeat(level)
+}
+
+pub(crate) struct Printer<'a> {
+    /// The source text
+    pub(crate) src: &'a str,
+    /// Lines of `src`
+    lines: Vec<&'a str>,
+    /// Byte offset of the beginning of each line
+    line_starts: Vec<usize>,
+    /// Comments in the source (in the order of appearance)
+    comments: Vec<Comment>,
+    /// Index of the first comment not printed yet
+    next_comment: usize,
+}
+
+/// A statement in a sequence (toplevel, class body, method body, etc.)
+pub(crate) enum Stmt<'b> {
+    Import(&'b Import),
+    Def(&'b Definition),
+    Case(&'b EnumCase),
+    Expr(&'b AstExpression),
+}
+
+impl<'a> Printer<'a> {
+    pub(crate) fn new(src: &'a str, comments: Vec<Comment>) -> Printer<'a> {
+        let mut line_starts = vec![0];
+        line_starts.extend(src.match_indices('\n').map(|(i, _)| i + 1));
+        Printer {
+            src,
+            lines: src.split('\n').collect(),
+            line_starts,
+            comments,
+            next_comment: 0,
+        }
+    }
+
+    pub(crate) fn print_program(&mut self, program: &Program) -> String {
+        let mut stmts = program
+            .imports
+            .iter()
+            .map(Stmt::Import)
+            .chain(program.toplevel_items.iter().map(|item| match item {
+                TopLevelItem::Def(x) => Stmt::Def(x),
+                TopLevelItem::Expr(x) => Stmt::Expr(x),
+            }))
+            .collect::<Vec<_>>();
+        stmts.sort_by_key(|stmt| self.stmt_begin(stmt).map(|l| l.pos).unwrap_or(0));
+        let (mut lines, _) = self.print_seq(&stmts, 0, None);
+        self.flush_comments(&mut lines, 0, |_| true);
+        if lines.is_empty() {
+            String::new()
+        } else {
+            lines.join("\n") + "\n"
+        }
+    }
+
+    /// Print the statements at the indentation `level`. Returns the lines
+    /// and the end of the last statement (or `after` if `stmts` is empty.)
+    pub(crate) fn print_seq(
+        &mut self,
+        stmts: &[Stmt],
+        level: usize,
+        after: Option<Location>,
+    ) -> (Vec<String>, Option<Location>) {
+        let mut lines = vec![];
+        let mut end = after;
+        for stmt in stmts {
+            if let Some(begin) = self.stmt_begin(stmt) {
+                self.flush_comments(&mut lines, level, |c| c.location.pos < begin.pos);
+                let new_line = end.as_ref().is_none_or(|l| l.line < begin.line);
+                if !lines.is_empty() && new_line && self.blank_line_before(begin.line) {
+                    lines.push("".to_string());
+                }
+            }
+            let (s, stmt_end) = self.print_stmt(stmt, level);
+            let mut line = format!("{}{}", indent(level), s);
+            if let Some(l) = &stmt_end {
+                if let Some(c) = self.take_trailing_comment(l.line) {
+                    line.push_str("  ");
+                    line.push_str(&c.text);
+                }
+            }
+            lines.push(line);
+            end = stmt_end;
+        }
+        (lines, end)
+    }
+
+    /// Print the comments which satisfy `pred` as own-line comments
+    pub(crate) fn flush_comments(
+        &mut self,
+        lines: &mut Vec<String>,
+        level: usize,
+        pred: impl Fn(&Comment) -> bool,
+    ) {
+        while let Some(c) = self.comments.get(self.next_comment) {
+            if !pred(c) {
+                break;
+            }
+            if !lines.is_empty() && self.blank_line_before(c.location.line) {
+                lines.push("".to_string());
+            }
+            lines.push(format!("{}{}", indent(level), c.text));
+            self.next_comment += 1;
+        }
+    }
+
+    /// Print the comments before the line `line` (eg. the line of `end`)
+    pub(crate) fn flush_comments_before_line(
+        &mut self,
+        lines: &mut Vec<String>,
+        level: usize,
+        line: usize,
+    ) {
+        self.flush_comments(lines, level, |c| c.location.line < line);
+    }
+
+    /// Take the next comment if it is on the line `line`
+    pub(crate) fn take_trailing_comment(&mut self, line: usize) -> Option<Comment> {
+        let c = self.comments.get(self.next_comment)?;
+        if c.location.line != line {
+            return None;
+        }
+        self.next_comment += 1;
+        Some(c.clone())
+    }
+
+    /// Returns the comment at the end of the line `line` (eg. the line of
+    /// `if`) with the leading spaces, or an empty string
+    pub(crate) fn header_comment(&mut self, line: Option<usize>) -> String {
+        line.and_then(|l| self.take_trailing_comment(l))
+            .map(|c| format!("  {}", c.text))
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the line before `line` is a blank line
+    fn blank_line_before(&self, line: usize) -> bool {
+        line > 0
+            && self
+                .lines
+                .get(line - 1)
+                .is_some_and(|l| l.trim().is_empty())
+    }
+
+    fn stmt_begin(&self, stmt: &Stmt) -> Option<Location> {
+        match stmt {
+            Stmt::Import(x) => match &x.kind {
+                shiika_ast::ImportKind::Names(names) => begin_of(&names.first()?.1),
+                _ => begin_of(&x.locs),
+            },
+            Stmt::Def(x) => begin_of(def_locs(x)),
+            Stmt::Case(x) => begin_of(&x.locs),
+            Stmt::Expr(x) => expr_begin(x),
+        }
+    }
+
+    fn print_stmt(&mut self, stmt: &Stmt, level: usize) -> (String, Option<Location>) {
+        match stmt {
+            Stmt::Import(x) => (self.import(x), end_of(&x.locs)),
+            Stmt::Def(x) => self.definition(x, level),
+            Stmt::Case(x) => self.enum_case(x),
+            Stmt::Expr(x) => (
+                self.expr(x, level, crate::expression::P_STMT),
+                end_of(&x.locs),
+            ),
+        }
+    }
+
+    /// Returns the source text of `locs`
+    pub(crate) fn slice(&self, locs: &LocationSpan) -> Option<&'a str> {
+        match locs {
+            LocationSpan::Just { begin, end, .. } => self.src.get(begin.pos..end.pos),
+            LocationSpan::Empty => None,
+        }
+    }
+
+    /// Returns true if the source text at `pos` starts with the keyword `kw`
+    pub(crate) fn keyword_at(&self, pos: usize, kw: &str) -> bool {
+        self.src
+            .get(pos..)
+            .is_some_and(|s| starts_with_keyword(s, kw))
+    }
+
+    /// Find the first keyword in `keywords` after `after`, skipping spaces,
+    /// `;`, newlines and comments. Returns the location just after the
+    /// keyword.
+    pub(crate) fn find_keyword(&self, after: &Location, keywords: &[&str]) -> Option<Location> {
+        let mut line = after.line;
+        let mut pos = after.pos;
+        loop {
+            let line_end = self.line_starts.get(line + 1).map(|x| x - 1);
+            let text = self.src.get(pos..line_end.unwrap_or(self.src.len()))?;
+            let rest = text.trim_start_matches(|c: char| c.is_whitespace() || c == ';');
+            if !rest.is_empty() && !rest.starts_with('#') {
+                let kw = keywords.iter().find(|kw| starts_with_keyword(rest, kw))?;
+                let kw_pos = pos + (text.len() - rest.len());
+                return Some(Location::new(line, 0, kw_pos + kw.len()));
+            }
+            line += 1;
+            pos = *self.line_starts.get(line)?;
+        }
+    }
+
+    /// Returns the location after the first `;` in the line of `loc`, or the
+    /// beginning of the next line
+    pub(crate) fn skip_header(&self, loc: &Location) -> Option<Location> {
+        let line_end = self
+            .line_starts
+            .get(loc.line + 1)
+            .copied()
+            .unwrap_or(self.src.len());
+        match self.src[loc.pos..line_end].find(';') {
+            Some(i) => Some(Location::new(loc.line, 0, loc.pos + i + 1)),
+            None => {
+                let pos = *self.line_starts.get(loc.line + 1)?;
+                Some(Location::new(loc.line + 1, 0, pos))
+            }
+        }
+    }
+
+    fn import(&self, import: &Import) -> String {
+        let path = self
+            .slice(&import.locs)
+            .filter(|s| s.starts_with('"'))
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| string_literal(&import.path));
+        match &import.kind {
+            shiika_ast::ImportKind::All => {
+                let line_head = begin_of(&import.locs)
+                    .and_then(|l| self.src.get(self.line_starts[l.line]..l.pos))
+                    .unwrap_or("");
+                if line_head.trim() == "require" {
+                    format!("require {}", path)
+                } else {
+                    format!("import {}", path)
+                }
+            }
+            shiika_ast::ImportKind::Namespace(name) => format!("import {} as {}", path, name),
+            shiika_ast::ImportKind::Names(names) => {
+                let names = names.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+                format!("import {} from {}", names.join(", "), path)
+            }
+        }
+    }
+}
+
+fn starts_with_keyword(s: &str, kw: &str) -> bool {
+    s.strip_prefix(kw)
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+}
+
+pub(crate) fn begin_of(locs: &LocationSpan) -> Option<Location> {
+    match locs {
+        LocationSpan::Just { begin, .. } => Some(begin.clone()),
+        LocationSpan::Empty => None,
+    }
+}
+
+pub(crate) fn end_of(locs: &LocationSpan) -> Option<Location> {
+    match locs {
+        LocationSpan::Just { end, .. } => Some(end.clone()),
+        LocationSpan::Empty => None,
+    }
+}
+
+/// Returns the later one of the two locations
+pub(crate) fn max_loc(a: Option<Location>, b: Option<Location>) -> Option<Location> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if a.pos >= b.pos { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Returns the beginning of `e` in the source. Unlike `e.locs`, this
+/// includes the receiver of a method call, etc.
+pub(crate) fn expr_begin(e: &AstExpression) -> Option<Location> {
+    let child = match &e.body {
+        AstExpressionBody::MethodCall(x) => x.receiver_expr.as_deref().and_then(expr_begin),
+        AstExpressionBody::LogicalAnd { left, .. } | AstExpressionBody::LogicalOr { left, .. } => {
+            expr_begin(left)
+        }
+        AstExpressionBody::LogicalNot { expr } => expr_begin(expr),
+        AstExpressionBody::If { then_exprs, .. } => then_exprs.first().and_then(expr_begin),
+        _ => None,
+    };
+    match (begin_of(&e.locs), child) {
+        (Some(a), Some(b)) => Some(if a.pos <= b.pos { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+fn def_locs(def: &Definition) -> &LocationSpan {
+    match def {
+        Definition::ClassDefinition { locs, .. }
+        | Definition::ModuleDefinition { locs, .. }
+        | Definition::EnumDefinition { locs, .. }
+        | Definition::ConstDefinition { locs, .. } => locs,
+        Definition::InstanceMethodDefinition { sig, .. }
+        | Definition::ClassMethodDefinition { sig, .. }
+        | Definition::MethodRequirementDefinition { sig } => &sig.locs,
+        Definition::InitializerDefinition(x) | Definition::ClassInitializerDefinition(x) => {
+            &x.sig.locs
+        }
+    }
+}
+
+/// Returns a string literal which evaluates to `s`
+pub(crate) fn string_literal(s: &str) -> String {
+    let mut out = String::from("\"");
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '#' if chars.peek() == Some(&'{') => out.push_str("\\#"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Returns `/source/` (the inverse of `Lexer::read_regex`)
+pub(crate) fn regex_literal(source: &str) -> String {
+    let mut out = String::from("/");
+    let mut chars = source.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                out.push(c);
+                out.extend(chars.next());
+            }
+            '/' => out.push_str("\\/"),
+            _ => out.push(c),
+        }
+    }
+    out.push('/');
+    out
+}
+
+pub(crate) fn begin_line(locs: &LocationSpan) -> Option<usize> {
+    begin_of(locs).map(|l| l.line)
+}
+
+pub(crate) fn end_line(locs: &LocationSpan) -> Option<usize> {
+    end_of(locs).map(|l| l.line)
+}
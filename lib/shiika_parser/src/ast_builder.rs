@@ -23,7 +23,7 @@ impl AstBuilder {
         }
     }
 
-    fn locs(&self, begin: Location, end: Location) -> LocationSpan {
+    pub fn locs(&self, begin: Location, end: Location) -> LocationSpan {
         LocationSpan::new(&self.filepath, begin, end)
     }
 
@@ -325,6 +325,23 @@ impl AstBuilder {
         self.primary_expression(begin, end, AstExpressionBody::ArrayLiteral(exprs))
     }
 
+    pub fn range_literal(
+        &self,
+        first: AstExpression,
+        last: AstExpression,
+        exclusive: bool,
+    ) -> AstExpression {
+        self.non_primary_expression_(
+            &first.locs.clone(),
+            &last.locs.clone(),
+            AstExpressionBody::RangeLiteral {
+                first: Box::new(first),
+                last: Box::new(last),
+                exclusive,
+            },
+        )
+    }
+
     pub fn float_literal(&self, value: f64, begin: Location, end: Location) -> AstExpression {
         self.primary_expression(begin, end, AstExpressionBody::FloatLiteral { value })
     }
@@ -333,6 +350,10 @@ impl AstBuilder {
         self.primary_expression(begin, end, AstExpressionBody::StringLiteral { content })
     }
 
+    pub fn regex_literal(&self, source: String, begin: Location, end: Location) -> AstExpression {
+        self.primary_expression(begin, end, AstExpressionBody::RegexLiteral { source })
+    }
+
     pub fn decimal_literal(&self, value: i64, begin: Location, end: Location) -> AstExpression {
         self.primary_expression(begin, end, AstExpressionBody::DecimalLiteral { value })
     }
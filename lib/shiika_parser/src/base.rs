@@ -1,8 +1,6 @@
-use crate::error::Error;
+use crate::error::{self, Error};
 pub use crate::lexer::*;
 pub use crate::Parser;
-use ariadne::{Label, Report, ReportKind, Source};
-use std::fs;
 
 use shiika_ast::*;
 
@@ -171,22 +169,8 @@ impl<'a> Parser<'a> {
 
     pub(super) fn parseerror(&self, msg: &str) -> Error {
         let (begin, end) = self.lexer.location_span();
-        let path = format!("{}", self.ast.filepath.display()); // ariadne 0.1.5 needs Id: Display (zesterer/ariadne#12)
-        let span = (&path, begin.pos..end.pos);
-        let src = if path.is_empty() {
-            // TODO: Use Option
-            Source::from(self.lexer.src)
-        } else {
-            Source::from(fs::read_to_string(&*self.ast.filepath).unwrap())
-        };
-        let mut report = vec![];
-        Report::build(ReportKind::Error, &path, begin.pos)
-            .with_message(msg)
-            .with_label(Label::new(span))
-            .finish()
-            .write((&path, src), &mut report)
-            .unwrap();
-        Error::ParseError(String::from_utf8_lossy(&report).to_string())
+        let report = error::build_report(msg, self.lexer.src, &self.ast.locs(begin, end));
+        Error::ParseError(report)
     }
 
     /// Print parser debug log (uncomment to enable)
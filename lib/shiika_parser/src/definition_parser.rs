@@ -24,6 +24,28 @@ impl<'a> Parser<'a> {
                 self.skip_ws()?;
                 self.parse_definition(true)
             }
+            Token::KwPrivate | Token::KwPublic => {
+                let visibility = if self.current_token_is(Token::KwPrivate) {
+                    Visibility::Private
+                } else {
+                    Visibility::Public
+                };
+                self.consume_token()?;
+                self.skip_ws()?;
+                let Some(mut def) = self.parse_definition(base_seen)? else {
+                    return Err(parse_error!(
+                        self,
+                        "visibility modifier must be followed by a definition"
+                    ));
+                };
+                if !def.set_visibility(visibility) {
+                    return Err(parse_error!(
+                        self,
+                        "visibility of initializers and requirements cannot be changed"
+                    ));
+                }
+                Ok(Some(def))
+            }
             Token::KwClass => Ok(Some(self.parse_class_definition(base_seen)?)),
             Token::KwModule => Ok(Some(self.parse_module_definition()?)),
             Token::KwEnum => Ok(Some(self.parse_enum_definition()?)),
@@ -47,6 +69,7 @@ impl<'a> Parser<'a> {
         self.skip_ws()?;
 
         // Class name
+        let (name_begin, name_end) = self.lexer.location_span();
         match self.current_token() {
             Token::UpperWord(s) => {
                 name = class_firstname(s);
@@ -101,6 +124,8 @@ impl<'a> Parser<'a> {
             typarams,
             supers,
             defs,
+            visibility: Visibility::Public,
+            locs: self.ast.locs(name_begin, name_end),
         })
     }
 
@@ -114,6 +139,7 @@ impl<'a> Parser<'a> {
         self.skip_ws()?;
 
         // Module name
+        let (name_begin, name_end) = self.lexer.location_span();
         match self.current_token() {
             Token::UpperWord(s) => {
                 name = module_firstname(s);
@@ -161,6 +187,8 @@ impl<'a> Parser<'a> {
             name,
             typarams,
             defs,
+            visibility: Visibility::Public,
+            locs: self.ast.locs(name_begin, name_end),
         })
     }
 
@@ -174,6 +202,7 @@ impl<'a> Parser<'a> {
         self.skip_ws()?;
 
         // Enum class name
+        let (name_begin, name_end) = self.lexer.location_span();
         match self.current_token() {
             Token::UpperWord(s) => {
                 name = class_firstname(s);
@@ -221,6 +250,8 @@ impl<'a> Parser<'a> {
             typarams,
             cases,
             defs,
+            visibility: Visibility::Public,
+            locs: self.ast.locs(name_begin, name_end),
         })
     }
 
@@ -245,6 +276,7 @@ impl<'a> Parser<'a> {
         debug_assert!(self.consume(Token::KwCase)?);
         self.skip_wsn()?;
         let name;
+        let (name_begin, name_end) = self.lexer.location_span();
         match self.current_token() {
             Token::UpperWord(s) => {
                 name = class_firstname(s);
@@ -273,7 +305,11 @@ impl<'a> Parser<'a> {
                 ))
             }
         };
-        Ok(shiika_ast::EnumCase { name, params })
+        Ok(shiika_ast::EnumCase {
+            name,
+            params,
+            locs: self.ast.locs(name_begin, name_end),
+        })
     }
 
     /// Parse superclass and included modules of a class.
@@ -407,6 +443,7 @@ impl<'a> Parser<'a> {
         let mut name = None;
         let ret_typ;
         let mut is_class_method = false;
+        let (mut name_begin, mut name_end) = self.lexer.location_span();
 
         // `self.` (Optional)
         if self.consume(Token::KwSelf)? {
@@ -422,6 +459,7 @@ impl<'a> Parser<'a> {
 
         // Method name
         if name == None {
+            (name_begin, name_end) = self.lexer.location_span();
             name = Some(method_firstname(self.get_method_name()?));
             self.consume_token()?;
         }
@@ -460,6 +498,8 @@ impl<'a> Parser<'a> {
             typarams,
             params,
             ret_typ,
+            visibility: Visibility::Public,
+            locs: self.ast.locs(name_begin, name_end),
         };
         Ok((sig, is_class_method))
     }
@@ -511,6 +551,7 @@ impl<'a> Parser<'a> {
             Token::RShift => ">>",
             Token::LessThan => "<",
             Token::LessEq => "<=",
+            Token::Compare => "<=>",
             Token::GreaterThan => ">",
             Token::GreaterEq => ">=",
             Token::EqEq => "==",
@@ -523,6 +564,7 @@ impl<'a> Parser<'a> {
     // Parse type parameters of a class or a method
     // - `class Foo<A, B, C>`
     // - `def foo<A, B, C>( ... )`
+    // - `def foo<A : Comparable<A>>( ... )`
     fn parse_opt_typarams(&mut self) -> Result<Vec<AstTyParam>, Error> {
         if !self.current_token_is(Token::LessThan) {
             return Ok(Default::default());
@@ -545,13 +587,24 @@ impl<'a> Parser<'a> {
                         Some(Token::KwIn) => AstVariance::Contravariant,
                         _ => panic!("[BUG] unexpected variance token"),
                     };
-                    typarams.push(AstTyParam {
-                        name: s.to_string(),
-                        variance: v,
-                    });
+                    let name = s.to_string();
                     variance = None;
                     self.consume_token()?;
                     self.skip_wsn()?;
+                    let upper_bound = if self.current_token_is(Token::Colon) {
+                        self.consume_token()?;
+                        self.skip_ws()?;
+                        let typ = self.parse_typ()?;
+                        self.skip_wsn()?;
+                        Some(typ)
+                    } else {
+                        None
+                    };
+                    typarams.push(AstTyParam {
+                        name,
+                        variance: v,
+                        upper_bound,
+                    });
                 }
                 Token::Comma => {
                     self.consume_token()?;
@@ -768,6 +821,7 @@ impl<'a> Parser<'a> {
     pub fn parse_const_definition(&mut self) -> Result<shiika_ast::Definition, Error> {
         self.debug_log("parse_const_definition");
         self.lv += 1;
+        let (name_begin, name_end) = self.lexer.location_span();
         let name = match self.current_token() {
             Token::UpperWord(s) => s.to_string(),
             _ => panic!("must be called on an UpperWord"),
@@ -781,6 +835,11 @@ impl<'a> Parser<'a> {
         let expr = self.parse_expr()?;
 
         self.lv -= 1;
-        Ok(shiika_ast::Definition::ConstDefinition { name, expr })
+        Ok(shiika_ast::Definition::ConstDefinition {
+            name,
+            expr,
+            visibility: Visibility::Public,
+            locs: self.ast.locs(name_begin, name_end),
+        })
     }
 }
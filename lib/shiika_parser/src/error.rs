@@ -1,11 +1,40 @@
 use crate::lexer::Cursor;
+use ariadne::{Label, ReportKind, Source};
+use shiika_ast::LocationSpan;
+use skc_error::{Report, ReportLocation};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     /// Error on parsing
     #[error("{0})")]
-    ParseError(String),
+    ParseError(Box<Report>),
     /// Error on tokenizing
     #[error("{msg}")]
     LexError { msg: String, location: Cursor },
+    /// Error on resolving imports
+    #[error("{0}")]
+    LoadError(Box<Report>),
+}
+
+/// Build an error report with ariadne
+pub(crate) fn build_report(msg: &str, src: &str, locs: &LocationSpan) -> Box<Report> {
+    let location = ReportLocation::new(locs);
+    let (path, span) = match &location {
+        // ariadne 0.1.5 needs Id: Display (zesterer/ariadne#12)
+        Some(l) => (format!("{}", l.path.display()), l.begin.pos..l.end.pos),
+        None => (String::new(), 0..0),
+    };
+    let mut rendered = vec![];
+    ariadne::Report::build(ReportKind::Error, &path, span.start)
+        .with_message(msg)
+        .with_label(Label::new((&path, span)))
+        .finish()
+        .write((&path, Source::from(src)), &mut rendered)
+        .unwrap();
+    Box::new(Report {
+        msg: msg.to_string(),
+        location,
+        is_warning: false,
+        rendered: String::from_utf8_lossy(&rendered).to_string(),
+    })
 }
@@ -275,23 +275,22 @@ impl<'a> Parser<'a> {
         })
     }
 
-    // TODO: decide the symbol
-    // Maybe `a..=b` and `a..<b` ?
+    /// `a..b`, `a...b`
     fn parse_range_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_range_expr");
         self.skip_ws()?;
-        let expr = self.parse_operator_or()?;
-        //        self.skip_ws();
-        //        match self.current_token() {
-        //            Token::symbol(s @ "..") | Token::symbol(s @ "...") => {
-        //                let inclusive = (s == "..");
-        //                self.skip_wsn();
-        //                let end_expr = self.parse_operator_or()?;
-        //                Ok(shiika_ast::range_expr(Some(expr), Some(end_expr), inclusive))
-        //            },
-        //            _ => Ok(expr)
-        //        }
+        let mut expr = self.parse_operator_or()?;
+        let token = self.next_nonspace_token()?;
+        if token == Token::DotDot || token == Token::DotDotDot {
+            self.skip_ws()?;
+            self.consume_token()?;
+            self.skip_wsn()?;
+            let last = self.parse_operator_or()?;
+            expr = self
+                .ast
+                .range_literal(expr, last, token == Token::DotDotDot);
+        }
         self.lv -= 1;
         Ok(expr)
     }
@@ -342,7 +341,7 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
-    /// `==`, etc.
+    /// `==`, `<=>`, etc.
     fn parse_equality_expr(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
         self.debug_log("parse_equality_expr");
@@ -351,6 +350,7 @@ impl<'a> Parser<'a> {
         let op = match self.next_nonspace_token()? {
             Token::EqEq => "==",
             Token::NotEq => "!=",
+            Token::Compare => "<=>",
             _ => {
                 self.lv -= 1;
                 return Ok(left);
@@ -362,6 +362,12 @@ impl<'a> Parser<'a> {
         self.skip_wsn()?;
         let right = self.parse_relational_expr()?;
         let end = self.lexer.location();
+        if op == "<=>" {
+            self.lv -= 1;
+            return Ok(self
+                .ast
+                .simple_method_call(Some(left), op, vec![right], begin, end));
+        }
         let call_eq = self
             .ast
             .simple_method_call(Some(left), "==", vec![right], begin, end);
@@ -644,8 +650,21 @@ impl<'a> Parser<'a> {
                 Token::KwWhen => {
                     self.consume_token()?;
                     self.skip_ws()?;
+                    let pat_begin = self.lexer.location();
                     let pattern = self.parse_pattern()?;
+                    let pat_end = self.lexer.location();
                     self.skip_ws()?;
+                    let guard = if self.current_token_is(Token::ModIf)
+                        || self.current_token_is(Token::KwIf)
+                    {
+                        self.consume_token()?;
+                        self.skip_ws()?;
+                        let cond = self.parse_operator_expr()?;
+                        self.skip_ws()?;
+                        Some(cond)
+                    } else {
+                        None
+                    };
                     if self.current_token_is(Token::KwThen) {
                         self.consume_token()?;
                     } else {
@@ -653,13 +672,25 @@ impl<'a> Parser<'a> {
                     }
                     let exprs =
                         self.parse_exprs(vec![Token::KwEnd, Token::KwWhen, Token::KwElse])?;
-                    clauses.push((pattern, exprs));
+                    clauses.push(AstMatchClause {
+                        pattern,
+                        guard,
+                        body: exprs,
+                        locs: self.ast.locs(pat_begin, pat_end),
+                    });
                 }
                 Token::KwElse => {
+                    let else_begin = self.lexer.location();
                     self.consume_token()?;
+                    let else_end = self.lexer.location();
                     let exprs = self.parse_exprs(vec![Token::KwEnd])?;
                     let pattern = shiika_ast::AstPattern::VariablePattern("_".to_string());
-                    clauses.push((pattern, exprs));
+                    clauses.push(AstMatchClause {
+                        pattern,
+                        guard: None,
+                        body: exprs,
+                        locs: self.ast.locs(else_begin, else_end),
+                    });
                 }
                 Token::KwEnd => {
                     self.consume_token()?;
@@ -954,6 +985,7 @@ impl<'a> Parser<'a> {
             Token::Number(_) => self.parse_decimal_literal(),
             Token::Str(_) => self.parse_string_literal(),
             Token::StrWithInterpolation { .. } => self.parse_string_with_interpolation(),
+            Token::Regex(_) => self.parse_regex_literal(),
             Token::LParen => self.parse_parenthesized_funcall(),
             token => Err(parse_error!(self, "unexpected token: {:?}", token)),
         }?;
@@ -1091,7 +1123,7 @@ impl<'a> Parser<'a> {
         assert!(self.consume(Token::KwFn)?);
         let params;
         if self.consume(Token::LParen)? {
-            params = self.parse_block_params(true, &Token::RParen)?;
+            params = self.parse_block_params(&Token::RParen)?;
             self.skip_ws()?;
         } else {
             params = vec![];
@@ -1186,11 +1218,14 @@ impl<'a> Parser<'a> {
             Token::Number(s) => {
                 if s.contains('.') {
                     let end = self.lexer.location();
-                    let value = s.parse().unwrap();
+                    let value = parse_float_literal(&s);
                     self.ast.float_literal(value, begin, end)
                 } else {
                     let end = self.lexer.location();
-                    let value = s.parse().unwrap();
+                    let Some(value) = parse_int_literal(&s) else {
+                        self.lv -= 1;
+                        return Err(self.parseerror("integer literal too large"));
+                    };
                     self.ast.decimal_literal(value, begin, end)
                 }
             }
@@ -1213,6 +1248,16 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_regex_literal(&mut self) -> Result<AstExpression, Error> {
+        let begin = self.lexer.location();
+        if let Token::Regex(source) = self.consume_token()? {
+            let end = self.lexer.location();
+            Ok(self.ast.regex_literal(source, begin, end))
+        } else {
+            Err(self.parseerror("invalid call"))
+        }
+    }
+
     /// Process a string literal with interpolation (`#{}`)
     fn parse_string_with_interpolation(&mut self) -> Result<AstExpression, Error> {
         self.lv += 1;
@@ -1332,7 +1377,7 @@ impl<'a> Parser<'a> {
         self.expect(Token::KwDo)?;
         self.skip_ws()?;
         let block_params = if self.consume(Token::Or)? {
-            self.parse_block_params(false, &Token::Or)?
+            self.parse_block_params(&Token::Or)?
         } else {
             vec![]
         };
@@ -1354,7 +1399,7 @@ impl<'a> Parser<'a> {
         self.expect(Token::LBrace)?;
         self.skip_ws()?;
         let block_params = if self.consume(Token::Or)? {
-            self.parse_block_params(false, &Token::Or)?
+            self.parse_block_params(&Token::Or)?
         } else {
             vec![]
         };
@@ -1369,11 +1414,7 @@ impl<'a> Parser<'a> {
     }
 
     /// Parse `a, b, ...` in `|...|` or `fn(...){`
-    fn parse_block_params(
-        &mut self,
-        type_required: bool,
-        stop_tok: &Token,
-    ) -> Result<Vec<BlockParam>, Error> {
+    fn parse_block_params(&mut self, stop_tok: &Token) -> Result<Vec<BlockParam>, Error> {
         self.lv += 1;
         self.debug_log("parse_block_params");
         self.skip_ws()?;
@@ -1391,7 +1432,7 @@ impl<'a> Parser<'a> {
                     }
                 }
                 Token::KeyName(_) | Token::LowerWord(_) => {
-                    params.push(self.parse_block_param(type_required)?);
+                    params.push(self.parse_block_param()?);
                     comma_seen = false;
                 }
                 token => {
@@ -1415,7 +1456,7 @@ impl<'a> Parser<'a> {
         Ok(params)
     }
 
-    fn parse_block_param(&mut self, type_required: bool) -> Result<BlockParam, Error> {
+    fn parse_block_param(&mut self) -> Result<BlockParam, Error> {
         // Name
         let name;
         let colon_seen = match self.current_token() {
@@ -1443,48 +1484,79 @@ impl<'a> Parser<'a> {
         let opt_typ = if colon_seen {
             Some(self.parse_typ()?)
         } else {
-            if type_required {
-                return Err(parse_error!(
-                    self,
-                    "type annotation of fn param is mandatory"
-                ));
-            }
             None
         };
 
         Ok(shiika_ast::BlockParam { name, opt_typ })
     }
 
-    /// Parse pattern of match expr
+    /// Parse pattern of match expr (`A | B` or a single pattern)
     fn parse_pattern(&mut self) -> Result<AstPattern, Error> {
         self.lv += 1;
         self.debug_log("parse_pattern");
+        let mut pats = vec![self.parse_single_pattern()?];
+        while self.next_nonspace_token()? == Token::Or {
+            self.skip_ws()?;
+            self.consume_token()?;
+            self.skip_wsn()?;
+            pats.push(self.parse_single_pattern()?);
+        }
+        self.lv -= 1;
+        if pats.len() == 1 {
+            Ok(pats.pop().unwrap())
+        } else {
+            Ok(shiika_ast::AstPattern::OrPattern(pats))
+        }
+    }
+
+    /// Parse a pattern other than `A | B`
+    fn parse_single_pattern(&mut self) -> Result<AstPattern, Error> {
+        self.lv += 1;
+        self.debug_log("parse_single_pattern");
         let token = self.current_token();
         let item = match token {
             Token::LowerWord(s) => {
                 let name = s.to_string();
                 self.consume_token()?;
-                shiika_ast::AstPattern::VariablePattern(name)
+                if self.next_nonspace_token()? == Token::At {
+                    self.skip_ws()?;
+                    self.consume_token()?;
+                    self.skip_ws()?;
+                    let pattern = self.parse_single_pattern()?;
+                    shiika_ast::AstPattern::BindingPattern {
+                        name,
+                        pattern: Box::new(pattern),
+                    }
+                } else {
+                    shiika_ast::AstPattern::VariablePattern(name)
+                }
             }
             Token::UpperWord(s) => {
                 let name = s.to_string();
+                let begin = self.lexer.location();
                 self.consume_token()?;
-                self.parse_extractor_pattern(name)?
+                self.parse_extractor_pattern(name, begin)?
             }
             Token::KwTrue | Token::KwFalse => {
                 let b = *token == Token::KwTrue;
                 self.consume_token()?;
                 shiika_ast::AstPattern::BooleanLiteralPattern(b)
             }
-            Token::Number(s) => {
-                if s.contains('.') {
-                    let value = s.parse().unwrap();
+            Token::Number(_) | Token::UnaryMinus => {
+                let first = self.parse_number_pattern()?;
+                let token = self.next_nonspace_token()?;
+                if token == Token::DotDot || token == Token::DotDotDot {
+                    self.skip_ws()?;
                     self.consume_token()?;
-                    shiika_ast::AstPattern::FloatLiteralPattern(value)
+                    self.skip_ws()?;
+                    let last = self.parse_number_pattern()?;
+                    shiika_ast::AstPattern::RangePattern {
+                        first: Box::new(first),
+                        last: Box::new(last),
+                        exclusive: token == Token::DotDotDot,
+                    }
                 } else {
-                    let value = s.parse().unwrap();
-                    self.consume_token()?;
-                    shiika_ast::AstPattern::IntegerLiteralPattern(value)
+                    first
                 }
             }
             Token::Str(content) => {
@@ -1495,6 +1567,7 @@ impl<'a> Parser<'a> {
             Token::StrWithInterpolation { .. } => {
                 todo!()
             }
+            Token::LSqBracket => self.parse_array_pattern()?,
             _ => {
                 return Err(parse_error!(self, "expected a pattern but got {:?}", token));
             }
@@ -1503,8 +1576,84 @@ impl<'a> Parser<'a> {
         Ok(item)
     }
 
+    /// Parse pattern like `[first, *rest]`
+    fn parse_array_pattern(&mut self) -> Result<AstPattern, Error> {
+        self.lv += 1;
+        self.debug_log("parse_array_pattern");
+        assert!(self.consume(Token::LSqBracket)?);
+        self.skip_wsn()?;
+        let mut before = vec![];
+        let mut rest = None;
+        let mut after = vec![];
+        let mut first = true;
+        loop {
+            if self.consume(Token::RSqBracket)? {
+                break;
+            }
+            if !first {
+                self.expect(Token::Comma)?;
+                self.skip_wsn()?;
+                // Allow trailing comma
+                if self.consume(Token::RSqBracket)? {
+                    break;
+                }
+            }
+            first = false;
+            if self.consume(Token::Mul)? {
+                if rest.is_some() {
+                    return Err(parse_error!(self, "only one `*' is allowed in a pattern"));
+                }
+                let name = match self.current_token() {
+                    Token::LowerWord(s) => s.to_string(),
+                    token => {
+                        return Err(parse_error!(self, "expected a name but got {:?}", token));
+                    }
+                };
+                self.consume_token()?;
+                rest = Some(name);
+            } else if rest.is_some() {
+                after.push(self.parse_pattern()?);
+            } else {
+                before.push(self.parse_pattern()?);
+            }
+            self.skip_wsn()?;
+        }
+        self.lv -= 1;
+        Ok(shiika_ast::AstPattern::ArrayPattern {
+            before,
+            rest,
+            after,
+        })
+    }
+
+    /// Parse pattern like `1`, `-1` or `1.0`
+    fn parse_number_pattern(&mut self) -> Result<AstPattern, Error> {
+        let minus = self.consume(Token::UnaryMinus)?;
+        let item = match self.current_token() {
+            Token::Number(s) if s.contains('.') => {
+                let value = parse_float_literal(s);
+                shiika_ast::AstPattern::FloatLiteralPattern(if minus { -value } else { value })
+            }
+            Token::Number(s) => {
+                let Some(value) = parse_int_literal(s) else {
+                    return Err(parse_error!(self, "integer literal too large"));
+                };
+                shiika_ast::AstPattern::IntegerLiteralPattern(if minus { -value } else { value })
+            }
+            token => {
+                return Err(parse_error!(self, "expected a number but got {:?}", token));
+            }
+        };
+        self.consume_token()?;
+        Ok(item)
+    }
+
     /// Parse pattern like `Some(val)`
-    fn parse_extractor_pattern(&mut self, upper_word: String) -> Result<AstPattern, Error> {
+    fn parse_extractor_pattern(
+        &mut self,
+        upper_word: String,
+        begin: Location,
+    ) -> Result<AstPattern, Error> {
         self.lv += 1;
         self.debug_log("parse_extractor_pattern");
 
@@ -1523,6 +1672,8 @@ impl<'a> Parser<'a> {
                 break;
             }
         }
+        let end = self.lexer.location();
+        let locs = self.ast.locs(begin, end);
 
         // Parameters (optional)
         let mut params = vec![];
@@ -1542,6 +1693,28 @@ impl<'a> Parser<'a> {
         }
 
         self.lv -= 1;
-        Ok(shiika_ast::AstPattern::ExtractorPattern { names, params })
+        Ok(shiika_ast::AstPattern::ExtractorPattern {
+            names,
+            params,
+            locs,
+        })
     }
 }
+
+/// Convert a number literal like `1_000` or `0xff` to i64. Returns None if
+/// it overflows.
+fn parse_int_literal(s: &str) -> Option<i64> {
+    let s = s.replace('_', "");
+    let (digits, radix) = match s.get(..2) {
+        Some("0x") => (&s[2..], 16),
+        Some("0o") => (&s[2..], 8),
+        Some("0b") => (&s[2..], 2),
+        _ => (&s[..], 10),
+    };
+    i64::from_str_radix(digits, radix).ok()
+}
+
+/// Convert a number literal like `1_000.5` to f64.
+fn parse_float_literal(s: &str) -> f64 {
+    s.replace('_', "").parse().unwrap()
+}
@@ -1,5 +1,6 @@
 use crate::error::Error;
 use shiika_ast::{Location, Token};
+use std::collections::BTreeMap;
 
 /// Lexer
 #[derive(Debug)]
@@ -18,6 +19,18 @@ pub struct Lexer<'a> {
     space_seen: bool,
     /// If true, parse `>>` as `>` + `>`
     pub rshift_is_gtgt: bool,
+    /// Comments read so far (key: position of the comment). This is a map
+    /// because the same comment may be read twice on backtracking
+    comments: BTreeMap<usize, Comment>,
+}
+
+/// A comment (from `#` to the end of the line.) Comments are not passed to
+/// the parser but kept as trivia so that the formatter can restore them.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Comment {
+    /// The comment text including the leading `#`
+    pub text: String,
+    pub location: Location,
 }
 
 /// Flags to decide a `-`, `+`, etc. is unary or binary.
@@ -30,6 +43,9 @@ pub struct Lexer<'a> {
 /// - `p - x`  # binary minus            ExprArg
 /// - `p -x`   # unary minus             ExprArg
 /// - `1 -2`   # binary minus (unusual)  ExprArg
+///
+/// A `/` starts a regex literal where a `-` would be unary (eg. `p /x/`),
+/// if the regex is closed in the line (see `Lexer::starts_regex`.)
 #[derive(Debug, PartialEq, Eq)]
 pub enum LexerState {
     /// A new expression begins here
@@ -66,6 +82,11 @@ impl Cursor {
         }
     }
 
+    /// Returns the location this cursor points to
+    pub fn location(&self) -> Location {
+        Location::new(self.line, self.col, self.pos)
+    }
+
     /// Return the current char (None if eof)
     pub fn peek(&self, src: &str) -> Option<char> {
         src[self.pos..].chars().next()
@@ -130,6 +151,7 @@ impl<'a> Lexer<'a> {
             state,
             space_seen: false,
             rshift_is_gtgt: false,
+            comments: Default::default(),
         };
         lexer.read_token().unwrap();
         lexer
@@ -158,6 +180,11 @@ impl<'a> Lexer<'a> {
         (begin, end)
     }
 
+    /// Returns the comments read so far, in the order of appearance
+    pub fn comments(&self) -> Vec<Comment> {
+        self.comments.values().cloned().collect()
+    }
+
     pub fn debug_info(&self) -> String {
         format!("{:?} {:?}", self.current_token, self.state)
     }
@@ -330,16 +357,21 @@ impl<'a> Lexer<'a> {
         Token::Newline
     }
 
-    /// A comment is always followed by a newline, so treat the combination as a newline
+    /// Save the comment as trivia and read the newline after it
     fn read_comment(&mut self, next_cur: &mut Cursor) -> Token {
-        next_cur.proceed(self.src); // Skip the `#'
-        loop {
-            let c = next_cur.proceed(self.src);
-            if c == '\n' {
-                break;
-            }
+        let src = self.src;
+        let begin = next_cur.clone();
+        while !matches!(next_cur.peek(src), None | Some('\n')) {
+            next_cur.proceed(src);
+        }
+        self.comments.entry(begin.pos).or_insert_with(|| Comment {
+            text: src[begin.pos..next_cur.pos].trim_end().to_string(),
+            location: begin.location(),
+        });
+        match next_cur.peek(src) {
+            Some(_) => self.read_newline(next_cur),
+            None => Token::Eof,
         }
-        Token::Newline
     }
 
     fn read_upper_word(&mut self, next_cur: &mut Cursor, cur: Option<&Cursor>) -> Token {
@@ -399,7 +431,10 @@ impl<'a> Lexer<'a> {
         let s = &self.src[begin..next_cur.pos];
         let (token, state) = match s {
             "require" => (Token::KwRequire, LexerState::ExprBegin),
+            "import" => (Token::KwImport, LexerState::ExprBegin),
             "base" => (Token::KwBase, LexerState::ExprBegin),
+            "private" => (Token::KwPrivate, LexerState::ExprBegin),
+            "public" => (Token::KwPublic, LexerState::ExprBegin),
             "class" => (Token::KwClass, LexerState::ExprBegin),
             "module" => (Token::KwModule, LexerState::ExprBegin),
             "requirement" => (Token::KwRequirement, LexerState::ExprBegin),
@@ -455,12 +490,15 @@ impl<'a> Lexer<'a> {
         {
             next_cur.proceed(self.src);
         }
-        // TODO: LexError if no word succeeds '@'
         let begin = match cur {
             Some(c) => c.pos,
             None => self.cur.pos,
         };
         let s = &self.src[begin..next_cur.pos];
+        if s == "@" {
+            // eg. `p @ Point(x, y)` in a pattern
+            return Token::At;
+        }
         Token::IVar(s.to_string())
     }
 
@@ -525,7 +563,9 @@ impl<'a> Lexer<'a> {
                 }
             }
             '/' => {
-                if c2 == Some('=') {
+                if self.starts_regex(next_cur, c2) {
+                    Ok((self.read_regex(next_cur)?, Some(LexerState::ExprEnd)))
+                } else if c2 == Some('=') {
                     next_cur.proceed(self.src);
                     Ok((Token::DivEq, Some(LexerState::ExprBegin)))
                 } else {
@@ -559,7 +599,12 @@ impl<'a> Lexer<'a> {
             '<' => {
                 if c2 == Some('=') {
                     next_cur.proceed(self.src);
-                    Ok((Token::LessEq, Some(LexerState::ExprBegin)))
+                    if next_cur.peek(self.src) == Some('>') {
+                        next_cur.proceed(self.src);
+                        Ok((Token::Compare, Some(LexerState::ExprBegin)))
+                    } else {
+                        Ok((Token::LessEq, Some(LexerState::ExprBegin)))
+                    }
                 } else if c2 == Some('<') {
                     next_cur.proceed(self.src);
                     let c3 = next_cur.peek(self.src);
@@ -598,7 +643,19 @@ impl<'a> Lexer<'a> {
                     Ok((Token::GreaterThan, Some(LexerState::ExprBegin)))
                 }
             }
-            '.' => Ok((Token::Dot, Some(LexerState::ExprBegin))),
+            '.' => {
+                if c2 == Some('.') {
+                    next_cur.proceed(self.src);
+                    if next_cur.peek(self.src) == Some('.') {
+                        next_cur.proceed(self.src);
+                        Ok((Token::DotDotDot, Some(LexerState::ExprBegin)))
+                    } else {
+                        Ok((Token::DotDot, Some(LexerState::ExprBegin)))
+                    }
+                } else {
+                    Ok((Token::Dot, Some(LexerState::ExprBegin)))
+                }
+            }
             '@' => Ok((Token::At, Some(LexerState::ExprBegin))),
             '~' => Ok((Token::Tilde, Some(LexerState::ExprBegin))),
             '?' => Ok((Token::Question, Some(LexerState::ExprBegin))),
@@ -644,31 +701,78 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn read_number(&mut self, next_cur: &mut Cursor, cur: Option<&Cursor>) -> Result<Token, Error> {
-        loop {
-            match self.char_type(next_cur.peek(self.src)) {
-                CharType::Number => {
-                    next_cur.proceed(self.src);
-                }
-                CharType::UpperWord | CharType::LowerWord => {
-                    // TODO: this should be lexing error
-                    return Err(self.lex_error("need space after a number"));
-                }
-                CharType::Symbol => {
-                    if next_cur.peek(self.src) == Some('.') {
-                        if self.char_type(next_cur.peek2(self.src)) == CharType::Number {
-                            next_cur.proceed(self.src);
-                            next_cur.proceed(self.src);
-                        } else {
-                            break;
-                        }
-                    } else {
-                        break;
-                    }
+    /// Returns true if the `/` just read starts a regex literal.
+    /// `next_cur` points to the char after the `/`.
+    ///
+    /// - `/=/`   # regex      ExprBegin
+    /// - `p /a/` # regex      ExprArg
+    /// - `a /2`  # division   ExprArg (no closing `/` in the line)
+    /// - `a /= 2`# `/=`       ExprArg
+    /// - `a / 2` # division   ExprArg
+    fn starts_regex(&self, next_cur: &Cursor, next_char: Option<char>) -> bool {
+        match self.state {
+            LexerState::ExprBegin => true,
+            LexerState::ExprArg => {
+                // `a /= 2` is an assignment
+                let div_eq = next_char == Some('=') && next_cur.peek2(self.src) == Some(' ');
+                self.current_token == Token::Space
+                    && next_char != Some(' ')
+                    && !div_eq
+                    && self.regex_closes(next_cur)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns true if there is a closing `/` before the end of the line
+    fn regex_closes(&self, cur: &Cursor) -> bool {
+        let mut chars = self.src[cur.pos..].chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => return false,
+                '/' => return true,
+                '\\' => {
+                    chars.next();
                 }
-                _ => break,
+                _ => (),
             }
         }
+        false
+    }
+
+    /// Read a number literal like `12`, `1.5`, `1_000` or `0xff`
+    fn read_number(&mut self, next_cur: &mut Cursor, cur: Option<&Cursor>) -> Result<Token, Error> {
+        let radix = if next_cur.peek(self.src) == Some('0') {
+            match next_cur.peek2(self.src) {
+                Some('x') => 16,
+                Some('o') => 8,
+                Some('b') => 2,
+                _ => 10,
+            }
+        } else {
+            10
+        };
+        if radix != 10 {
+            // Skip the prefix
+            next_cur.proceed(self.src);
+            next_cur.proceed(self.src);
+        }
+        self.read_digits(next_cur, radix)?;
+        if radix == 10
+            && next_cur.peek(self.src) == Some('.')
+            && self.char_type(next_cur.peek2(self.src)) == CharType::Number
+        {
+            next_cur.proceed(self.src);
+            self.read_digits(next_cur, radix)?;
+        }
+        match self.char_type(next_cur.peek(self.src)) {
+            CharType::Number => return Err(self.lex_error("invalid digit in a number")),
+            CharType::UpperWord | CharType::LowerWord => {
+                // TODO: this should be lexing error
+                return Err(self.lex_error("need space after a number"));
+            }
+            _ => (),
+        }
         let begin = match cur {
             Some(c) => c.pos,
             None => self.cur.pos,
@@ -676,6 +780,28 @@ impl<'a> Lexer<'a> {
         Ok(Token::Number(self.src[begin..next_cur.pos].to_string()))
     }
 
+    /// Read digits of a number. `_` may be used between digits.
+    fn read_digits(&self, next_cur: &mut Cursor, radix: u32) -> Result<(), Error> {
+        let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_digit(radix));
+        if !is_digit(next_cur.peek(self.src)) {
+            return Err(self.lex_error("missing digits in a number"));
+        }
+        loop {
+            let c = next_cur.peek(self.src);
+            if is_digit(c) {
+                next_cur.proceed(self.src);
+            } else if c == Some('_') {
+                next_cur.proceed(self.src);
+                if !is_digit(next_cur.peek(self.src)) {
+                    return Err(self.lex_error("`_' in a number must be followed by a digit"));
+                }
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
     /// Read a string literal
     /// Also parse escape sequences here
     /// - cont: true if reading string after `#{}'
@@ -731,6 +857,40 @@ impl<'a> Lexer<'a> {
         Ok(Token::Str(buf))
     }
 
+    /// Read a regex literal like `/a+/`. The leading `/` is already consumed.
+    /// Escape sequences are kept as is (except `\/`) because they are
+    /// interpreted by the regex engine.
+    fn read_regex(&mut self, next_cur: &mut Cursor) -> Result<Token, Error> {
+        let mut buf = String::new();
+        loop {
+            match next_cur.peek(self.src) {
+                None | Some('\n') => {
+                    return Err(self.lex_error("found unterminated regex"));
+                }
+                Some('/') => {
+                    next_cur.proceed(self.src);
+                    break;
+                }
+                Some('\\') => {
+                    next_cur.proceed(self.src);
+                    match next_cur.peek(self.src) {
+                        None | Some('\n') => {
+                            return Err(self.lex_error("found unterminated regex"));
+                        }
+                        Some('/') => {}
+                        Some(_) => buf.push('\\'),
+                    }
+                    buf.push(next_cur.proceed(self.src));
+                }
+                Some(c) => {
+                    next_cur.proceed(self.src);
+                    buf.push(c);
+                }
+            }
+        }
+        Ok(Token::Regex(buf))
+    }
+
     /// Return special char written with '\'
     fn _read_escape_sequence(&self, c: Option<char>) -> Result<char, Error> {
         match c {
@@ -774,3 +934,87 @@ impl<'a> Lexer<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the tokens of `src`. `state` is the lexer state set by the
+    /// parser at the beginning (eg. `ExprArg` after a method name.)
+    fn tokens(src: &str, state: LexerState) -> Vec<Token> {
+        let mut lexer = Lexer::new_with_state(src, state);
+        let mut tokens = vec![];
+        while lexer.current_token != Token::Eof {
+            tokens.push(lexer.consume_token().unwrap());
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_regex_starting_with_eq() {
+        assert_eq!(
+            tokens("/=/", LexerState::ExprBegin),
+            vec![Token::Regex("=".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_regex_as_arg() {
+        // `p /a/`
+        assert_eq!(
+            tokens(" /a/", LexerState::ExprArg),
+            vec![Token::Space, Token::Regex("a".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_div_with_spaces() {
+        // `a / 2`
+        assert_eq!(
+            tokens(" / 2", LexerState::ExprArg),
+            vec![
+                Token::Space,
+                Token::Div,
+                Token::Space,
+                Token::Number("2".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_div_with_space_before() {
+        // `a /2`
+        assert_eq!(
+            tokens(" /2", LexerState::ExprArg),
+            vec![Token::Space, Token::Div, Token::Number("2".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_div_eq() {
+        // `a/=2`
+        assert_eq!(
+            tokens("/=2", LexerState::ExprEnd),
+            vec![Token::DivEq, Token::Number("2".to_string())]
+        );
+        // `a /=2`
+        assert_eq!(
+            tokens(" /=2", LexerState::ExprArg),
+            vec![Token::Space, Token::DivEq, Token::Number("2".to_string())]
+        );
+        // `a /= b / 2`
+        assert_eq!(
+            tokens(" /= b / 2", LexerState::ExprArg)[..3],
+            [Token::Space, Token::DivEq, Token::Space]
+        );
+    }
+
+    #[test]
+    fn test_regex_starting_with_eq_as_arg() {
+        // `p /=/`
+        assert_eq!(
+            tokens(" /=/", LexerState::ExprArg),
+            vec![Token::Space, Token::Regex("=".to_string())]
+        );
+    }
+}
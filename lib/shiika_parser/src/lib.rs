@@ -17,6 +17,7 @@ mod definition_parser;
 mod error;
 mod expression_parser;
 pub mod lexer;
+pub mod loader;
 mod source_file;
 use crate::ast_builder::AstBuilder;
 pub use crate::error::Error;
@@ -42,17 +43,23 @@ impl<'a> Parser<'a> {
         }
     }
 
-    /// Parse a method signature
+    /// Parse a method signature. It may be prefixed with `private`
+    /// (eg. `private _fill_buf -> Bool`.)
     pub fn parse_signature(sig_str: &str) -> Result<ast::AstMethodSignature, Error> {
+        let (visibility, sig_str) = match sig_str.strip_prefix("private ") {
+            Some(rest) => (ast::Visibility::Private, rest.trim_start()),
+            None => (ast::Visibility::Public, sig_str),
+        };
         let lexer = Lexer::new_with_state(sig_str, LexerState::MethodName);
         let mut parser = Parser {
             lexer,
             ast: AstBuilder::empty(),
             lv: 0,
         };
-        let (ast_sig, _) = parser.parse_method_signature()?;
+        let (mut ast_sig, _) = parser.parse_method_signature()?;
         // Check if entire string is consumed
         parser.expect_eof()?;
+        ast_sig.visibility = visibility;
         Ok(ast_sig)
     }
 
@@ -65,11 +72,25 @@ impl<'a> Parser<'a> {
         Ok(program)
     }
 
+    /// Parse a file and returns the ast with the comments in it
+    pub fn parse_with_comments(
+        file: &SourceFile,
+    ) -> Result<(ast::Program, Vec<lexer::Comment>), Error> {
+        let mut parser = Parser::new(file);
+        let program = parser.parse_program()?;
+        Ok((program, parser.lexer.comments()))
+    }
+
     fn parse_program(&mut self) -> Result<ast::Program, Error> {
         self.skip_wsn()?;
-        let toplevel_items = self.parse_toplevel_items()?;
+        let mut imports = vec![];
+        let toplevel_items = self.parse_toplevel_items(&mut imports)?;
         self.expect_eof()?;
-        Ok(ast::Program { toplevel_items })
+        Ok(ast::Program {
+            toplevel_items,
+            imports,
+            imported_names: Default::default(),
+        })
     }
 
     pub fn expect_eof(&self) -> Result<(), Error> {
@@ -83,19 +104,31 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    fn parse_toplevel_items(&mut self) -> Result<Vec<ast::TopLevelItem>, Error> {
+    fn parse_toplevel_items(
+        &mut self,
+        imports: &mut Vec<ast::Import>,
+    ) -> Result<Vec<ast::TopLevelItem>, Error> {
         let mut items = vec![];
         let mut base_seen = false;
         loop {
             match self.current_token() {
                 Token::KwRequire => {
-                    self.skip_require()?;
+                    imports.push(self.parse_require()?);
+                }
+                Token::KwImport => {
+                    imports.push(self.parse_import()?);
                 }
                 Token::KwBase => {
                     self.consume(Token::KwBase)?;
                     self.skip_ws()?;
                     base_seen = true;
                 }
+                Token::KwPrivate | Token::KwPublic => {
+                    return Err(parse_error!(
+                        self,
+                        "visibility modifier is only allowed in a class, module or enum"
+                    ));
+                }
                 Token::KwClass => {
                     items.push(ast::TopLevelItem::Def(
                         self.parse_class_definition(base_seen)?,
@@ -129,14 +162,85 @@ impl<'a> Parser<'a> {
         Ok(items)
     }
 
-    /// Skip `require "foo"`
-    fn skip_require(&mut self) -> Result<(), Error> {
+    /// Parse `require "foo"` (same as `import "foo"`)
+    fn parse_require(&mut self) -> Result<ast::Import, Error> {
         assert!(self.consume(Token::KwRequire)?);
         self.skip_ws()?;
-        match self.current_token() {
-            Token::Str(_) | Token::StrWithInterpolation { .. } => self.consume_token()?,
-            _ => return Err(parse_error!(self, "unexpected argument for require")),
+        let (path, locs) = self.parse_import_path()?;
+        Ok(ast::Import {
+            path,
+            kind: ast::ImportKind::All,
+            locs,
+        })
+    }
+
+    /// Parse one of the following.
+    /// - `import "foo"`
+    /// - `import "foo" as Foo`
+    /// - `import A, B from "foo"`
+    fn parse_import(&mut self) -> Result<ast::Import, Error> {
+        self.lv += 1;
+        self.debug_log("parse_import");
+        assert!(self.consume(Token::KwImport)?);
+        self.skip_ws()?;
+        let import = if let Token::UpperWord(_) = self.current_token() {
+            let mut names = vec![];
+            loop {
+                let (begin, end) = self.lexer.location_span();
+                let Token::UpperWord(name) = self.consume_token()? else {
+                    return Err(parse_error!(self, "expected a name to import"));
+                };
+                names.push((name, self.ast.locs(begin, end)));
+                self.skip_ws()?;
+                if !self.consume(Token::Comma)? {
+                    break;
+                }
+                self.skip_ws()?;
+                if !matches!(self.current_token(), Token::UpperWord(_)) {
+                    return Err(parse_error!(self, "expected a name to import"));
+                }
+            }
+            if !self.consume(Token::LowerWord("from".to_string()))? {
+                return Err(parse_error!(self, "expected `from'"));
+            }
+            self.skip_ws()?;
+            let (path, locs) = self.parse_import_path()?;
+            ast::Import {
+                path,
+                kind: ast::ImportKind::Names(names),
+                locs,
+            }
+        } else {
+            let (path, locs) = self.parse_import_path()?;
+            let kind = if self.next_nonspace_token()? == Token::LowerWord("as".to_string()) {
+                self.skip_ws()?;
+                self.consume_token()?;
+                self.skip_ws()?;
+                let Token::UpperWord(name) = self.current_token().clone() else {
+                    return Err(parse_error!(self, "expected a namespace name"));
+                };
+                self.consume_token()?;
+                ast::ImportKind::Namespace(name)
+            } else {
+                ast::ImportKind::All
+            };
+            ast::Import { path, kind, locs }
         };
-        Ok(())
+        self.lv -= 1;
+        Ok(import)
+    }
+
+    /// Parse the string literal after `import`
+    fn parse_import_path(&mut self) -> Result<(String, ast::LocationSpan), Error> {
+        let begin = self.lexer.location();
+        match self.current_token() {
+            Token::Str(s) => {
+                let s = s.clone();
+                self.consume_token()?;
+                let end = self.lexer.location();
+                Ok((s, self.ast.locs(begin, end)))
+            }
+            _ => Err(parse_error!(self, "expected a string literal")),
+        }
     }
 }
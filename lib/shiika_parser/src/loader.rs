@@ -0,0 +1,327 @@
+//! Resolve `import` statements and load the files into a `Program`.
+//!
+//! Files are loaded in the order that imported files come first.
+//!
+//! Each imported file is loaded in its own namespace, named after the file
+//! (eg. `stringUtil` for string_util.sk). Since the name starts with a
+//! lowercase letter, it cannot be written in the program; the toplevel
+//! names of the file are visible only from the files which import them.
+//! - `import "./foo.sk"` (or `require "./foo.sk"`) makes all the toplevel
+//!   names of foo.sk visible from the importing file.
+//! - `import "./foo.sk" as Foo` makes them visible as `Foo::...`.
+//! - `import A, B from "./foo.sk"` makes only `A` and `B` visible.
+//!
+//! The entry file is loaded in the toplevel namespace. If it is the
+//! `index.sk` of a package, the files it imports with `import "./foo.sk"`
+//! (and those imported from them in the same way) are also loaded in the
+//! toplevel namespace because they make up the toplevel of the package (eg.
+//! `Int`, `Array`, etc. of the core package.)
+//!
+//! An import path which does not start with `./` or `../` is a package name.
+//! Package `foo` is loaded from `foo/index.sk` in one of the package
+//! directories.
+use crate::error::{self, Error};
+use crate::{Parser, SourceFile};
+use shiika_ast::{
+    self as ast, Definition, Import, ImportKind, LocationSpan, TopLevelItem, Visibility,
+};
+use shiika_core::names::ModuleFirstname;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Read a .sk file (and those imported by it)
+pub fn load(path: &Path, package_dirs: &[PathBuf]) -> Result<ast::Program, Error> {
+    let content = fs::read_to_string(path).map_err(|e| {
+        let msg = format!("failed to load {}: {}", path.display(), e);
+        Error::LoadError(Box::new(msg.into()))
+    })?;
+    load_source(SourceFile::new(normalize(path), content), package_dirs)
+}
+
+/// Like `load`, but the content of the entry file is given
+pub fn load_source(file: SourceFile, package_dirs: &[PathBuf]) -> Result<ast::Program, Error> {
+    let mut loader = Loader {
+        package_dirs,
+        files: vec![],
+        loading: vec![],
+    };
+    loader.load_file(file)?;
+    loader.build_program()
+}
+
+/// A parsed file
+struct LoadedFile {
+    file: SourceFile,
+    program: ast::Program,
+    /// Imports in this file and the paths they are resolved to
+    deps: Vec<(Import, PathBuf)>,
+}
+
+struct Loader<'a> {
+    package_dirs: &'a [PathBuf],
+    /// Files loaded so far (imported files come first)
+    files: Vec<LoadedFile>,
+    /// Files being loaded (used to detect cycles)
+    loading: Vec<PathBuf>,
+}
+
+impl<'a> Loader<'a> {
+    fn load_file(&mut self, file: SourceFile) -> Result<(), Error> {
+        let mut program = Parser::new(&file).parse_program()?;
+        self.loading.push(file.path.to_path_buf());
+        let mut deps = vec![];
+        for import in std::mem::take(&mut program.imports) {
+            let dep = self.resolve(&file, &import)?;
+            if let Some(i) = self.loading.iter().position(|x| *x == dep) {
+                let cycle = self.loading[i..]
+                    .iter()
+                    .chain(std::iter::once(&dep))
+                    .map(|x| x.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+                let msg = format!("import cycle detected: {}", cycle);
+                return Err(load_error(&msg, &file, &import.locs));
+            }
+            if !self.is_loaded(&dep) {
+                let content = fs::read_to_string(&dep).map_err(|e| {
+                    let msg = format!("failed to load {}: {}", dep.display(), e);
+                    load_error(&msg, &file, &import.locs)
+                })?;
+                self.load_file(SourceFile::new(dep.clone(), content))?;
+            }
+            deps.push((import, dep));
+        }
+        self.loading.pop();
+        self.files.push(LoadedFile {
+            file,
+            program,
+            deps,
+        });
+        Ok(())
+    }
+
+    /// Returns the path of the file to be imported
+    fn resolve(&self, file: &SourceFile, import: &Import) -> Result<PathBuf, Error> {
+        if import.is_package() {
+            self.package_dirs
+                .iter()
+                .map(|dir| dir.join(&import.path).join("index.sk"))
+                .find(|path| path.is_file())
+                .map(|path| normalize(&path))
+                .ok_or_else(|| {
+                    let msg = format!("package `{}' not found", import.path);
+                    load_error(&msg, file, &import.locs)
+                })
+        } else {
+            let path = normalize(&file.path.with_file_name(&import.path));
+            if path.is_file() {
+                Ok(path)
+            } else {
+                let msg = format!("file `{}' not found", path.display());
+                Err(load_error(&msg, file, &import.locs))
+            }
+        }
+    }
+
+    fn is_loaded(&self, path: &Path) -> bool {
+        self.files.iter().any(|f| f.file.path.as_path() == path)
+    }
+
+    fn find_file(&self, path: &Path) -> &LoadedFile {
+        self.files
+            .iter()
+            .find(|f| f.file.path.as_path() == path)
+            .unwrap()
+    }
+
+    /// Decide namespaces of the files and merge them into one program
+    fn build_program(self) -> Result<ast::Program, Error> {
+        let namespaces = self.decide_namespaces();
+        let mut imported_names = ast::ImportedNames::default();
+        for f in &self.files {
+            for (import, dep) in &f.deps {
+                let ns = &namespaces[dep.as_path()];
+                let fullname = |name: &str| {
+                    ns.iter()
+                        .cloned()
+                        .chain(std::iter::once(name.to_string()))
+                        .collect::<Vec<_>>()
+                };
+                let path = f.file.path.to_path_buf();
+                let defined = self.find_file(dep).program.defs();
+                match &import.kind {
+                    ImportKind::All => {
+                        for def in &defined {
+                            if let Some(name) = toplevel_name(def) {
+                                if !is_private(def) {
+                                    imported_names.insert(
+                                        path.clone(),
+                                        name.clone(),
+                                        fullname(name),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    ImportKind::Namespace(alias) => {
+                        imported_names.insert(path, alias.clone(), ns.iter().cloned().collect());
+                    }
+                    ImportKind::Names(names) => {
+                        for (name, locs) in names {
+                            let Some(def) = defined.iter().find(|d| toplevel_name(d) == Some(name))
+                            else {
+                                let msg = format!("`{}' is not defined in {}", name, dep.display());
+                                return Err(load_error(&msg, &f.file, locs));
+                            };
+                            if is_private(def) {
+                                let msg = format!("`{}' is private in {}", name, dep.display());
+                                return Err(load_error(&msg, &f.file, locs));
+                            }
+                            imported_names.insert(path.clone(), name.clone(), fullname(name));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut program = ast::Program::default();
+        for f in self.files {
+            let mut items = f.program.toplevel_items;
+            if let Some(ns) = &namespaces[f.file.path.as_path()] {
+                if let Some(TopLevelItem::Expr(expr)) =
+                    items.iter().find(|x| matches!(x, TopLevelItem::Expr(_)))
+                {
+                    let msg = "toplevel expression is not allowed in an imported file";
+                    return Err(load_error(msg, &f.file, &expr.locs));
+                }
+                let defs = items
+                    .into_iter()
+                    .filter_map(|x| match x {
+                        TopLevelItem::Def(d) => Some(d),
+                        TopLevelItem::Expr(_) => None,
+                    })
+                    .collect();
+                let begin = ast::Location::new(0, 0, 0);
+                items = vec![TopLevelItem::Def(Definition::ModuleDefinition {
+                    name: ModuleFirstname(ns.clone()),
+                    typarams: vec![],
+                    defs,
+                    visibility: Visibility::Public,
+                    locs: LocationSpan::new(&f.file.path, begin.clone(), begin),
+                })];
+            }
+            program.toplevel_items.append(&mut items);
+        }
+        program.imported_names = imported_names;
+        Ok(program)
+    }
+
+    /// Returns the namespace of each file (`None` means the toplevel)
+    fn decide_namespaces(&self) -> HashMap<PathBuf, Option<String>> {
+        // The entry file (the last one) is always in the toplevel
+        let entry = self.files.last().unwrap();
+        let mut toplevel = vec![entry.file.path.to_path_buf()];
+        if entry.file.path.file_name() == Some("index.sk".as_ref()) {
+            let mut i = 0;
+            while i < toplevel.len() {
+                for (import, dep) in &self.find_file(&toplevel[i]).deps {
+                    if import.kind == ImportKind::All && !toplevel.contains(dep) {
+                        toplevel.push(dep.clone());
+                    }
+                }
+                i += 1;
+            }
+        }
+        let mut namespaces: HashMap<PathBuf, Option<String>> =
+            toplevel.into_iter().map(|path| (path, None)).collect();
+        for f in &self.files {
+            if namespaces.contains_key(f.file.path.as_path()) {
+                continue;
+            }
+            let base = namespace_name(&f.file.path);
+            let mut name = base.clone();
+            let mut i = 2;
+            while namespaces.values().any(|x| x.as_ref() == Some(&name)) {
+                name = format!("{}{}", base, i);
+                i += 1;
+            }
+            namespaces.insert(f.file.path.to_path_buf(), Some(name));
+        }
+        namespaces
+    }
+}
+
+/// Returns the name defined by a toplevel definition
+fn toplevel_name(def: &Definition) -> Option<&String> {
+    match def {
+        Definition::ClassDefinition { name, .. } => Some(&name.0),
+        Definition::ModuleDefinition { name, .. } => Some(&name.0),
+        Definition::EnumDefinition { name, .. } => Some(&name.0),
+        Definition::ConstDefinition { name, .. } => Some(name),
+        _ => None,
+    }
+}
+
+/// Returns true if `def` is declared with `private`
+fn is_private(def: &Definition) -> bool {
+    match def {
+        Definition::ClassDefinition { visibility, .. }
+        | Definition::ModuleDefinition { visibility, .. }
+        | Definition::EnumDefinition { visibility, .. }
+        | Definition::ConstDefinition { visibility, .. } => *visibility == Visibility::Private,
+        _ => false,
+    }
+}
+
+/// Create a namespace name from a file path (eg. `./string_util.sk` ->
+/// `stringUtil`, `foo/index.sk` -> `foo`.) The name starts with a lowercase
+/// letter so that it does not conflict with the names in the program.
+fn namespace_name(path: &Path) -> String {
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    let name = if stem == "index" {
+        path.parent()
+            .and_then(|p| p.file_name())
+            .map(|s| s.to_string_lossy())
+            .unwrap_or(stem)
+    } else {
+        stem
+    };
+    let camel = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let mut cs = s.chars();
+            let head = cs.next().unwrap().to_ascii_uppercase();
+            std::iter::once(head).chain(cs).collect::<String>()
+        })
+        .collect::<String>();
+    let mut cs = camel.chars();
+    match cs.next() {
+        Some(head) => std::iter::once(head.to_ascii_lowercase())
+            .chain(cs)
+            .collect(),
+        None => "file".to_string(),
+    }
+}
+
+/// Remove `.` and `..` in the path (without accessing the filesystem)
+fn normalize(path: &Path) -> PathBuf {
+    let mut ret = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => (),
+            Component::ParentDir
+                if matches!(ret.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                ret.pop();
+            }
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
+
+fn load_error(msg: &str, file: &SourceFile, locs: &LocationSpan) -> Error {
+    Error::LoadError(error::build_report(msg, &file.content, locs))
+}
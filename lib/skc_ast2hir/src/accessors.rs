@@ -1,9 +1,14 @@
 use crate::hir_maker::HirMaker;
+use crate::type_system::variance;
+use shiika_ast::LocationSpan;
 use shiika_core::names::*;
 use skc_hir::*;
 
 impl<'hir_maker> HirMaker<'hir_maker> {
-    /// Define getters and setters (unless there is a method of the same name)
+    /// Define getters and setters (unless there is a method of the same name.)
+    /// Setters are not defined for `let` ivars and the ones which break the
+    /// variance of the class type parameters (eg. `value=` of
+    /// `class Box<out T>` with `@value: T`.)
     pub(super) fn define_accessors(
         &mut self,
         clsname: &ClassFullname,
@@ -31,15 +36,26 @@ impl<'hir_maker> HirMaker<'hir_maker> {
             }
 
             let setter_name = format!("{}=", accessor_name);
-            if !method_names.iter().any(|x| ***x == setter_name) {
+            let setter_sig = create_setter_signature(clsname, ivar);
+            if !ivar.readonly
+                && !method_names.iter().any(|x| ***x == setter_name)
+                && self.obeys_variance(clsname, &setter_sig)
+            {
                 let setter = create_setter(clsname, ivar);
-                let setter_sig = create_setter_signature(clsname, ivar);
                 self.method_dict
                     .add_method(clsname.to_type_fullname(), setter);
                 self.class_dict.add_method(setter_sig);
             }
         }
     }
+
+    fn obeys_variance(&self, clsname: &ClassFullname, sig: &MethodSignature) -> bool {
+        let typarams = self
+            .class_dict
+            .get_typarams(&clsname.to_type_fullname())
+            .unwrap_or(&[]);
+        variance::check_signature(&self.class_dict, typarams, sig, &LocationSpan::Empty).is_ok()
+    }
 }
 
 fn create_getter_signature(clsname: &ClassFullname, ivar: &SkIVar) -> MethodSignature {
@@ -51,6 +67,7 @@ fn create_getter_signature(clsname: &ClassFullname, ivar: &SkIVar) -> MethodSign
         asyncness: Asyncness::Sync,
         is_virtual: false,
         is_rust: false,
+        is_private: false,
     }
 }
 
@@ -82,6 +99,7 @@ fn create_setter_signature(clsname: &ClassFullname, ivar: &SkIVar) -> MethodSign
         asyncness: Asyncness::Sync,
         is_virtual: false,
         is_rust: false,
+        is_private: false,
     }
 }
 
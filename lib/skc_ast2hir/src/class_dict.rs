@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 mod build_wtable;
 mod found_method;
 mod indexing;
@@ -7,7 +7,7 @@ pub mod type_index;
 use anyhow::Result;
 pub use found_method::{CallType, FoundMethod};
 pub use indexing::RustMethods;
-use shiika_ast::{self, AstMethodSignature};
+use shiika_ast::{self, AstMethodSignature, LocationSpan};
 use shiika_core::names::*;
 use skc_hir::*;
 use type_index::TypeIndex;
@@ -22,6 +22,10 @@ pub struct ClassDict<'hir_maker> {
     pub sk_types: SkTypes,
     /// Imported classes (TODO: Rename to `imported_types`)
     pub imported_classes: &'hir_maker SkTypes,
+    /// Names imported with `import A from "..."`
+    pub imported_names: shiika_ast::ImportedNames,
+    /// Constants (including nested types) defined with `private`
+    pub private_consts: HashSet<ConstFullname>,
 }
 
 pub fn new<'hir_maker>(
@@ -32,6 +36,8 @@ pub fn new<'hir_maker>(
         type_index,
         sk_types: Default::default(),
         imported_classes,
+        imported_names: Default::default(),
+        private_consts: Default::default(),
     }
 }
 
@@ -39,11 +45,14 @@ pub fn create<'hir_maker>(
     defs: &[&shiika_ast::Definition],
     type_index: TypeIndex,
     imported_classes: &'hir_maker SkTypes,
+    imported_names: shiika_ast::ImportedNames,
 ) -> Result<ClassDict<'hir_maker>> {
     let mut dict = ClassDict {
         type_index,
         sk_types: Default::default(),
         imported_classes,
+        imported_names,
+        private_consts: Default::default(),
     };
     dict.index_program(defs, HashMap::new())?;
     Ok(dict)
@@ -59,6 +68,8 @@ pub fn create_for_corelib<'hir_maker>(
         type_index,
         sk_types,
         imported_classes,
+        imported_names: Default::default(),
+        private_consts: Default::default(),
     };
     dict.index_program(defs, index_rust_method_sigs())?;
     Ok(dict)
@@ -76,6 +87,15 @@ fn index_rust_method_sigs() -> indexing::RustMethods {
 }
 
 impl<'hir_maker> ClassDict<'hir_maker> {
+    /// If the first part of `names` is imported into the file where `locs`
+    /// is in, returns the full name.
+    pub fn imported_name(&self, names: &[String], locs: &LocationSpan) -> Option<Vec<String>> {
+        let first = names.first()?;
+        self.imported_names
+            .lookup(locs, first)
+            .map(|fullname| fullname.iter().chain(names[1..].iter()).cloned().collect())
+    }
+
     /// Define ivars of a class
     pub fn define_ivars(&mut self, classname: &ClassFullname, own_ivars: HashMap<String, SkIVar>) {
         let superclass = &self.get_class(classname).superclass.clone();
@@ -99,6 +119,18 @@ impl<'hir_maker> ClassDict<'hir_maker> {
             class.ivars.insert(k, v);
         });
     }
+
+    /// Set the return type of a method (used when it is inferred)
+    pub fn set_method_ret_ty(
+        &mut self,
+        fullname: &MethodFullname,
+        ret_ty: shiika_core::ty::TermTy,
+    ) {
+        let sk_type = self.get_type_mut(&fullname.type_name);
+        if let Some((sig, _)) = sk_type.base_mut().method_sigs.get_mut(&fullname.first_name) {
+            sig.ret_ty = ret_ty;
+        }
+    }
 }
 
 /// Returns signature of `#initialize` inherited from generic class
@@ -1,15 +1,17 @@
 use crate::class_dict::build_wtable::build_wtable;
 use crate::class_dict::*;
 use crate::convert_exprs::params;
+use crate::enum_equality;
 use crate::error;
 use crate::parse_typarams;
+use crate::type_system::variance;
 use anyhow::Result;
-use shiika_ast::{self, LocationSpan, UnresolvedTypeName};
+use shiika_ast::{self, LocationSpan, UnresolvedTypeName, Visibility};
 use shiika_core::{names::*, ty, ty::*};
 use skc_error::{self, Label};
 use skc_hir::method_signature::{signature_of_new, MethodSignature};
 use skc_hir::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Used during indexing.
 /// To register Rust methods, the class must be already indexed, but for #initialize, we want
@@ -50,6 +52,9 @@ impl<'hir_maker> ClassDict<'hir_maker> {
         mut rust_methods: RustMethods,
     ) -> Result<()> {
         let namespace = Namespace::root();
+        for def in toplevel_defs {
+            collect_private_consts(&mut self.private_consts, &namespace, def);
+        }
         for def in toplevel_defs {
             match def {
                 shiika_ast::Definition::ClassDefinition {
@@ -58,11 +63,12 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     typarams,
                     supers,
                     defs,
+                    ..
                 } => self.index_class(
                     *inheritable,
                     &namespace,
                     name,
-                    parse_typarams(typarams),
+                    self.resolve_typarams(&namespace, typarams, None)?,
                     supers,
                     defs,
                     &mut rust_methods,
@@ -71,10 +77,11 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     name,
                     typarams,
                     defs,
+                    ..
                 } => self.index_module(
                     &namespace,
                     name,
-                    parse_typarams(typarams),
+                    self.resolve_typarams(&namespace, typarams, None)?,
                     defs,
                     &mut rust_methods,
                 )?,
@@ -83,17 +90,18 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     typarams,
                     cases,
                     defs,
+                    ..
                 } => self.index_enum(
                     &namespace,
                     name,
-                    parse_typarams(typarams),
+                    self.resolve_typarams(&namespace, typarams, None)?,
                     cases,
                     defs,
                     &mut rust_methods,
                 )?,
                 shiika_ast::Definition::ConstDefinition { .. } => (),
                 _ => {
-                    return Err(error::syntax_error(&format!(
+                    return Err(error::syntax_error(format!(
                         "must not be toplevel: {:?}",
                         def
                     )))
@@ -310,9 +318,15 @@ impl<'hir_maker> ClassDict<'hir_maker> {
             },
             rust_methods,
         )?;
-        for case in cases {
-            self.index_enum_case(namespace, &fullname, &typarams, case)?;
+        for (idx, case) in cases.iter().enumerate() {
+            self.index_enum_case(namespace, &fullname, &typarams, idx, case, defs)?;
         }
+        self.get_class_mut(&fullname).enum_cases = Some(
+            cases
+                .iter()
+                .map(|case| case.name.add_namespace(&fullname.0))
+                .collect(),
+        );
 
         Ok(())
     }
@@ -322,7 +336,9 @@ impl<'hir_maker> ClassDict<'hir_maker> {
         namespace: &Namespace,
         enum_fullname: &ClassFullname,
         typarams: &[ty::TyParam],
+        case_idx: usize,
         case: &shiika_ast::EnumCase,
+        enum_defs: &[shiika_ast::Definition],
     ) -> Result<()> {
         let ivar_list = self._enum_case_ivars(namespace, typarams, case)?;
         let fullname = case.name.add_namespace(&enum_fullname.0);
@@ -331,6 +347,19 @@ impl<'hir_maker> ClassDict<'hir_maker> {
 
         let mut instance_methods = enum_case_getters(&fullname, &ivar_list);
         instance_methods.insert(initialize_sig);
+        for def in enum_equality::case_defs(&fullname, case_idx, case, enum_defs) {
+            if let shiika_ast::Definition::InstanceMethodDefinition { sig, .. } = &def {
+                instance_methods.insert(self.create_maybe_virtual_signature(
+                    false,
+                    namespace,
+                    fullname.to_type_fullname(),
+                    sig,
+                    &case_typarams,
+                    &Some(superclass.clone()),
+                    false,
+                )?);
+            }
+        }
 
         self.add_new_class(
             ClassSpec {
@@ -448,6 +477,7 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     if sig.name.0 == "initialize" {
                         self._index_accessors(&mut instance_methods, sig, &hir_sig);
                     }
+                    variance::check_signature(self, typarams, &hir_sig, &sig.locs)?;
                     instance_methods.insert(hir_sig);
                 }
                 shiika_ast::Definition::ClassMethodDefinition { sig, .. } => {
@@ -487,12 +517,13 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     typarams,
                     supers,
                     defs,
+                    ..
                 } => {
                     self.index_class(
                         *inheritable,
                         namespace,
                         name,
-                        parse_typarams(typarams),
+                        self.resolve_typarams(namespace, typarams, None)?,
                         supers,
                         defs,
                         rust_methods,
@@ -502,11 +533,12 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     name,
                     typarams,
                     defs,
+                    ..
                 } => {
                     self.index_module(
                         namespace,
                         name,
-                        parse_typarams(typarams),
+                        self.resolve_typarams(namespace, typarams, None)?,
                         defs,
                         rust_methods,
                     )?;
@@ -521,9 +553,10 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                             false,
                             false,
                         )?;
+                        variance::check_signature(self, typarams, &hir_sig, &sig.locs)?;
                         requirements.push(hir_sig);
                     } else {
-                        return Err(error::syntax_error(&format!(
+                        return Err(error::syntax_error(format!(
                             "only modules have method requirement: {:?} {:?} {:?}",
                             namespace, fullname, sig
                         )));
@@ -534,11 +567,12 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     typarams,
                     cases,
                     defs,
+                    ..
                 } => {
                     self.index_enum(
                         namespace,
                         name,
-                        parse_typarams(typarams),
+                        self.resolve_typarams(namespace, typarams, None)?,
                         cases,
                         defs,
                         rust_methods,
@@ -569,6 +603,7 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                 asyncness: Asyncness::Sync,
                 is_virtual: false,
                 is_rust: false,
+                is_private: false,
             };
             instance_methods.insert(sig);
         }
@@ -589,7 +624,9 @@ impl<'hir_maker> ClassDict<'hir_maker> {
 
         let sk_type = {
             if self.known(&fullname_) {
-                // predefined as bootstrap
+                // predefined as bootstrap. Take the variance from the definition
+                let sk_type = self.sk_types.types.get_mut(&fullname_).unwrap();
+                sk_type.base_mut().typarams = c.typarams.clone();
             } else {
                 let ivars = self.superclass_ivars(&c.superclass).unwrap_or_default();
                 let base = SkTypeBase {
@@ -606,6 +643,7 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     inheritable: Default::default(),
                     const_is_obj: c.const_is_obj,
                     wtable: Default::default(),
+                    enum_cases: None,
                 });
             }
             self.sk_types.types.get_mut(&fullname_).unwrap()
@@ -649,6 +687,7 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     inheritable: false,
                     const_is_obj: false,
                     wtable: Default::default(),
+                    enum_cases: None,
                 });
             }
             self.sk_types.types.get_mut(&meta_name).unwrap()
@@ -738,6 +777,7 @@ impl<'hir_maker> ClassDict<'hir_maker> {
                     inheritable: false,
                     const_is_obj: false,
                     wtable: Default::default(),
+                    enum_cases: None,
                 });
             }
             self.sk_types.types.get_mut(&meta_name).unwrap()
@@ -786,7 +826,8 @@ impl<'hir_maker> ClassDict<'hir_maker> {
         is_virtual: bool,
         is_rust: bool,
     ) -> Result<MethodSignature> {
-        let method_typarams = parse_typarams(&sig.typarams);
+        let method_typarams =
+            self.resolve_typarams(namespace, &sig.typarams, Some(class_typarams))?;
         let fullname = method_fullname(type_fullname, &sig.name.0);
         let ret_ty = if let Some(typ) = &sig.ret_typ {
             self.resolve_typename(namespace, class_typarams, &method_typarams, typ)?
@@ -812,9 +853,44 @@ impl<'hir_maker> ClassDict<'hir_maker> {
             asyncness,
             is_virtual,
             is_rust,
+            is_private: sig.visibility == Visibility::Private,
         })
     }
 
+    /// Convert AstTyParam to TyParam with the upper bounds resolved.
+    /// `class_typarams` is given when converting the typarams of a method.
+    pub fn resolve_typarams(
+        &self,
+        namespace: &Namespace,
+        ast_typarams: &[shiika_ast::AstTyParam],
+        class_typarams: Option<&[ty::TyParam]>,
+    ) -> Result<Vec<ty::TyParam>> {
+        let mut typarams = parse_typarams(ast_typarams);
+        // The bounds may refer to the typarams being defined
+        // (eg. `T : Comparable<T>`)
+        let unbounded = typarams.clone();
+        let (cls_typarams, mth_typarams) = match class_typarams {
+            Some(c) => (c, unbounded.as_slice()),
+            None => (unbounded.as_slice(), Default::default()),
+        };
+        for (typaram, ast_typaram) in typarams.iter_mut().zip(ast_typarams) {
+            let Some(name) = &ast_typaram.upper_bound else {
+                continue;
+            };
+            let bound = self.resolve_typename(namespace, cls_typarams, mth_typarams, name)?;
+            match bound.body {
+                TyBody::TyRaw(lit_ty) => typaram.upper_bound = lit_ty,
+                TyBody::TyPara(_) => {
+                    return Err(error::type_error(format!(
+                        "the upper bound of `{}' must be a class or a module",
+                        typaram.name
+                    )))
+                }
+            }
+        }
+        Ok(typarams)
+    }
+
     /// Resolve the given type name to fullname
     pub fn resolve_typename(
         &self,
@@ -827,9 +903,11 @@ impl<'hir_maker> ClassDict<'hir_maker> {
         if name.args.is_empty() && name.names.len() == 1 {
             let s = name.names.first().unwrap();
             if let Some(idx) = class_typarams.iter().position(|t| *s == t.name) {
-                return Ok(ty::typaram_ref(s, TyParamKind::Class, idx).into_term_ty());
+                let tpref = class_typarams[idx].to_typaram_ref(TyParamKind::Class, idx);
+                return Ok(tpref.into_term_ty());
             } else if let Some(idx) = method_typarams.iter().position(|t| *s == t.name) {
-                return Ok(ty::typaram_ref(s, TyParamKind::Method, idx).into_term_ty());
+                let tpref = method_typarams[idx].to_typaram_ref(TyParamKind::Method, idx);
+                return Ok(tpref.into_term_ty());
             }
         }
         // Otherwise:
@@ -858,12 +936,24 @@ impl<'hir_maker> ClassDict<'hir_maker> {
     ) -> Result<(Vec<String>, &[TyParam])> {
         let n = namespace.size();
         for k in 0..=n {
+            if k == n {
+                // Names imported into this file take precedence over the toplevel
+                if let Some(resolved) = self.imported_name(names, locs) {
+                    if let Some(typarams) = self
+                        .type_index
+                        .get(&class_fullname(resolved.join("::")).into())
+                    {
+                        return Ok((resolved, typarams));
+                    }
+                }
+            }
             let mut resolved = namespace.head(n - k).to_vec();
             resolved.append(&mut names.to_vec());
             if let Some(typarams) = self
                 .type_index
                 .get(&class_fullname(resolved.join("::")).into())
             {
+                self.check_const_visibility(&resolved, namespace, locs)?;
                 return Ok((resolved, typarams));
             }
         }
@@ -872,7 +962,7 @@ impl<'hir_maker> ClassDict<'hir_maker> {
         let report = skc_error::build_report(msg, locs, |r, locs_span| {
             r.with_label(Label::new(locs_span).with_message("unknown type"))
         });
-        Err(error::name_error(&report))
+        Err(error::name_error(report))
     }
 
     fn transfer_rust_methods(
@@ -930,7 +1020,7 @@ fn enum_case_superclass(
         .map(|(i, t)| {
             if case.appears(&t.name) {
                 case_typarams.push(t.clone());
-                ty::typaram_ref(&t.name, TyParamKind::Class, i).into_term_ty()
+                t.to_typaram_ref(TyParamKind::Class, i).into_term_ty()
             } else {
                 ty::raw("Never")
             }
@@ -960,6 +1050,45 @@ fn enum_case_new_sig(
     )
 }
 
+/// Collect the names of constants and nested types defined with `private`
+fn collect_private_consts(
+    consts: &mut HashSet<ConstFullname>,
+    namespace: &Namespace,
+    def: &shiika_ast::Definition,
+) {
+    let (name, visibility, defs) = match def {
+        shiika_ast::Definition::ClassDefinition {
+            name,
+            visibility,
+            defs,
+            ..
+        }
+        | shiika_ast::Definition::EnumDefinition {
+            name,
+            visibility,
+            defs,
+            ..
+        } => (&name.0, visibility, defs.as_slice()),
+        shiika_ast::Definition::ModuleDefinition {
+            name,
+            visibility,
+            defs,
+            ..
+        } => (&name.0, visibility, defs.as_slice()),
+        shiika_ast::Definition::ConstDefinition {
+            name, visibility, ..
+        } => (name, visibility, Default::default()),
+        _ => return,
+    };
+    if *visibility == Visibility::Private {
+        consts.insert(namespace.const_fullname(name));
+    }
+    let inner_namespace = namespace.add(name.clone());
+    for def in defs {
+        collect_private_consts(consts, &inner_namespace, def);
+    }
+}
+
 /// Create signatures of getters of an enum case
 fn enum_case_getters(case_fullname: &ClassFullname, ivars: &[SkIVar]) -> MethodSignatures {
     let iter = ivars.iter().map(|ivar| MethodSignature {
@@ -970,6 +1099,7 @@ fn enum_case_getters(case_fullname: &ClassFullname, ivars: &[SkIVar]) -> MethodS
         asyncness: Asyncness::Unknown,
         is_virtual: false,
         is_rust: false,
+        is_private: false,
     });
     MethodSignatures::from_iterator(iter)
 }
@@ -86,6 +86,19 @@ impl<'hir_maker> ClassDict<'hir_maker> {
             TyBody::TyRaw(LitTy { type_args, .. }) => {
                 (current_type.erasure(), type_args.as_slice())
             }
+            // Methods of the upper bound can be called on a type parameter
+            TyBody::TyPara(TyParamRef {
+                upper_bound,
+                as_class: false,
+                ..
+            }) => {
+                return self.lookup_method_(
+                    receiver_type,
+                    &upper_bound.to_term_ty(),
+                    method_name,
+                    locs,
+                );
+            }
             TyBody::TyPara(_) => (Erasure::nonmeta("Object"), Default::default()),
         };
         let sk_type = self.get_type(&erasure.to_type_fullname());
@@ -130,6 +143,50 @@ impl<'hir_maker> ClassDict<'hir_maker> {
         ))
     }
 
+    /// Returns error if `found` is a private method and the receiver is
+    /// given explicitly. (Calling with `self.` is allowed.)
+    pub fn check_method_visibility(
+        &self,
+        found: &FoundMethod,
+        explicit_receiver: bool,
+        locs: &LocationSpan,
+    ) -> Result<()> {
+        if found.sig.is_private && explicit_receiver {
+            return Err(error::private_method_called(&found.sig, locs));
+        }
+        Ok(())
+    }
+
+    /// Returns error if the constant `names` (or any of its namespace) is
+    /// private and referred from outside of the namespace where it is defined.
+    pub fn check_const_visibility(
+        &self,
+        names: &[String],
+        namespace: &Namespace,
+        locs: &LocationSpan,
+    ) -> Result<()> {
+        for i in 1..=names.len() {
+            let owner = &names[..i - 1];
+            if self
+                .private_consts
+                .contains(&ConstFullname::new(names[..i].to_vec()))
+                && !namespace.0.starts_with(owner)
+            {
+                return Err(error::private_constant_referred(
+                    &names[..i].join("::"),
+                    locs,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the type parameters of the type, if it is known. Unlike
+    /// `find_type`, this works for the types not indexed yet.
+    pub fn get_typarams(&self, fullname: &TypeFullname) -> Option<&[TyParam]> {
+        self.type_index.get(fullname).map(|v| v.as_slice())
+    }
+
     /// Return the class/module of the specified name, if any
     pub fn find_type(&self, fullname: &TypeFullname) -> Option<&SkType> {
         self.sk_types
@@ -19,12 +19,52 @@ impl<'hir_maker> HirMaker<'hir_maker> {
     pub(super) fn convert_exprs(&mut self, exprs: &[AstExpression]) -> Result<HirExpression> {
         let hir_exprs = exprs
             .iter()
-            .map(|expr| self.convert_expr(expr))
+            .enumerate()
+            .map(|(i, expr)| self.convert_expr_in_seq(expr, &exprs[i + 1..]))
             .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Hir::expressions(hir_exprs))
     }
 
+    /// Convert an expression with the hint of its type. Currently the hint
+    /// is used for lambdas without parameter types and `Foo.new` where
+    /// `Foo` is a generic class.
+    pub(super) fn convert_expr_expecting(
+        &mut self,
+        expr: &AstExpression,
+        expected: &TermTy,
+    ) -> Result<HirExpression> {
+        match &expr.body {
+            AstExpressionBody::LambdaExpr {
+                params,
+                exprs,
+                is_fn,
+            } => {
+                let param_tys = match expected.fn_x_info() {
+                    Some(tys) if tys.len() == params.len() + 1 => tys[..params.len()].to_vec(),
+                    _ => vec![],
+                };
+                self.convert_lambda_expr(params, exprs, is_fn, &param_tys, &expr.locs)
+            }
+            AstExpressionBody::MethodCall(AstMethodCall {
+                receiver_expr,
+                method_name,
+                args,
+                type_args,
+                ..
+            }) => method_call::convert_method_call(
+                self,
+                receiver_expr,
+                method_name,
+                args,
+                type_args,
+                Some(expected),
+                &expr.locs,
+            ),
+            _ => self.convert_expr(expr),
+        }
+    }
+
     pub(super) fn convert_expr(&mut self, expr: &AstExpression) -> Result<HirExpression> {
         // Debug helper: print the expr under processing
         //println!(
@@ -66,7 +106,7 @@ impl<'hir_maker> HirMaker<'hir_maker> {
                 name,
                 rhs,
                 readonly,
-            } => self.convert_lvar_decl(name, rhs, readonly, &expr.locs),
+            } => self.convert_lvar_decl(name, rhs, readonly, None, &expr.locs),
 
             AstExpressionBody::LVarAssign { name, rhs } => {
                 self.convert_lvar_assign(name, rhs, &expr.locs)
@@ -92,14 +132,24 @@ impl<'hir_maker> HirMaker<'hir_maker> {
                 args,
                 type_args,
                 ..
-            }) => method_call::convert_method_call(
-                self,
-                receiver_expr,
-                method_name,
-                args,
-                type_args,
-                &expr.locs,
-            ),
+            }) => {
+                // `a[1..3]` is a shorthand for `a.slice(1..3)`
+                let slice = method_firstname("slice");
+                let method_name = if method_name.0 == "[]" && is_range_arg(args) {
+                    &slice
+                } else {
+                    method_name
+                };
+                method_call::convert_method_call(
+                    self,
+                    receiver_expr,
+                    method_name,
+                    args,
+                    type_args,
+                    None,
+                    &expr.locs,
+                )
+            }
 
             AstExpressionBody::LambdaInvocation { fn_expr, args } => {
                 let hir_fn_expr = self.convert_expr(fn_expr)?;
@@ -110,7 +160,7 @@ impl<'hir_maker> HirMaker<'hir_maker> {
                 params,
                 exprs,
                 is_fn,
-            } => self.convert_lambda_expr(params, exprs, is_fn, &expr.locs),
+            } => self.convert_lambda_expr(params, exprs, is_fn, Default::default(), &expr.locs),
 
             // Note: there is no `AstExpressionBody::LVarRef` because it is included in this.
             AstExpressionBody::BareName(name) => self.convert_bare_name(name, &expr.locs),
@@ -131,6 +181,12 @@ impl<'hir_maker> HirMaker<'hir_maker> {
 
             AstExpressionBody::ArrayLiteral(exprs) => self.convert_array_literal(exprs, &expr.locs),
 
+            AstExpressionBody::RangeLiteral {
+                first,
+                last,
+                exclusive,
+            } => self.convert_range_literal(first, last, *exclusive, &expr.locs),
+
             AstExpressionBody::FloatLiteral { value } => {
                 Ok(Hir::float_literal(*value, expr.locs.clone()))
             }
@@ -141,6 +197,10 @@ impl<'hir_maker> HirMaker<'hir_maker> {
 
             AstExpressionBody::StringLiteral { content } => {
                 Ok(self.convert_string_literal(content, &expr.locs))
+            }
+
+            AstExpressionBody::RegexLiteral { source } => {
+                self.convert_regex_literal(source, &expr.locs)
             } //x => panic!("TODO: {:?}", x)
         }
     }
@@ -332,7 +392,11 @@ impl<'hir_maker> HirMaker<'hir_maker> {
                 LocationSpan::todo(),
             )
         };
-        let merge_ty = self._validate_return_type(&arg_expr.ty, locs)?;
+        let merge_ty = if self.record_return_ty(&arg_expr.ty) {
+            arg_expr.ty.clone()
+        } else {
+            self._validate_return_type(&arg_expr.ty, locs)?
+        };
         let cast = Hir::bit_cast(merge_ty, arg_expr);
         Ok(Hir::return_expression(from, cast, locs.clone()))
     }
@@ -377,17 +441,22 @@ impl<'hir_maker> HirMaker<'hir_maker> {
 
     /// Local variable declaration
     /// `let a = ...` or `var a = ...`
-    fn convert_lvar_decl(
+    /// `expected` is the type of the lvar inferred from its later uses (if any)
+    pub(super) fn convert_lvar_decl(
         &mut self,
         name: &str,
         rhs: &AstExpression,
         readonly: &bool,
+        expected: Option<&TermTy>,
         locs: &LocationSpan,
     ) -> Result<HirExpression> {
         if self._lookup_var(name, locs.clone())?.is_some() {
             return Err(error::lvar_redeclaration(name, locs));
         }
-        let expr = self.convert_expr(rhs)?;
+        let expr = match expected {
+            Some(t) => self.convert_expr_expecting(rhs, t)?,
+            None => self.convert_expr(rhs)?,
+        };
         self.ctx_stack
             .declare_lvar(name, expr.ty.clone(), *readonly);
         Ok(Hir::lvar_decl(
@@ -547,11 +616,14 @@ impl<'hir_maker> HirMaker<'hir_maker> {
         Ok(Hir::const_assign(fullname, hir_expr, locs.clone()))
     }
 
+    /// Convert a lambda expression. Types of the parameters are taken from
+    /// `inferred_param_tys` if not specified.
     pub(super) fn convert_lambda_expr(
         &mut self,
         params: &[shiika_ast::BlockParam],
         exprs: &[AstExpression],
         is_fn: &bool,
+        inferred_param_tys: &[TermTy],
         locs: &LocationSpan,
     ) -> Result<HirExpression> {
         let namespace = self.ctx_stack.const_scopes().next().unwrap();
@@ -561,7 +633,8 @@ impl<'hir_maker> HirMaker<'hir_maker> {
             params,
             &self.ctx_stack.current_class_typarams(),
             &self.ctx_stack.current_method_typarams(),
-            Default::default(),
+            inferred_param_tys,
+            locs,
         )?;
 
         // Convert lambda body
@@ -661,6 +734,7 @@ impl<'hir_maker> HirMaker<'hir_maker> {
             &method_firstname(name),
             &Default::default(),
             Default::default(),
+            None,
             locs,
         )
     }
@@ -866,10 +940,31 @@ impl<'hir_maker> HirMaker<'hir_maker> {
             }
         }
 
+        let current_namespace = self.ctx_stack.const_scopes().next().unwrap();
         for namespace in self.ctx_stack.const_scopes() {
+            if namespace.is_root() {
+                // Names imported into this file take precedence over the toplevel
+                if let Some(names) = self.class_dict.imported_name(&name.0, locs) {
+                    let resolved = resolved_const_name(namespace.clone(), names);
+                    let full = resolved.to_const_fullname();
+                    if let Some(ty) = self._lookup_const(&full) {
+                        self.class_dict.check_const_visibility(
+                            &resolved.names,
+                            &current_namespace,
+                            locs,
+                        )?;
+                        return Ok(Hir::const_ref(ty, full, locs.clone()));
+                    }
+                }
+            }
             let resolved = resolved_const_name(namespace, name.0.to_vec());
             let full = resolved.to_const_fullname();
             if let Some(ty) = self._lookup_const(&full) {
+                self.class_dict.check_const_visibility(
+                    &resolved.names,
+                    &current_namespace,
+                    locs,
+                )?;
                 return Ok(Hir::const_ref(ty, full, locs.clone()));
             }
         }
@@ -956,6 +1051,13 @@ impl<'hir_maker> HirMaker<'hir_maker> {
             .class_dict
             .get_type(&base_expr.ty.instance_ty().base_type_name());
         type_checking::check_class_specialization(sk_type, &arg_exprs, locs)?;
+        type_checking::check_tyarg_bounds(
+            &self.class_dict,
+            &sk_type.base().typarams,
+            &type_args,
+            &type_args,
+            locs,
+        )?;
 
         let meta_spe_ty = base_expr.ty.specialized_ty(type_args);
         Ok(Hir::method_call(
@@ -1027,6 +1129,73 @@ impl<'hir_maker> HirMaker<'hir_maker> {
         }
     }
 
+    /// Generate HIR for a range literal (`Range.new(first, last, exclusive)`)
+    fn convert_range_literal(
+        &mut self,
+        first: &AstExpression,
+        last: &AstExpression,
+        exclusive: bool,
+        locs: &LocationSpan,
+    ) -> Result<HirExpression> {
+        let expr = |body| AstExpression {
+            body,
+            primary: true,
+            locs: locs.clone(),
+        };
+        let receiver = expr(AstExpressionBody::CapitalizedName(UnresolvedConstName(
+            vec!["Range".to_string()],
+        )));
+        let token = if exclusive {
+            Token::KwTrue
+        } else {
+            Token::KwFalse
+        };
+        let mut args = AstCallArgs::new();
+        args.unnamed = vec![
+            first.clone(),
+            last.clone(),
+            expr(AstExpressionBody::PseudoVariable(token)),
+        ];
+        method_call::convert_method_call(
+            self,
+            &Some(Box::new(receiver)),
+            &method_firstname("new"),
+            &args,
+            &[],
+            None,
+            locs,
+        )
+    }
+
+    /// Convert `/source/` into `Regex.new("source")`
+    fn convert_regex_literal(
+        &mut self,
+        source: &str,
+        locs: &LocationSpan,
+    ) -> Result<HirExpression> {
+        let expr = |body| AstExpression {
+            body,
+            primary: true,
+            locs: locs.clone(),
+        };
+        let receiver = expr(AstExpressionBody::CapitalizedName(UnresolvedConstName(
+            vec!["Regex".to_string()],
+        )));
+        let mut args = AstCallArgs::new();
+        args.unnamed = vec![expr(AstExpressionBody::StringLiteral {
+            content: source.to_string(),
+        })];
+        method_call::convert_method_call(
+            self,
+            &Some(Box::new(receiver)),
+            &method_firstname("new"),
+            &args,
+            &[],
+            None,
+            locs,
+        )
+    }
+
     pub fn create_array_instance(
         &mut self,
         item_exprs: Vec<HirExpression>,
@@ -1139,3 +1308,16 @@ impl<'hir_maker> HirMaker<'hir_maker> {
         idx
     }
 }
+
+/// Returns true if `args` is a range literal (eg. `1..3`)
+fn is_range_arg(args: &AstCallArgs) -> bool {
+    args.named.is_empty()
+        && args.block.is_none()
+        && matches!(
+            args.unnamed.as_slice(),
+            [AstExpression {
+                body: AstExpressionBody::RangeLiteral { .. },
+                ..
+            }]
+        )
+}
@@ -122,6 +122,7 @@ fn _convert_block(
         &mk.ctx_stack.current_class_typarams(),
         &mk.ctx_stack.current_method_typarams(),
         inferred_block_param_tys,
+        &locs,
     )?;
 
     // Convert lambda body
@@ -142,7 +143,14 @@ fn _convert_block(
 }
 
 pub fn lambda_ty(params: &[MethodParam], ret_ty: &TermTy) -> TermTy {
-    let mut tyargs = params.iter().map(|x| x.ty.clone()).collect::<Vec<_>>();
-    tyargs.push(ret_ty.clone());
-    ty::spe(&format!("Fn{}", params.len()), tyargs)
+    let param_tys = params.iter().map(|x| x.ty.clone()).collect::<Vec<_>>();
+    lambda_ty_with(param_tys, ret_ty.clone())
+}
+
+/// Returns the type `FnX<...>` of the given parameter types
+pub fn lambda_ty_with(param_tys: Vec<TermTy>, ret_ty: TermTy) -> TermTy {
+    let n = param_tys.len();
+    let mut tyargs = param_tys;
+    tyargs.push(ret_ty);
+    ty::spe(&format!("Fn{}", n), tyargs)
 }
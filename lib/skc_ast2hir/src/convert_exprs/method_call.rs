@@ -3,26 +3,37 @@ use crate::convert_exprs::{block, block::BlockTaker};
 use crate::error;
 use crate::hir_maker::HirMaker;
 use crate::pattern_match;
+use crate::type_inference::lvar_ty;
 use crate::type_inference::Infer;
-use crate::type_system::type_checking;
+use crate::type_system::{sendable, type_checking};
 use anyhow::{Context, Result};
-use shiika_ast::{AstCallArgs, AstExpression, LocationSpan};
+use shiika_ast::{AstCallArgs, AstExpression, AstExpressionBody, LocationSpan, Token};
 use shiika_core::names::{method_fullname, MethodFirstname};
 use shiika_core::{ty, ty::TermTy};
 use skc_hir::*;
 
+/// Returns false for `foo()` and `self.foo()`
+fn has_explicit_receiver(receiver_expr: &Option<Box<AstExpression>>) -> bool {
+    match receiver_expr {
+        Some(expr) => !matches!(expr.body, AstExpressionBody::PseudoVariable(Token::KwSelf)),
+        None => false,
+    }
+}
+
 pub enum ArrangedArg<'ast> {
     Expr(&'ast AstExpression),
     Default(&'ast TermTy),
 }
 
 /// Entry point of Converting `AstMethodCall` into `HirMethodCall`.
+/// `expected` is the type the call should return (if known.)
 pub fn convert_method_call(
     mk: &mut HirMaker,
     receiver_expr: &Option<Box<AstExpression>>,
     method_name: &MethodFirstname,
     args: &AstCallArgs,
     type_args: &[AstExpression],
+    expected: Option<&TermTy>,
     locs: &LocationSpan,
 ) -> Result<HirExpression> {
     // Check if this is a lambda invocation
@@ -41,9 +52,16 @@ pub fn convert_method_call(
     };
     let receiver_ty = &receiver_hir.ty;
 
-    let found = mk
+    let mut found = mk
         .class_dict
         .lookup_method(receiver_ty, method_name, locs)?;
+    if mk.resolve_method_ret_ty(&found.sig.fullname)? {
+        found = mk
+            .class_dict
+            .lookup_method(receiver_ty, method_name, locs)?;
+    }
+    mk.class_dict
+        .check_method_visibility(&found, has_explicit_receiver(receiver_expr), locs)?;
 
     let total_args = args.unnamed.len() + args.named.len();
     validate_argument_length(total_args, &found.sig.params, &locs)?;
@@ -52,7 +70,11 @@ pub fn convert_method_call(
 
     validate_method_tyargs(&found, type_args)?;
     let method_tyargs = if found.sig.has_typarams() && type_args.is_empty() {
-        if found.is_new(&receiver_ty) && receiver_ty.has_type_args() {
+        if let Some(tyargs) = expected_new_tyargs(&found, receiver_ty, expected) {
+            // `Foo.new` where `Foo<T>` is a generic class and `T` is
+            // inferred from the later uses
+            Some(tyargs)
+        } else if found.is_new(&receiver_ty) && receiver_ty.has_type_args() {
             // Special handling for `Foo<Bar>.new`
             Some(receiver_ty.type_args().to_vec())
         } else {
@@ -77,13 +99,33 @@ pub fn convert_method_call(
         .get_type(&found.owner.to_type_fullname())
         .base()
         .typarams;
-    let class_tyargs = receiver_ty.type_args();
-    let mut inf = Infer::new(&block_taker, class_typarams, class_tyargs, method_tyargs);
+    // The type arguments of the upper bound if the receiver is a typaram
+    let class_tyargs = receiver_ty.upper_bound().type_args;
+    let mut inf = Infer::new(
+        &block_taker,
+        class_typarams,
+        &class_tyargs,
+        method_tyargs,
+        &mk.holes,
+    );
     let mut arg_hirs = convert_method_args(mk, &mut inf, &block_taker, &arranged, &args.block)?;
 
     let tyargs = inf
         .method_tyargs()
         .with_context(|| error(&block_taker, locs))?;
+    // The typarams of `Foo.new` are the ones of `Foo`
+    let bound_class_tyargs = if found.is_new(receiver_ty) {
+        &tyargs
+    } else {
+        &class_tyargs
+    };
+    type_checking::check_tyarg_bounds(
+        &mk.class_dict,
+        &found.sig.typarams,
+        &tyargs,
+        bound_class_tyargs,
+        locs,
+    )?;
     let updated_param_types = inf.param_tys().with_context(|| error(&block_taker, locs))?;
 
     check_argument_types(
@@ -221,6 +263,7 @@ pub fn convert_lambda_invocation(
         Default::default(),
         Default::default(),
         Default::default(),
+        &mk.holes,
     );
 
     let arg_hirs = convert_method_args(mk, &mut inf, &block_taker, &arg_exprs, &args.block)?;
@@ -255,15 +298,35 @@ fn convert_method_args(
     arg_exprs: &[ArrangedArg],
     opt_block: &Option<Box<AstExpression>>,
 ) -> Result<Vec<HirExpression>> {
-    let mut arg_hirs = arg_exprs
-        .iter()
-        .map(|expr| match expr {
-            ArrangedArg::Expr(e) => mk.convert_expr(e),
-            ArrangedArg::Default(ty) => Ok(Hir::default_expression((*ty).clone())),
-        })
-        .collect::<Result<Vec<_>>>()?;
-    let arg_tys = arg_hirs.iter().map(|arg| &arg.ty).collect::<Vec<_>>();
-    inf.set_arg_tys(&arg_tys)?;
+    let mut arg_hirs = vec![];
+    // Lambdas without parameter types are converted after the other
+    // arguments so that the types can be inferred from them.
+    let mut deferred = vec![];
+    for (i, expr) in arg_exprs.iter().enumerate() {
+        let hir = match expr {
+            ArrangedArg::Expr(e) if is_untyped_fn(e) => {
+                deferred.push((i, e));
+                arg_hirs.push(None);
+                continue;
+            }
+            ArrangedArg::Expr(e) => mk.convert_expr(e)?,
+            ArrangedArg::Default(ty) => Hir::default_expression((*ty).clone()),
+        };
+        inf.set_arg_ty(i, &hir.ty)?;
+        arg_hirs.push(Some(hir));
+    }
+    for (i, e) in deferred {
+        let hir = match inf.fn_arg_param_tys(i) {
+            Some(param_tys) => {
+                let expected = block::lambda_ty_with(param_tys, ty::raw("Object"));
+                mk.convert_expr_expecting(e, &expected)?
+            }
+            None => mk.convert_expr(e)?,
+        };
+        inf.set_arg_ty(i, &hir.ty)?;
+        arg_hirs[i] = Some(hir);
+    }
+    let mut arg_hirs = arg_hirs.into_iter().map(|x| x.unwrap()).collect::<Vec<_>>();
 
     // Convert the block (if any)
     if let Some(block) = opt_block {
@@ -273,9 +336,40 @@ fn convert_method_args(
         arg_hirs.push(block_hir);
     }
 
+    for (tpref, t) in inf.hole_answers() {
+        mk.holes.resolve(&tpref, t);
+    }
     Ok(arg_hirs)
 }
 
+/// Returns true if `expr` is a `fn` without some of the parameter types
+fn is_untyped_fn(expr: &AstExpression) -> bool {
+    match &expr.body {
+        AstExpressionBody::LambdaExpr { params, is_fn, .. } => {
+            *is_fn && lvar_ty::lacks_param_ty(params)
+        }
+        _ => false,
+    }
+}
+
+/// Returns the type arguments for `Foo.new` taken from the expected type
+/// (eg. `Int` for `Array.new` if `Array<Int>` is expected.)
+fn expected_new_tyargs(
+    found: &FoundMethod,
+    receiver_ty: &TermTy,
+    expected: Option<&TermTy>,
+) -> Option<Vec<TermTy>> {
+    let t = expected?;
+    if found.is_new(receiver_ty)
+        && t.erasure().base_name == receiver_ty.erasure().base_name
+        && t.type_args().len() == found.sig.typarams.len()
+    {
+        Some(t.type_args().to_vec())
+    } else {
+        None
+    }
+}
+
 fn check_argument_types(
     mk: &HirMaker,
     sig: &MethodSignature,
@@ -284,6 +378,12 @@ fn check_argument_types(
     arg_types: &[TermTy],
 ) -> Result<()> {
     type_checking::check_method_args(&mk.class_dict, sig, receiver_hir, arg_hirs, arg_types)?;
+    if sendable::is_spawner(sig) {
+        if let Some(first_arg) = arg_hirs.first() {
+            let caller = mk.ctx_stack.method_ctx().map(|c| &c.signature);
+            sendable::check_spawned_lambda(&mk.class_dict, caller, first_arg)?;
+        }
+    }
     if let Some(last_arg) = arg_hirs.last_mut() {
         check_break_in_block(sig, last_arg)?;
     }
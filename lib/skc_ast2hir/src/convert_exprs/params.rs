@@ -11,7 +11,9 @@
 //! ```
 use crate::class_dict::ClassDict;
 use crate::convert_exprs::MethodParam;
+use crate::error;
 use anyhow::Result;
+use shiika_ast::LocationSpan;
 use shiika_core::names::Namespace;
 use shiika_core::ty::{self, TermTy};
 
@@ -48,6 +50,8 @@ pub fn convert_block_params(
     // the typarams of the current method.
     method_typarams: &[ty::TyParam],
     inferred_block_param_tys: &[TermTy],
+    // For error message
+    locs: &LocationSpan,
 ) -> Result<Vec<MethodParam>> {
     let mut hir_params = vec![];
     for (i, param) in ast_params.iter().enumerate() {
@@ -62,7 +66,9 @@ pub fn convert_block_params(
             }
         } else {
             // Infer from hint
-            let ty = &inferred_block_param_tys[i];
+            let Some(ty) = inferred_block_param_tys.get(i) else {
+                return Err(error::block_param_ty_unknown(&param.name, locs));
+            };
             MethodParam {
                 name: param.name.to_string(),
                 ty: ty.clone(),
@@ -4,7 +4,7 @@ use shiika_core::{ty, ty::*};
 use skc_hir::MethodParam;
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CtxStack {
     /// List of ctxs
     vec: Vec<HirMakerContext>,
@@ -278,7 +278,7 @@ impl CtxStack {
         if let Some(method_ctx) = self.method_ctx() {
             let typarams = &method_ctx.signature.typarams;
             if let Some(i) = typarams.iter().position(|t| *name == *t.name) {
-                return Some(ty::typaram_ref(name, ty::TyParamKind::Method, i));
+                return Some(typarams[i].to_typaram_ref(ty::TyParamKind::Method, i));
             }
             if let Some(class_ctx) = self.class_ctx() {
                 if method_ctx.signature.fullname.is_class_method() {
@@ -286,7 +286,7 @@ impl CtxStack {
                 }
                 let typarams = &class_ctx.typarams;
                 if let Some(i) = typarams.iter().position(|t| *name == *t.name) {
-                    return Some(ty::typaram_ref(name, ty::TyParamKind::Class, i));
+                    return Some(typarams[i].to_typaram_ref(ty::TyParamKind::Class, i));
                 }
             }
         }
@@ -0,0 +1,170 @@
+//! Structural `==` and `hash` of enum cases.
+//!
+//! For an enum case with parameters, these methods are defined as if they
+//! are written in Shiika (unless the enum defines `==` or `hash` by itself.)
+//!
+//! ```sk
+//! enum Foo
+//!   case Bar(a: Int, b: String)
+//! end
+//! # defines
+//! class Foo::Bar
+//!   def ==(other: Object) -> Bool
+//!     match other
+//!     when Foo::Bar(other_a, other_b) then @a == other_a and @b == other_b
+//!     else false
+//!     end
+//!   end
+//!
+//!   def hash -> Int
+//!     [0, @a.hash, @b.hash].hash  # 0 is the index of the case
+//!   end
+//! end
+//! ```
+use shiika_ast::*;
+use shiika_core::names::*;
+
+/// Returns the definitions of `==` and `hash` of the enum case.
+pub fn case_defs(
+    case_fullname: &ClassFullname,
+    case_idx: usize,
+    case: &EnumCase,
+    enum_defs: &[Definition],
+) -> Vec<Definition> {
+    if case.params.is_empty() || defines_equality(enum_defs) {
+        // Cases without params are singletons; `Object#==` is enough
+        return vec![];
+    }
+    vec![eq_def(case_fullname, case), hash_def(case_idx, case)]
+}
+
+/// Returns true if the enum defines `==` or `hash`.
+fn defines_equality(defs: &[Definition]) -> bool {
+    defs.iter().any(|def| match def {
+        Definition::InstanceMethodDefinition { sig, .. } => {
+            sig.name.0 == "==" || sig.name.0 == "hash"
+        }
+        _ => false,
+    })
+}
+
+fn eq_def(case_fullname: &ClassFullname, case: &EnumCase) -> Definition {
+    let other_name = |param: &Param| format!("other_{}", param.name);
+    let pattern = AstPattern::ExtractorPattern {
+        names: case_fullname.0.split("::").map(|s| s.to_string()).collect(),
+        params: case
+            .params
+            .iter()
+            .map(|param| AstPattern::VariablePattern(other_name(param)))
+            .collect(),
+        locs: LocationSpan::internal(),
+    };
+    let comparison = case
+        .params
+        .iter()
+        .map(|param| {
+            method_call(
+                expr(AstExpressionBody::IVarRef(param.name.clone())),
+                "==",
+                vec![expr(AstExpressionBody::BareName(other_name(param)))],
+            )
+        })
+        .reduce(|left, right| {
+            expr(AstExpressionBody::LogicalAnd {
+                left: Box::new(left),
+                right: Box::new(right),
+            })
+        })
+        .unwrap();
+    let match_expr = expr(AstExpressionBody::Match {
+        cond_expr: Box::new(expr(AstExpressionBody::BareName("other".to_string()))),
+        clauses: vec![
+            clause(pattern, comparison),
+            clause(
+                AstPattern::VariablePattern("_".to_string()),
+                expr(AstExpressionBody::PseudoVariable(Token::KwFalse)),
+            ),
+        ],
+    });
+    Definition::InstanceMethodDefinition {
+        sig: signature(
+            "==",
+            vec![Param {
+                name: "other".to_string(),
+                typ: type_name("Object"),
+                is_iparam: false,
+                default_expr: None,
+            }],
+            "Bool",
+        ),
+        body_exprs: vec![match_expr],
+    }
+}
+
+fn hash_def(case_idx: usize, case: &EnumCase) -> Definition {
+    let mut items = vec![expr(AstExpressionBody::DecimalLiteral {
+        value: case_idx as i64,
+    })];
+    items.extend(case.params.iter().map(|param| {
+        method_call(
+            expr(AstExpressionBody::IVarRef(param.name.clone())),
+            "hash",
+            vec![],
+        )
+    }));
+    let ary = expr(AstExpressionBody::ArrayLiteral(items));
+    Definition::InstanceMethodDefinition {
+        sig: signature("hash", vec![], "Int"),
+        body_exprs: vec![method_call(ary, "hash", vec![])],
+    }
+}
+
+fn signature(name: &str, params: Vec<Param>, ret: &str) -> AstMethodSignature {
+    AstMethodSignature {
+        name: method_firstname(name),
+        typarams: vec![],
+        params,
+        ret_typ: Some(type_name(ret)),
+        visibility: Visibility::Public,
+        locs: LocationSpan::internal(),
+    }
+}
+
+fn type_name(name: &str) -> UnresolvedTypeName {
+    UnresolvedTypeName {
+        names: vec![name.to_string()],
+        args: vec![],
+        locs: LocationSpan::internal(),
+    }
+}
+
+fn clause(pattern: AstPattern, body: AstExpression) -> AstMatchClause {
+    AstMatchClause {
+        pattern,
+        guard: None,
+        body: vec![body],
+        locs: LocationSpan::internal(),
+    }
+}
+
+fn method_call(receiver: AstExpression, name: &str, args: Vec<AstExpression>) -> AstExpression {
+    let mut call_args = AstCallArgs::new();
+    for arg in args {
+        call_args.add_unnamed(arg);
+    }
+    expr(AstExpressionBody::MethodCall(AstMethodCall {
+        receiver_expr: Some(Box::new(receiver)),
+        method_name: method_firstname(name),
+        args: call_args,
+        type_args: vec![],
+        may_have_paren_wo_args: false,
+    }))
+}
+
+fn expr(body: AstExpressionBody) -> AstExpression {
+    AstExpression {
+        body,
+        primary: true,
+        locs: LocationSpan::internal(),
+    }
+}
@@ -1,29 +1,38 @@
 use shiika_ast::LocationSpan;
-use shiika_core::ty::TermTy;
-use skc_error::Label;
+use shiika_core::ty::{TermTy, TyParam, Variance};
+use skc_error::{Label, Report};
 use skc_hir::MethodSignature;
 
 #[derive(thiserror::Error, Debug)]
 #[allow(clippy::enum_variant_names)]
 pub enum Error {
-    #[error("{msg})")]
-    SyntaxError { msg: String },
+    #[error("{report})")]
+    SyntaxError { report: Report },
     /// Errors of types
-    #[error("{msg}")]
-    TypeError { msg: String },
+    #[error("{report}")]
+    TypeError { report: Report },
     /// Invalid name
-    #[error("{msg}")]
-    NameError { msg: String },
+    #[error("{report}")]
+    NameError { report: Report },
     /// Syntactically correct but invalid program
-    #[error("{msg}")]
-    ProgramError { msg: String },
+    #[error("{report}")]
+    ProgramError { report: Report },
 }
 
-pub fn syntax_error(msg: &str) -> anyhow::Error {
-    Error::SyntaxError {
-        msg: msg.to_string(),
+impl Error {
+    /// Returns the report of this error (with its location, if any)
+    pub fn report(&self) -> &Report {
+        match self {
+            Error::SyntaxError { report }
+            | Error::TypeError { report }
+            | Error::NameError { report }
+            | Error::ProgramError { report } => report,
+        }
     }
-    .into()
+}
+
+pub fn syntax_error(msg: impl Into<Report>) -> anyhow::Error {
+    Error::SyntaxError { report: msg.into() }.into()
 }
 
 pub fn argument_error(expected: usize, got: usize, locs: &LocationSpan) -> anyhow::Error {
@@ -38,19 +47,16 @@ pub fn argument_error(expected: usize, got: usize, locs: &LocationSpan) -> anyho
     type_error(report)
 }
 
-pub fn type_error(msg: impl Into<String>) -> anyhow::Error {
-    Error::TypeError { msg: msg.into() }.into()
+pub fn type_error(msg: impl Into<Report>) -> anyhow::Error {
+    Error::TypeError { report: msg.into() }.into()
 }
 
-pub fn name_error(msg: &str) -> anyhow::Error {
-    Error::NameError {
-        msg: msg.to_string(),
-    }
-    .into()
+pub fn name_error(msg: impl Into<Report>) -> anyhow::Error {
+    Error::NameError { report: msg.into() }.into()
 }
 
-pub fn program_error(msg: impl Into<String>) -> anyhow::Error {
-    Error::ProgramError { msg: msg.into() }.into()
+pub fn program_error(msg: impl Into<Report>) -> anyhow::Error {
+    Error::ProgramError { report: msg.into() }.into()
 }
 
 pub fn lvar_redeclaration(name: &str, locs: &LocationSpan) -> anyhow::Error {
@@ -135,6 +141,22 @@ pub fn named_arg_for_lambda(name: &str, locs: &LocationSpan) -> anyhow::Error {
     program_error(report)
 }
 
+pub fn block_param_ty_unknown(name: &str, locs: &LocationSpan) -> anyhow::Error {
+    let msg = format!("could not infer the type of parameter `{}'", name);
+    let report = skc_error::build_report(msg, locs, |r, locs_span| {
+        r.with_label(Label::new(locs_span).with_message("please specify the type"))
+    });
+    type_error(report)
+}
+
+pub fn cannot_infer_lvar_ty(name: &str, locs: &LocationSpan) -> anyhow::Error {
+    let msg = format!("cannot infer the type of `{}'", name);
+    let report = skc_error::build_report(msg, locs, |r, locs_span| {
+        r.with_label(Label::new(locs_span).with_message("please specify the type"))
+    });
+    type_error(report)
+}
+
 pub fn method_call_tyinf_failed(detail: String, locs: &LocationSpan) -> anyhow::Error {
     let report =
         skc_error::build_report("Type inference failed".to_string(), locs, |r, locs_span| {
@@ -180,3 +202,136 @@ pub fn match_clauses_type_mismatch(
         .build(main_msg, &locs1);
     type_error(report)
 }
+
+pub fn non_exhaustive_match(missing: &[String], locs: &LocationSpan) -> anyhow::Error {
+    let names = missing
+        .iter()
+        .map(|x| format!("`{}'", x))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let main_msg = format!("match is not exhaustive ({} not covered)", names);
+    let report = skc_error::report_builder()
+        .annotate(locs.clone(), format!("missing {}", names))
+        .build(main_msg, locs);
+    program_error(report)
+}
+
+pub fn bind_in_alternative_pattern(name: &str, locs: &LocationSpan) -> anyhow::Error {
+    let main_msg = format!("cannot bind variable `{}' in an alternative pattern", name);
+    let report = skc_error::report_builder()
+        .annotate(locs.clone(), "use `_' instead".to_string())
+        .build(main_msg, locs);
+    program_error(report)
+}
+
+pub fn var_shared_with_task(what: &str, locs: &LocationSpan) -> anyhow::Error {
+    let main_msg = format!("cannot share {} with a task because it is a `var'", what);
+    let report = skc_error::report_builder()
+        .annotate(
+            locs.clone(),
+            "use `let' or pass the value with `Mutex' or `Channel'".to_string(),
+        )
+        .build(main_msg, locs);
+    program_error(report)
+}
+
+pub fn non_sendable_shared_with_task(
+    what: &str,
+    ty: &TermTy,
+    locs: &LocationSpan,
+) -> anyhow::Error {
+    let main_msg = format!(
+        "cannot share {} with a task because `{}' may be modified in parallel",
+        what, ty
+    );
+    let report = skc_error::report_builder()
+        .annotate(
+            locs.clone(),
+            "only immutable values can be shared".to_string(),
+        )
+        .build(main_msg, locs);
+    program_error(report)
+}
+
+pub fn non_literal_lambda_for_task(locs: &LocationSpan) -> anyhow::Error {
+    let main_msg = "the function run as a task must be a `fn' literal or a block".to_string();
+    let report = skc_error::report_builder()
+        .annotate(
+            locs.clone(),
+            "the values captured by this function cannot be checked".to_string(),
+        )
+        .build(main_msg, locs);
+    program_error(report)
+}
+
+pub fn private_method_called(sig: &MethodSignature, locs: &LocationSpan) -> anyhow::Error {
+    let main_msg = format!("private method `{}' called with a receiver", sig.fullname);
+    let report = skc_error::report_builder()
+        .annotate(
+            locs.clone(),
+            "private methods can only be called on `self'".to_string(),
+        )
+        .build(main_msg, locs);
+    program_error(report)
+}
+
+pub fn private_constant_referred(name: &str, locs: &LocationSpan) -> anyhow::Error {
+    let main_msg = format!("private constant `{}' referred from outside", name);
+    let report = skc_error::report_builder()
+        .annotate(
+            locs.clone(),
+            "private constants can only be referred to inside their namespace".to_string(),
+        )
+        .build(main_msg, locs);
+    program_error(report)
+}
+
+pub fn variance_mismatch(
+    typaram: &TyParam,
+    sig: &MethodSignature,
+    locs: &LocationSpan,
+) -> anyhow::Error {
+    let (kind, position) = match typaram.variance {
+        Variance::Contravariant => ("contravariant (`in')", "an output"),
+        _ => ("covariant (`out')", "an input"),
+    };
+    let main_msg = format!(
+        "type parameter `{}' is {} but appears in {} position of `{}'",
+        typaram.name, kind, position, sig.fullname
+    );
+    let report = skc_error::report_builder()
+        .annotate(
+            locs.clone(),
+            format!("`{}' cannot be used here", typaram.name),
+        )
+        .build(main_msg, locs);
+    type_error(report)
+}
+
+pub fn tyarg_bound_mismatch(
+    typaram: &TyParam,
+    tyarg: &TermTy,
+    bound: &TermTy,
+    locs: &LocationSpan,
+) -> anyhow::Error {
+    let main_msg = format!(
+        "type argument {} does not satisfy the bound of `{}'",
+        tyarg, typaram.name
+    );
+    let report = skc_error::report_builder()
+        .annotate(
+            locs.clone(),
+            format!("`{}' must conform to {}", typaram.name, bound),
+        )
+        .build(main_msg, locs);
+    type_error(report)
+}
+
+/// Returns a warning (not an error) for a match clause which never matches
+pub fn unreachable_match_clause(locs: &LocationSpan) -> Report {
+    let main_msg = "unreachable match clause".to_string();
+    skc_error::report_builder()
+        .warning()
+        .annotate(locs.clone(), "previous clauses cover this".to_string())
+        .build(main_msg, locs)
+}
@@ -1,15 +1,18 @@
 use crate::class_dict::ClassDict;
 use crate::ctx_stack::CtxStack;
+use crate::enum_equality;
 use crate::error;
 use crate::hir_maker_context::*;
 use crate::method_dict::MethodDict;
-use crate::parse_typarams;
+use crate::type_inference::{lvar_ty::HoledLVar, method_ret_ty::PendingMethod, Holes};
 use crate::type_system::type_checking;
 use anyhow::Result;
 use shiika_ast::*;
 use shiika_core::{names::*, ty, ty::*};
 use skc_hir::*;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct HirMaker<'hir_maker> {
@@ -31,6 +34,30 @@ pub struct HirMaker<'hir_maker> {
     pub(super) lambda_ct: usize,
     /// Counter for unique name
     pub(super) gensym_ct: usize,
+    /// Warnings found so far (rendered reports)
+    pub(super) warnings: Vec<skc_error::Report>,
+    /// Types of lvars to be inferred from their later uses
+    pub(super) holes: Holes,
+    /// Lvars whose type is being inferred (`Some` while converting the
+    /// rest of the sequence to collect their uses)
+    pub(super) lvar_inference: Option<Vec<HoledLVar>>,
+    /// Inferred types of lvars, keyed by the location of the declaration
+    /// (`None` if it could not be inferred)
+    pub(super) inferred_lvar_tys: HashMap<(Rc<PathBuf>, usize), Option<TermTy>>,
+    /// Methods whose return type is to be inferred from the body
+    pub(super) pending_methods: HashMap<MethodFullname, PendingMethod>,
+    /// Methods whose return type is being inferred
+    pub(super) inferring_methods: Vec<MethodFullname>,
+}
+
+/// State of `HirMaker` saved by `HirMaker::snapshot`
+pub(super) struct Snapshot {
+    ctx_stack: CtxStack,
+    lambda_ct: usize,
+    gensym_ct: usize,
+    n_str_literals: usize,
+    n_const_inits: usize,
+    n_warnings: usize,
 }
 
 impl<'hir_maker> HirMaker<'hir_maker> {
@@ -48,9 +75,38 @@ impl<'hir_maker> HirMaker<'hir_maker> {
             ctx_stack: CtxStack::new(vec![HirMakerContext::toplevel()]),
             lambda_ct: 0,
             gensym_ct: 0,
+            warnings: vec![],
+            holes: Default::default(),
+            lvar_inference: None,
+            inferred_lvar_tys: Default::default(),
+            pending_methods: Default::default(),
+            inferring_methods: vec![],
         }
     }
 
+    /// Save the current state so that the conversion can be tried without
+    /// side effects (used for type inference.)
+    pub(super) fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            ctx_stack: self.ctx_stack.clone(),
+            lambda_ct: self.lambda_ct,
+            gensym_ct: self.gensym_ct,
+            n_str_literals: self.str_literals.len(),
+            n_const_inits: self.const_inits.len(),
+            n_warnings: self.warnings.len(),
+        }
+    }
+
+    /// Restore the state saved by `snapshot`
+    pub(super) fn restore(&mut self, snapshot: Snapshot) {
+        self.ctx_stack = snapshot.ctx_stack;
+        self.lambda_ct = snapshot.lambda_ct;
+        self.gensym_ct = snapshot.gensym_ct;
+        self.str_literals.truncate(snapshot.n_str_literals);
+        self.const_inits.truncate(snapshot.n_const_inits);
+        self.warnings.truncate(snapshot.n_warnings);
+    }
+
     /// Destructively convert self to Hir
     pub fn extract_hir(&mut self, main_exprs: Vec<HirExpression>, main_lvars: HirLVars) -> Hir {
         // Extract data from self
@@ -62,15 +118,30 @@ impl<'hir_maker> HirMaker<'hir_maker> {
         std::mem::swap(&mut str_literals, &mut self.str_literals);
         let mut const_inits = vec![];
         std::mem::swap(&mut const_inits, &mut self.const_inits);
+        let warnings = std::mem::take(&mut self.warnings);
+        let type_consts = sk_types
+            .types
+            .keys()
+            .map(|name| name.to_const_fullname())
+            .collect::<HashSet<_>>();
+        let private_consts = self
+            .class_dict
+            .private_consts
+            .iter()
+            .filter(|name| !type_consts.contains(name))
+            .cloned()
+            .collect();
 
         Hir {
             sk_types,
             sk_methods,
             constants,
+            private_consts,
             str_literals,
             const_inits,
             main_exprs,
             main_lvars,
+            warnings,
         }
     }
 
@@ -165,11 +236,12 @@ impl<'hir_maker> HirMaker<'hir_maker> {
                 }
             }
         }
+        self.collect_pending_methods(&Namespace::root(), None, &defs);
         self.process_defs(&Namespace::root(), None, &defs)?;
 
         let mut main_exprs = vec![];
-        for expr in top_exprs {
-            main_exprs.push(self.convert_expr(&expr)?);
+        for (i, expr) in top_exprs.iter().enumerate() {
+            main_exprs.push(self.convert_expr_in_seq(expr, &top_exprs[i + 1..])?);
         }
 
         debug_assert!(self.ctx_stack.len() == 1);
@@ -222,7 +294,7 @@ impl<'hir_maker> HirMaker<'hir_maker> {
                 shiika_ast::Definition::ClassInitializerDefinition { .. } => {
                     // Already processed in process_class_def
                 }
-                shiika_ast::Definition::ConstDefinition { name, expr } => {
+                shiika_ast::Definition::ConstDefinition { name, expr, .. } => {
                     if opt_fullname.is_some() {
                         // Already processed
                     } else {
@@ -234,23 +306,41 @@ impl<'hir_maker> HirMaker<'hir_maker> {
                     defs,
                     typarams,
                     ..
-                } => self.process_class_def(namespace, name, parse_typarams(typarams), defs)?,
+                } => self.process_class_def(
+                    namespace,
+                    name,
+                    self.class_dict
+                        .resolve_typarams(namespace, typarams, None)?,
+                    defs,
+                )?,
                 shiika_ast::Definition::ModuleDefinition {
                     name,
                     typarams,
                     defs,
                     ..
                 } => {
-                    self.process_module_def(namespace, name, parse_typarams(typarams), defs)?;
+                    self.process_module_def(
+                        namespace,
+                        name,
+                        self.class_dict
+                            .resolve_typarams(namespace, typarams, None)?,
+                        defs,
+                    )?;
                 }
                 shiika_ast::Definition::EnumDefinition {
                     name,
                     typarams,
                     cases,
                     defs,
-                } => {
-                    self.process_enum_def(namespace, name, parse_typarams(typarams), cases, defs)?
-                }
+                    ..
+                } => self.process_enum_def(
+                    namespace,
+                    name,
+                    self.class_dict
+                        .resolve_typarams(namespace, typarams, None)?,
+                    cases,
+                    defs,
+                )?,
                 shiika_ast::Definition::MethodRequirementDefinition { .. } => {
                     // Already processed in class_dict/indexing.rs
                 }
@@ -362,7 +452,7 @@ impl<'hir_maker> HirMaker<'hir_maker> {
         defs: &[shiika_ast::Definition],
     ) -> Result<()> {
         for def in defs {
-            if let shiika_ast::Definition::ConstDefinition { name, expr } = def {
+            if let shiika_ast::Definition::ConstDefinition { name, expr, .. } = def {
                 let full = namespace.const_fullname(name);
                 let hir_expr = self.convert_expr(expr)?;
                 self.register_const_full(full, hir_expr);
@@ -463,7 +553,9 @@ impl<'hir_maker> HirMaker<'hir_maker> {
         body_exprs: &[AstExpression],
         super_ivars: Option<SkIVars>,
     ) -> Result<(SkMethod, HashMap<String, SkIVar>)> {
+        self.resolve_method_ret_ty(&method_fullname(type_fullname.clone(), &ast_sig.name.0))?;
         // MethodSignature is built beforehand by class_dict::new
+        // (return type may be updated by resolve_method_ret_ty)
         let signature = self
             .class_dict
             .find_method_sig(type_fullname, &ast_sig.name)
@@ -546,8 +638,9 @@ impl<'hir_maker> HirMaker<'hir_maker> {
     ) -> Result<()> {
         let fullname = namespace.class_fullname(firstname);
         let inner_namespace = namespace.add(firstname.to_string());
-        for case in cases {
+        for (idx, case) in cases.iter().enumerate() {
             self._register_enum_case_class(&inner_namespace, case)?;
+            self._define_enum_case_equality(&inner_namespace, idx, case, defs)?;
         }
         self.ctx_stack
             .push(HirMakerContext::class(inner_namespace.clone(), typarams));
@@ -617,6 +710,29 @@ impl<'hir_maker> HirMaker<'hir_maker> {
         Ok(())
     }
 
+    /// Define `==` and `hash` of an enum case (see enum_equality.rs)
+    fn _define_enum_case_equality(
+        &mut self,
+        namespace: &Namespace,
+        idx: usize,
+        case: &EnumCase,
+        enum_defs: &[shiika_ast::Definition],
+    ) -> Result<()> {
+        let fullname = namespace.class_fullname(&case.name);
+        let case_defs = enum_equality::case_defs(&fullname, idx, case, enum_defs);
+        if case_defs.is_empty() {
+            return Ok(());
+        }
+        let typarams = self.class_dict.get_class(&fullname).base.typarams.clone();
+        self.ctx_stack.push(HirMakerContext::class(
+            namespace.add(case.name.to_string()),
+            typarams,
+        ));
+        self.process_defs(namespace, Some(&fullname.to_type_fullname()), &case_defs)?;
+        self.ctx_stack.pop_class_ctx();
+        Ok(())
+    }
+
     /// Generate special lvar name
     /// Note: don't forget calling ctx_stack.declare_lvar
     pub fn generate_lvar_name(&mut self, prefix: &str) -> String {
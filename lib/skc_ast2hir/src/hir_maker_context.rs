@@ -2,7 +2,7 @@ use shiika_core::{names::*, ty::*};
 use skc_hir::{MethodParam, MethodSignature, SkIVars};
 use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum HirMakerContext {
     Toplevel(ToplevelCtx),
@@ -52,6 +52,7 @@ impl HirMakerContext {
             lvars: Default::default(),
             iivars: Default::default(),
             super_ivars: super_ivars.unwrap_or_default(),
+            return_tys: None,
         })
     }
 
@@ -85,13 +86,13 @@ impl HirMakerContext {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ToplevelCtx {
     /// Current local variables
     pub lvars: HashMap<String, CtxLVar>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ClassCtx {
     /// Current namespace
     pub namespace: Namespace,
@@ -101,7 +102,7 @@ pub struct ClassCtx {
     pub lvars: HashMap<String, CtxLVar>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MethodCtx {
     /// Signature of the current method
     pub signature: MethodSignature,
@@ -113,9 +114,12 @@ pub struct MethodCtx {
     /// List of inherited ivars
     /// Empty if the method is not `#initialize`
     pub super_ivars: SkIVars, // TODO: this can be just &'a SkIVars
+    /// Types of the arguments of `return` found so far.
+    /// `Some` only while inferring the return type of this method
+    pub return_tys: Option<Vec<TermTy>>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LambdaCtx {
     /// true if this lambda is `fn(){}`. false if it is a block (`do..end`,`{...}`)
     pub is_fn: bool,
@@ -149,26 +153,26 @@ impl LambdaCtx {
 }
 
 /// Indicates we're in a while expr
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct WhileCtx {
     pub lvars: HashMap<String, CtxLVar>,
 }
 
 /// Indicates we're in a if expr
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct IfCtx {
     pub lvars: HashMap<String, CtxLVar>,
 }
 
 /// Each clause of match expression has its own lvars
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MatchClauseCtx {
     /// Local variables introduced when matched
     pub lvars: HashMap<String, CtxLVar>,
 }
 
 /// A local variable
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CtxLVar {
     #[allow(dead_code)] // FIXME
     pub name: String,
@@ -179,7 +183,7 @@ pub struct CtxLVar {
 
 pub type CtxLVars = HashMap<String, CtxLVar>;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LambdaCapture {
     /// The index of ctx stack where this lvar is captured
     pub ctx_idx: usize,
@@ -192,7 +196,7 @@ pub struct LambdaCapture {
     pub detail: LambdaCaptureDetail,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(clippy::enum_variant_names)]
 pub enum LambdaCaptureDetail {
     // Capturing a local variable
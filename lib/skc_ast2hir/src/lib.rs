@@ -2,7 +2,8 @@ mod accessors;
 pub mod class_dict;
 mod convert_exprs;
 mod ctx_stack;
-mod error;
+mod enum_equality;
+pub mod error;
 pub mod hir_maker;
 mod hir_maker_context;
 mod method_dict;
@@ -18,10 +19,11 @@ use skc_hir::Hir;
 use skc_mir::LibraryExports;
 mod rustlib_methods;
 
-pub fn make_hir(ast: shiika_ast::Program, imports: &LibraryExports) -> Result<Hir> {
+pub fn make_hir(mut ast: shiika_ast::Program, imports: &LibraryExports) -> Result<Hir> {
+    let imported_names = std::mem::take(&mut ast.imported_names);
     let defs = ast.defs();
     let type_index = type_index::create(&defs, &Default::default(), &imports.sk_types);
-    let class_dict = class_dict::create(&defs, type_index, &imports.sk_types)?;
+    let class_dict = class_dict::create(&defs, type_index, &imports.sk_types, imported_names)?;
 
     let mut hir_maker = HirMaker::new(class_dict, &imports.constants);
     hir_maker.define_class_constants()?;
@@ -1,8 +1,11 @@
+mod exhaustiveness;
 use crate::error;
 use crate::hir_maker::extract_lvars;
 use crate::hir_maker::HirMaker;
 use crate::hir_maker_context::HirMakerContext;
+use crate::type_system::type_checking;
 use anyhow::Result;
+use exhaustiveness::{Ctor, Pat};
 use shiika_ast::*;
 use shiika_core::{names::*, ty, ty::*};
 use skc_hir::pattern_match::{Component, MatchClause};
@@ -21,13 +24,19 @@ pub fn expand_result_try(
     let ok_pattern = AstPattern::ExtractorPattern {
         names: vec!["Ok".to_string()],
         params: vec![AstPattern::VariablePattern(ok_tmp.clone())],
+        locs: locs.clone(),
     };
     let ok_body = AstExpression {
         body: AstExpressionBody::BareName(ok_tmp),
         primary: true,
         locs: locs.clone(),
     };
-    let ok_clause = (ok_pattern, vec![ok_body]);
+    let ok_clause = AstMatchClause {
+        pattern: ok_pattern,
+        guard: None,
+        body: vec![ok_body],
+        locs: locs.clone(),
+    };
 
     // `when e then return e.unsafe_cast(Fail)`
     let err_tmp = mk.generate_lvar_name("e");
@@ -60,11 +69,16 @@ pub fn expand_result_try(
         primary: false,
         locs: locs.clone(),
     };
-    let err_clause = (err_pattern, vec![err_body]);
+    let err_clause = AstMatchClause {
+        pattern: err_pattern,
+        guard: None,
+        body: vec![err_body],
+        locs: locs.clone(),
+    };
 
     let clauses = vec![
-        convert_match_clause(mk, &tmp_ref, &ok_clause)?,
-        convert_match_clause(mk, &tmp_ref, &err_clause)?,
+        convert_match_clause(mk, &tmp_ref, &ok_clause)?.0,
+        convert_match_clause(mk, &tmp_ref, &err_clause)?.0,
     ];
 
     _convert_match_expr(mk, tmp_name, receiver_hir, clauses)
@@ -79,10 +93,13 @@ pub fn convert_match_expr(
     let cond_expr = mk.convert_expr(cond)?;
     let tmp_name = mk.generate_lvar_name("expr");
     let tmp_ref = Hir::lvar_ref(cond_expr.ty.clone(), tmp_name.clone(), LocationSpan::todo());
-    let mut clauses = ast_clauses
+    let (mut clauses, pats): (Vec<_>, Vec<_>) = ast_clauses
         .iter()
         .map(|clause| convert_match_clause(mk, &tmp_ref, clause))
-        .collect::<Result<Vec<MatchClause>>>()?;
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .unzip();
+    check_clauses(mk, &tmp_ref.ty, ast_clauses, &pats, &cond.locs)?;
     let panic_msg = Hir::string_literal(
         mk.register_string_literal("no matching clause found"),
         LocationSpan::todo(),
@@ -102,6 +119,37 @@ pub fn convert_match_expr(
 
     _convert_match_expr(mk, tmp_name, cond_expr, clauses)
 }
+
+/// Warn unreachable clauses and check that the clauses cover all the cases
+/// (if the value is an enum or `Bool`)
+fn check_clauses(
+    mk: &mut HirMaker,
+    value_ty: &TermTy,
+    ast_clauses: &[AstMatchClause],
+    pats: &[Pat],
+    locs: &LocationSpan,
+) -> Result<()> {
+    // Clauses with a guard may not match even if the pattern matches
+    let mut unguarded = vec![];
+    for i in 0..pats.len() {
+        if !exhaustiveness::is_useful(&mk.class_dict, &unguarded, &pats[i]) {
+            mk.warnings
+                .push(error::unreachable_match_clause(&ast_clauses[i].locs));
+        }
+        if ast_clauses[i].guard.is_none() {
+            unguarded.push(pats[i].clone());
+        }
+    }
+    if exhaustiveness::is_checked_type(&mk.class_dict, value_ty) {
+        let missing = exhaustiveness::missing_patterns(&mk.class_dict, &unguarded);
+        if !missing.is_empty() {
+            let names = missing.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+            return Err(error::non_exhaustive_match(&names, locs));
+        }
+    }
+    Ok(())
+}
+
 pub fn _convert_match_expr(
     mk: &mut HirMaker,
     tmp_name: String,
@@ -130,31 +178,70 @@ pub fn _convert_match_expr(
 fn convert_match_clause(
     mk: &mut HirMaker,
     value: &HirExpression,
-    (pat, body): &(AstPattern, Vec<AstExpression>),
-) -> Result<MatchClause> {
-    let components = convert_match(mk, value, pat)?;
-    let (body_hir, lvars) = compile_body(mk, &components, body)?;
-    Ok(MatchClause {
+    clause: &AstMatchClause,
+) -> Result<(MatchClause, Pat)> {
+    check_alternatives(&clause.pattern, false, &clause.locs)?;
+    let (mut components, pat) = convert_match(mk, value, &clause.pattern)?;
+    let (body_hir, lvars) = compile_body(mk, &mut components, &clause.guard, &clause.body)?;
+    let match_clause = MatchClause {
         components,
         body_hir,
         lvars,
-    })
+    };
+    Ok((match_clause, pat))
 }
 
-/// Compile clause body into HIR
+/// Check that no variables are bound in alternative patterns
+fn check_alternatives(pat: &AstPattern, in_alt: bool, locs: &LocationSpan) -> Result<()> {
+    match pat {
+        AstPattern::VariablePattern(name) | AstPattern::BindingPattern { name, .. }
+            if in_alt && name != "_" =>
+        {
+            return Err(error::bind_in_alternative_pattern(name, locs));
+        }
+        AstPattern::ArrayPattern {
+            rest: Some(name), ..
+        } if in_alt && name != "_" => {
+            return Err(error::bind_in_alternative_pattern(name, locs));
+        }
+        _ => {}
+    }
+    match pat {
+        AstPattern::ExtractorPattern { params, .. } => params
+            .iter()
+            .try_for_each(|p| check_alternatives(p, in_alt, locs)),
+        AstPattern::OrPattern(alts) => alts
+            .iter()
+            .try_for_each(|p| check_alternatives(p, true, locs)),
+        AstPattern::ArrayPattern { before, after, .. } => before
+            .iter()
+            .chain(after.iter())
+            .try_for_each(|p| check_alternatives(p, in_alt, locs)),
+        AstPattern::BindingPattern { pattern, .. } => check_alternatives(pattern, in_alt, locs),
+        _ => Ok(()),
+    }
+}
+
+/// Compile clause body (and the guard, if any) into HIR
 fn compile_body(
     mk: &mut HirMaker,
-    components: &[Component],
+    components: &mut Vec<Component>,
+    guard: &Option<AstExpression>,
     body: &[AstExpression],
 ) -> Result<(HirExpression, HirLVars)> {
     mk.ctx_stack.push(HirMakerContext::match_clause());
     // Declare lvars introduced by matching
-    for component in components {
+    for component in components.iter() {
         if let Component::Bind(name, expr) = component {
             let readonly = true;
             mk.ctx_stack.declare_lvar(name, expr.ty.clone(), readonly);
         }
     }
+    if let Some(cond) = guard {
+        let cond_hir = mk.convert_expr(cond)?;
+        type_checking::check_condition_ty(&cond_hir.ty, "match guard")?;
+        components.push(Component::Test(cond_hir));
+    }
     let hir_exprs = mk.convert_exprs(body)?;
     let mut clause_ctx = mk.ctx_stack.pop_match_clause_ctx();
     Ok((hir_exprs, extract_lvars(&mut clause_ctx.lvars)))
@@ -215,41 +302,239 @@ fn convert_match(
     mk: &mut HirMaker,
     value: &HirExpression,
     pat: &AstPattern,
-) -> Result<Vec<Component>> {
+) -> Result<(Vec<Component>, Pat)> {
+    let literal = |ctor: Ctor| Pat::Ctor {
+        ctor,
+        ty: value.ty.clone(),
+        args: vec![],
+    };
     match &pat {
-        AstPattern::ExtractorPattern { names, params } => {
-            convert_extractor(mk, value, names, params)
-        }
+        AstPattern::ExtractorPattern {
+            names,
+            params,
+            locs,
+        } => convert_extractor(mk, value, names, params, locs),
         AstPattern::VariablePattern(name) => {
-            if name == "_" {
-                Ok(vec![])
+            let components = if name == "_" {
+                vec![]
             } else {
-                Ok(vec![Component::Bind(name.to_string(), value.clone())])
-            }
+                vec![Component::Bind(name.to_string(), value.clone())]
+            };
+            Ok((components, Pat::Wildcard))
         }
         AstPattern::BooleanLiteralPattern(b) => {
             check_ty_raw(value, "Bool")?;
             let hir_bool = Hir::boolean_literal(*b, LocationSpan::todo());
-            Ok(vec![make_eq_test(value, "Bool", hir_bool)])
+            let components = vec![make_eq_test(value, "Bool", hir_bool)];
+            Ok((components, literal(Ctor::Bool(*b))))
         }
         AstPattern::IntegerLiteralPattern(i) => {
             check_ty_raw(value, "Int")?;
             let hir_int = Hir::decimal_literal(*i, LocationSpan::todo());
-            Ok(vec![make_eq_test(value, "Int", hir_int)])
+            let components = vec![make_eq_test(value, "Int", hir_int)];
+            Ok((components, literal(Ctor::Literal(i.to_string()))))
         }
         AstPattern::FloatLiteralPattern(f) => {
             check_ty_raw(value, "Float")?;
             let hir_int = Hir::float_literal(*f, LocationSpan::todo());
-            Ok(vec![make_eq_test(value, "Float", hir_int)])
+            let components = vec![make_eq_test(value, "Float", hir_int)];
+            Ok((components, literal(Ctor::Literal(f.to_string()))))
         }
         AstPattern::StringLiteralPattern(s) => {
             check_ty_raw(value, "String")?;
             let hir_str = mk.convert_string_literal(s, &LocationSpan::todo());
-            Ok(vec![make_eq_test(value, "String", hir_str)])
+            let components = vec![make_eq_test(value, "String", hir_str)];
+            Ok((components, literal(Ctor::Literal(format!("{:?}", s)))))
+        }
+        AstPattern::RangePattern {
+            first,
+            last,
+            exclusive,
+        } => {
+            let (name, hir_first, hir_last, repr) = match (&**first, &**last) {
+                (AstPattern::IntegerLiteralPattern(a), AstPattern::IntegerLiteralPattern(b)) => (
+                    "Int",
+                    Hir::decimal_literal(*a, LocationSpan::todo()),
+                    Hir::decimal_literal(*b, LocationSpan::todo()),
+                    (a.to_string(), b.to_string()),
+                ),
+                (AstPattern::FloatLiteralPattern(a), AstPattern::FloatLiteralPattern(b)) => (
+                    "Float",
+                    Hir::float_literal(*a, LocationSpan::todo()),
+                    Hir::float_literal(*b, LocationSpan::todo()),
+                    (a.to_string(), b.to_string()),
+                ),
+                _ => {
+                    return Err(error::type_error(
+                        "both ends of a range pattern must be Int or Float",
+                    ))
+                }
+            };
+            check_ty_raw(value, name)?;
+            let last_op = if *exclusive { "<" } else { "<=" };
+            let components = vec![
+                make_cmp_test(value, name, ">=", hir_first),
+                make_cmp_test(value, name, last_op, hir_last),
+            ];
+            let dots = if *exclusive { "..." } else { ".." };
+            let ctor = Ctor::Literal(format!("{}{}{}", repr.0, dots, repr.1));
+            Ok((components, literal(ctor)))
+        }
+        AstPattern::OrPattern(alts) => convert_alternatives(mk, value, alts),
+        AstPattern::ArrayPattern {
+            before,
+            rest,
+            after,
+        } => convert_array_pattern(mk, value, before, rest, after),
+        AstPattern::BindingPattern { name, pattern } => {
+            let (mut components, pat) = convert_match(mk, value, pattern)?;
+            // eg. `p` of `p @ Some(x)` is a `Maybe::Some`
+            let bound = match &**pattern {
+                AstPattern::ExtractorPattern { names, locs, .. } => {
+                    let pat_base_ty = get_base_ty(mk, names, locs)?;
+                    let pat_ty = infer_pat_ty(mk, &pat_base_ty, &value.ty);
+                    Hir::bit_cast(pat_ty, value.clone())
+                }
+                _ => value.clone(),
+            };
+            components.push(Component::Bind(name.to_string(), bound));
+            Ok((components, pat))
         }
     }
 }
 
+/// Create components for match against `A | B | ...`.
+/// The tests of each alternative are merged into one test.
+fn convert_alternatives(
+    mk: &mut HirMaker,
+    value: &HirExpression,
+    alts: &[AstPattern],
+) -> Result<(Vec<Component>, Pat)> {
+    let mut tests = vec![];
+    let mut pats = vec![];
+    for alt in alts {
+        let (components, pat) = convert_match(mk, value, alt)?;
+        let mut alt_tests = vec![];
+        for component in components {
+            match component {
+                Component::Test(t) => alt_tests.push(t),
+                Component::Bind(name, _) => {
+                    panic!("[BUG] variable `{}' is bound in an alternative", name)
+                }
+            }
+        }
+        let test = alt_tests
+            .into_iter()
+            .reduce(|a, b| Hir::logical_and(a, b, LocationSpan::todo()))
+            .unwrap_or_else(|| Hir::boolean_literal(true, LocationSpan::todo()));
+        tests.push(test);
+        pats.push(pat);
+    }
+    let test = tests
+        .into_iter()
+        .reduce(|a, b| Hir::logical_or(a, b, LocationSpan::todo()))
+        .unwrap();
+    Ok((vec![Component::Test(test)], Pat::Or(pats)))
+}
+
+/// Create components for match against `[a, b, *rest, c]`
+fn convert_array_pattern(
+    mk: &mut HirMaker,
+    value: &HirExpression,
+    before: &[AstPattern],
+    rest: &Option<String>,
+    after: &[AstPattern],
+) -> Result<(Vec<Component>, Pat)> {
+    if value.ty.erasure().base_name != "Array" {
+        return Err(error::type_error(format!(
+            "expr of `{}' never matches to an array pattern",
+            value.ty
+        )));
+    }
+    let ary_ty = value.ty.clone();
+    let item_ty = ary_ty.type_args()[0].clone();
+    let n_items = (before.len() + after.len()) as i64;
+    let length = Hir::method_call(
+        ty::raw("Int"),
+        value.clone(),
+        method_fullname_raw("Array", "length"),
+        vec![],
+        Default::default(),
+        false,
+    );
+    // eg. `value.length - 2`
+    let from_last = |n: i64| {
+        Hir::method_call(
+            ty::raw("Int"),
+            length.clone(),
+            method_fullname_raw("Int", "-"),
+            vec![Hir::decimal_literal(n, LocationSpan::todo())],
+            Default::default(),
+            false,
+        )
+    };
+    let op = if rest.is_some() { ">=" } else { "==" };
+    let n_items_hir = Hir::decimal_literal(n_items, LocationSpan::todo());
+    let mut components = vec![make_cmp_test(&length, "Int", op, n_items_hir)];
+    let mut pats = vec![];
+    let indices = (0..before.len())
+        .map(|i| Hir::decimal_literal(i as i64, LocationSpan::todo()))
+        .chain((0..after.len()).map(|i| from_last((after.len() - i) as i64)));
+    for (pattern, idx) in before.iter().chain(after.iter()).zip(indices) {
+        // eg. `value[0]`
+        let item = Hir::method_call(
+            item_ty.clone(),
+            value.clone(),
+            method_fullname_raw("Array", "[]"),
+            vec![idx],
+            Default::default(),
+            false,
+        );
+        let (mut c, pat) = convert_match(mk, &item, pattern)?;
+        components.append(&mut c);
+        pats.push(pat);
+    }
+    if let Some(name) = rest {
+        if name != "_" {
+            // eg. `value.drop(1).first_n(value.length - 2)`
+            let mut rest_hir = Hir::method_call(
+                ary_ty.clone(),
+                value.clone(),
+                method_fullname_raw("Array", "drop"),
+                vec![Hir::decimal_literal(
+                    before.len() as i64,
+                    LocationSpan::todo(),
+                )],
+                Default::default(),
+                false,
+            );
+            if !after.is_empty() {
+                rest_hir = Hir::method_call(
+                    ary_ty.clone(),
+                    rest_hir,
+                    method_fullname_raw("Array", "first_n"),
+                    vec![from_last(n_items)],
+                    Default::default(),
+                    false,
+                );
+            }
+            components.push(Component::Bind(name.to_string(), rest_hir));
+        }
+    }
+    let ctor = Ctor::Array {
+        n_before: before.len(),
+        has_rest: rest.is_some(),
+    };
+    Ok((
+        components,
+        Pat::Ctor {
+            ctor,
+            ty: value.ty.clone(),
+            args: pats,
+        },
+    ))
+}
+
 /// Check the type of `value` is `ty::raw(name)`
 fn check_ty_raw(value: &HirExpression, name: &str) -> Result<()> {
     if value.ty != ty::raw(name) {
@@ -263,10 +548,15 @@ fn check_ty_raw(value: &HirExpression, name: &str) -> Result<()> {
 
 /// Make `lhs == rhs`
 fn make_eq_test(value: &HirExpression, name: &str, rhs: HirExpression) -> Component {
+    make_cmp_test(value, name, "==", rhs)
+}
+
+/// Make `lhs <op> rhs` (eg. `lhs <= rhs`)
+fn make_cmp_test(value: &HirExpression, name: &str, op: &str, rhs: HirExpression) -> Component {
     let test = Hir::method_call(
         ty::raw("Bool"),
         value.clone(),
-        method_fullname_raw(name, "=="),
+        method_fullname_raw(name, op),
         vec![rhs],
         Default::default(),
         true,
@@ -280,9 +570,10 @@ fn convert_extractor(
     value: &HirExpression,
     names: &[String],
     param_patterns: &[AstPattern],
-) -> Result<Vec<Component>> {
+    locs: &LocationSpan,
+) -> Result<(Vec<Component>, Pat)> {
     // eg. `ty::raw("Maybe::Some")`
-    let pat_base_ty = get_base_ty(mk, names)?;
+    let pat_base_ty = get_base_ty(mk, names, locs)?;
     let pat_ty = infer_pat_ty(mk, &pat_base_ty, &value.ty);
     if !mk.class_dict.conforms(&pat_ty, &value.ty) {
         return Err(error::type_error(&format!(
@@ -291,16 +582,20 @@ fn convert_extractor(
         )));
     }
     let cast_value = Hir::bit_cast(pat_ty.clone(), value.clone());
-    let mut components = extract_props(mk, &cast_value, &pat_ty, param_patterns)?;
+    let (mut components, args) = extract_props(mk, &cast_value, &pat_ty, param_patterns)?;
 
     let test = Component::Test(test_class(mk, value, &pat_ty));
     components.insert(0, test);
-    Ok(components)
+    let pat = Pat::Ctor {
+        ctor: Ctor::Class(pat_ty.erasure().to_class_fullname()),
+        ty: value.ty.clone(),
+        args,
+    };
+    Ok((components, pat))
 }
 
-fn get_base_ty(mk: &mut HirMaker, names: &[String]) -> Result<Erasure> {
-    let expr =
-        mk.convert_capitalized_name(&UnresolvedConstName(names.to_vec()), &LocationSpan::todo())?;
+fn get_base_ty(mk: &mut HirMaker, names: &[String], locs: &LocationSpan) -> Result<Erasure> {
+    let expr = mk.convert_capitalized_name(&UnresolvedConstName(names.to_vec()), locs)?;
     if expr.ty.is_metaclass() || expr.ty.is_typaram_ref() {
         return Ok(expr.ty.instance_ty().erasure());
     }
@@ -350,7 +645,7 @@ fn extract_props(
     value: &HirExpression,
     pat_ty: &TermTy,
     patterns: &[AstPattern],
-) -> Result<Vec<Component>> {
+) -> Result<(Vec<Component>, Vec<Pat>)> {
     let ivars = class_props(mk, pat_ty)?; // eg. ("value", ty::spe("Maybe", "Int"))
     if ivars.len() != patterns.len() {
         return Err(error::program_error(&format!(
@@ -361,6 +656,7 @@ fn extract_props(
         )));
     }
     let mut components = vec![];
+    let mut pats = vec![];
     for i in 0..ivars.len() {
         let (name_, ty) = &ivars[i];
         let name = name_.replace('@', "");
@@ -373,9 +669,11 @@ fn extract_props(
             Default::default(),
             false, // todo!("check whether virtual or not"),
         );
-        components.append(&mut convert_match(mk, &ivar_ref, &patterns[i])?);
+        let (mut c, pat) = convert_match(mk, &ivar_ref, &patterns[i])?;
+        components.append(&mut c);
+        pats.push(pat);
     }
-    Ok(components)
+    Ok((components, pats))
 }
 
 /// Create `expr.class == cls`
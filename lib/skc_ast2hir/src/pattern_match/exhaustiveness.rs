@@ -0,0 +1,304 @@
+//! Exhaustiveness and reachability check of match clauses.
+//!
+//! This is based on the "usefulness" algorithm described in
+//! "Warnings for pattern matching" (Luc Maranget, 2007).
+//! Patterns are simplified into constructors (enum cases, `true`/`false`,
+//! literals, etc.) and wildcards. A set of constructors is regarded as
+//! complete only for `Bool`, enums and (non-inheritable) classes; for other
+//! types (eg. `Int`) a wildcard is needed to cover all the values.
+use crate::class_dict::ClassDict;
+use shiika_core::names::ClassFullname;
+use shiika_core::{ty, ty::TermTy};
+use std::fmt;
+
+/// Simplified pattern
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum Pat {
+    /// Variable pattern (including `_`)
+    Wildcard,
+    Ctor {
+        ctor: Ctor,
+        /// Type of the value matched against this pattern
+        ty: TermTy,
+        args: Vec<Pat>,
+    },
+    /// `A | B | ...`
+    Or(Vec<Pat>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ctor {
+    Bool(bool),
+    /// Enum case or class (eg. `Maybe::Some`)
+    Class(ClassFullname),
+    /// Int, Float or String literal (or a range of them)
+    Literal(String),
+    /// Array pattern (eg. `[a, *rest, b]`.) Patterns with the same shape
+    /// are regarded as the same constructor.
+    Array {
+        n_before: usize,
+        has_rest: bool,
+    },
+}
+
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pat::Wildcard => write!(f, "_"),
+            Pat::Or(pats) => {
+                let pats = pats.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+                write!(f, "{}", pats.join(" | "))
+            }
+            Pat::Ctor {
+                ctor: Ctor::Array { n_before, has_rest },
+                args,
+                ..
+            } => {
+                let mut items = args.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+                if *has_rest {
+                    items.insert(*n_before, "*_".to_string());
+                }
+                write!(f, "[{}]", items.join(", "))
+            }
+            Pat::Ctor { ctor, args, .. } => {
+                match ctor {
+                    Ctor::Bool(b) => write!(f, "{}", b)?,
+                    // Omit the namespace (eg. `Some` rather than `Maybe::Some`)
+                    Ctor::Class(name) => write!(f, "{}", name.0.rsplit("::").next().unwrap())?,
+                    Ctor::Literal(s) => write!(f, "{}", s)?,
+                    Ctor::Array { .. } => unreachable!(),
+                }
+                if !args.is_empty() {
+                    let args = args.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+                    write!(f, "({})", args.join(", "))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Returns true if values of `ty` are checked for exhaustiveness
+pub fn is_checked_type(class_dict: &ClassDict, ty: &TermTy) -> bool {
+    if *ty == ty::raw("Bool") {
+        return true;
+    }
+    !ty.is_typaram_ref()
+        && class_dict
+            .lookup_class(&ty.erasure().to_class_fullname())
+            .is_some_and(|cls| cls.enum_cases.is_some())
+}
+
+/// Returns true if `pat` matches some value that none of `prev` matches
+pub fn is_useful(class_dict: &ClassDict, prev: &[Pat], pat: &Pat) -> bool {
+    let matrix = prev.iter().map(|p| vec![p.clone()]).collect::<Vec<_>>();
+    useful(class_dict, &matrix, std::slice::from_ref(pat))
+}
+
+/// Returns the patterns which are not covered by `pats`
+pub fn missing_patterns(class_dict: &ClassDict, pats: &[Pat]) -> Vec<Pat> {
+    let matrix = pats.iter().map(|p| vec![p.clone()]).collect::<Vec<_>>();
+    missing(class_dict, &matrix, 1)
+        .into_iter()
+        .map(|mut row| row.remove(0))
+        .collect()
+}
+
+/// Returns true if `row` matches some value that no rows in `matrix` match
+fn useful(class_dict: &ClassDict, matrix: &[Vec<Pat>], row: &[Pat]) -> bool {
+    let Some((head, rest)) = row.split_first() else {
+        return matrix.is_empty();
+    };
+    let matrix = &expand_or(matrix);
+    match head {
+        Pat::Or(alts) => alts
+            .iter()
+            .any(|alt| useful(class_dict, matrix, &concat(std::slice::from_ref(alt), rest))),
+        Pat::Ctor { ctor, args, .. } => {
+            let m = specialize(matrix, ctor, args.len());
+            useful(class_dict, &m, &concat(args, rest))
+        }
+        Pat::Wildcard => match complete_signature(class_dict, matrix) {
+            Some(sig) => sig.iter().any(|(ctor, arity)| {
+                let m = specialize(matrix, ctor, *arity);
+                useful(class_dict, &m, &concat(&wildcards(*arity), rest))
+            }),
+            None => useful(class_dict, &default_matrix(matrix), rest),
+        },
+    }
+}
+
+/// Returns the rows of patterns (of length `width`) which are not covered
+/// by `matrix`
+fn missing(class_dict: &ClassDict, matrix: &[Vec<Pat>], width: usize) -> Vec<Vec<Pat>> {
+    if width == 0 {
+        return if matrix.is_empty() {
+            vec![vec![]]
+        } else {
+            vec![]
+        };
+    }
+    let matrix = &expand_or(matrix);
+    if let Some(sig) = complete_signature(class_dict, matrix) {
+        let ty = head_ty(matrix).unwrap();
+        let mut rows = vec![];
+        for (ctor, arity) in sig {
+            let m = specialize(matrix, &ctor, arity);
+            for row in missing(class_dict, &m, arity + width - 1) {
+                let (args, rest) = row.split_at(arity);
+                let head = Pat::Ctor {
+                    ctor: ctor.clone(),
+                    ty: ty.clone(),
+                    args: args.to_vec(),
+                };
+                rows.push(concat(&[head], rest));
+            }
+        }
+        return rows;
+    }
+
+    let rests = missing(class_dict, &default_matrix(matrix), width - 1);
+    if rests.is_empty() {
+        return vec![];
+    }
+    // Constructors which do not appear in the first column
+    let heads = match (signature(class_dict, matrix), head_ty(matrix)) {
+        (Some(sig), Some(ty)) => {
+            let present = head_ctors(matrix);
+            sig.into_iter()
+                .filter(|(ctor, _)| !present.contains(&ctor))
+                .map(|(ctor, arity)| Pat::Ctor {
+                    ctor,
+                    ty: ty.clone(),
+                    args: wildcards(arity),
+                })
+                .collect()
+        }
+        _ => vec![Pat::Wildcard],
+    };
+    heads
+        .iter()
+        .flat_map(|head| {
+            rests
+                .iter()
+                .map(|rest| concat(std::slice::from_ref(head), rest))
+        })
+        .collect()
+}
+
+/// Returns the constructors of the first column if they cover all the
+/// values of its type
+fn complete_signature(class_dict: &ClassDict, matrix: &[Vec<Pat>]) -> Option<Vec<(Ctor, usize)>> {
+    let sig = signature(class_dict, matrix)?;
+    let present = head_ctors(matrix);
+    if sig.iter().all(|(ctor, _)| present.contains(&ctor)) {
+        Some(sig)
+    } else {
+        None
+    }
+}
+
+/// Returns all the constructors (and their arity) of the type of the
+/// first column. Returns None if there are infinitely many (eg. `Int`) or
+/// the first column has no constructors.
+fn signature(class_dict: &ClassDict, matrix: &[Vec<Pat>]) -> Option<Vec<(Ctor, usize)>> {
+    let ty = head_ty(matrix)?;
+    if *ty == ty::raw("Bool") {
+        return Some(vec![(Ctor::Bool(true), 0), (Ctor::Bool(false), 0)]);
+    }
+    if ty.is_typaram_ref() {
+        return None;
+    }
+    let cls = class_dict.lookup_class(&ty.erasure().to_class_fullname())?;
+    if let Some(cases) = &cls.enum_cases {
+        return Some(
+            cases
+                .iter()
+                .map(|case| {
+                    let arity = class_dict.get_class(case).ivars.len();
+                    (Ctor::Class(case.clone()), arity)
+                })
+                .collect(),
+        );
+    }
+    // A pattern of the class itself matches any value of it unless it
+    // has subclasses.
+    if cls.inheritable {
+        return None;
+    }
+    let this = Ctor::Class(cls.fullname());
+    matrix.iter().find_map(|row| match &row[0] {
+        Pat::Ctor { ctor, args, .. } if *ctor == this => Some(vec![(this.clone(), args.len())]),
+        _ => None,
+    })
+}
+
+/// Returns the type of the first column
+fn head_ty(matrix: &[Vec<Pat>]) -> Option<&TermTy> {
+    matrix.iter().find_map(|row| match &row[0] {
+        Pat::Ctor { ty, .. } => Some(ty),
+        Pat::Wildcard | Pat::Or(_) => None,
+    })
+}
+
+/// Returns the constructors in the first column
+fn head_ctors(matrix: &[Vec<Pat>]) -> Vec<&Ctor> {
+    matrix
+        .iter()
+        .filter_map(|row| match &row[0] {
+            Pat::Ctor { ctor, .. } => Some(ctor),
+            Pat::Wildcard | Pat::Or(_) => None,
+        })
+        .collect()
+}
+
+/// Keep the rows which may match `ctor` and expand the arguments of the
+/// first column
+fn specialize(matrix: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+    matrix
+        .iter()
+        .filter_map(|row| match &row[0] {
+            Pat::Ctor { ctor: c, args, .. } if c == ctor => Some(concat(args, &row[1..])),
+            Pat::Ctor { .. } => None,
+            Pat::Wildcard => Some(concat(&wildcards(arity), &row[1..])),
+            Pat::Or(_) => panic!("[BUG] or-pattern must be expanded"),
+        })
+        .collect()
+}
+
+/// Keep the rows whose first column is a wildcard and remove the column
+fn default_matrix(matrix: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    matrix
+        .iter()
+        .filter(|row| matches!(row[0], Pat::Wildcard))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+/// Expand the rows whose first column is an or-pattern into the rows for
+/// each alternative
+fn expand_or(matrix: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+    let mut rows = vec![];
+    for row in matrix {
+        match &row[0] {
+            Pat::Or(alts) => {
+                let expanded = alts
+                    .iter()
+                    .map(|alt| concat(std::slice::from_ref(alt), &row[1..]))
+                    .collect::<Vec<_>>();
+                rows.append(&mut expand_or(&expanded));
+            }
+            _ => rows.push(row.clone()),
+        }
+    }
+    rows
+}
+
+fn wildcards(n: usize) -> Vec<Pat> {
+    vec![Pat::Wildcard; n]
+}
+
+fn concat(a: &[Pat], b: &[Pat]) -> Vec<Pat> {
+    a.iter().chain(b.iter()).cloned().collect()
+}
@@ -1,5 +1,5 @@
 use crate::type_index::TypeIndex;
-use shiika_ast::{AstMethodSignature, UnresolvedTypeName};
+use shiika_ast::{AstMethodSignature, UnresolvedTypeName, Visibility};
 use shiika_core::names::ClassFullname;
 use shiika_core::{names::method_fullname, ty, ty::TermTy};
 use skc_corelib::{self};
@@ -62,6 +62,7 @@ fn make_hir_sig(
         asyncness: Asyncness::Unknown,
         is_virtual: false,
         is_rust: true,
+        is_private: ast_sig.visibility == Visibility::Private,
     }
 }
 
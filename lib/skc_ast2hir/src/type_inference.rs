@@ -1,7 +1,11 @@
+mod holes;
+pub mod lvar_ty;
+pub mod method_ret_ty;
 mod tmp_ty;
 use crate::convert_exprs::block::BlockTaker;
 use crate::error::type_error;
 use anyhow::{anyhow, Context, Result};
+pub use holes::Holes;
 use shiika_core::ty::{self, LitTy, TermTy, TyParam, TyParamKind, TyParamRef};
 use std::collections::HashMap;
 use std::fmt;
@@ -17,6 +21,8 @@ pub struct Infer {
     pub ans: Answer,
     // For error message
     vars: Vec<(Id, TyParamRef)>,
+    /// `Holes` appear in this method call
+    holes: Vec<(Id, TyParamRef)>,
 }
 
 impl Infer {
@@ -25,43 +31,63 @@ impl Infer {
         class_typarams: &[TyParam],
         class_tyargs: &[TermTy],
         given_method_tyargs: Option<Vec<TermTy>>,
+        given_holes: &Holes,
     ) -> Infer {
         let mut ans = Answer::new();
         let mut i = 0;
         let mut vars = vec![];
         let mut method_tyargs = vec![];
+        // Types to be inferred from this method call
+        let mut holes = vec![];
+        for (tpref, answer) in given_holes.iter() {
+            holes.push((i, tpref.clone()));
+            if let Some(t) = answer {
+                ans.merge(i, TmpTy::from(t));
+            }
+            i += 1;
+        }
         // Class-wise typarams
         if !class_tyargs.is_empty() {
-            for tpref in
-                ty::typarams_to_typaram_refs(class_typarams, TyParamKind::Class, false).into_iter()
+            for (tpref, tyarg) in
+                ty::typarams_to_typaram_refs(class_typarams, TyParamKind::Class, false)
+                    .into_iter()
+                    .zip(class_tyargs.iter())
             {
-                vars.push((i, tpref));
-                ans.merge(i, TmpTy::make(&class_tyargs[i], Default::default()));
-                i += 1;
+                match TmpTy::make(tyarg, &holes) {
+                    // eg. `T` of `Array<T>#push` is `?0` when the receiver
+                    // is from `let a = Array.new`
+                    TmpTy::Unknown(id) => vars.push((id, tpref)),
+                    t => {
+                        vars.push((i, tpref));
+                        ans.merge(i, t);
+                        i += 1;
+                    }
+                }
             }
         }
-        let n_class_typarams = i;
         // Method-wise typarams
-        for tpref in
+        for (j, tpref) in
             ty::typarams_to_typaram_refs(&block_taker.typarams(), TyParamKind::Method, false)
                 .into_iter()
+                .enumerate()
         {
             vars.push((i, tpref));
             method_tyargs.push(TmpTy::unknown(i));
             if let Some(v) = given_method_tyargs.as_ref() {
-                ans.merge(i, TmpTy::make(&v[i - n_class_typarams], Default::default()));
+                ans.merge(i, TmpTy::make(&v[j], Default::default()));
             }
             i += 1;
         }
 
         // Note: passing `vars` to TmpTy::make because these may refer
-        // class-wise or method-wise type parameters.
+        // class-wise or method-wise type parameters (or holes.)
+        let all_vars = vars.iter().chain(holes.iter()).cloned().collect::<Vec<_>>();
         let param_tys = block_taker
             .param_tys()
             .iter()
-            .map(|t| TmpTy::make(&t, &vars))
+            .map(|t| TmpTy::make(&t, &all_vars))
             .collect::<Vec<_>>();
-        let ret_ty = TmpTy::make(&block_taker.ret_ty(), &vars);
+        let ret_ty = TmpTy::make(&block_taker.ret_ty(), &all_vars);
 
         Infer {
             method_tyargs,
@@ -69,27 +95,53 @@ impl Infer {
             ret_ty,
             ans,
             vars,
+            holes,
         }
     }
 
-    // Give the argument types for hint.
-    // Length of arg_tys may be shorter than param_tys (when block is given).
-    pub fn set_arg_tys(&mut self, arg_tys: &[&TermTy]) -> Result<()> {
-        let equations = self
-            .param_tys
-            .iter()
-            .zip(arg_tys.iter())
-            .map(|(l, r)| Equation(l.clone(), TmpTy::from(r)))
-            .collect::<Vec<_>>();
-        unify(equations, &mut self.ans).with_context(|| self.error())
-    }
-
     pub fn set_block_ty(&mut self, block_ty: &TermTy) -> Result<()> {
         let l = self.param_tys.last().unwrap().clone();
-        let equation = Equation(l, TmpTy::from(block_ty));
+        let equation = Equation(l, TmpTy::make(block_ty, &self.holes));
+        unify(vec![equation], &mut self.ans).with_context(|| self.error())
+    }
+
+    /// Give the type of the argument at `idx` for hint.
+    /// (`idx` may not reach the last param when block is given)
+    pub fn set_arg_ty(&mut self, idx: usize, arg_ty: &TermTy) -> Result<()> {
+        let equation = Equation(
+            self.param_tys[idx].clone(),
+            TmpTy::make(arg_ty, &self.holes),
+        );
         unify(vec![equation], &mut self.ans).with_context(|| self.error())
     }
 
+    /// Returns the parameter types of the `Fn` expected for the argument
+    /// at `idx`, if they are known at this point.
+    pub fn fn_arg_param_tys(&self, idx: usize) -> Option<Vec<TermTy>> {
+        match self.param_tys.get(idx)? {
+            TmpTy::Literal {
+                base_name,
+                type_args,
+                ..
+            } if base_name.starts_with("Fn") => self
+                .ans
+                .apply_to_vec(&type_args[0..type_args.len() - 1])
+                .ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns the types found for the holes
+    pub fn hole_answers(&self) -> Vec<(TyParamRef, TermTy)> {
+        self.holes
+            .iter()
+            .filter_map(|(id, tpref)| {
+                let t = self.ans.apply_to(&TmpTy::Unknown(*id)).ok()?;
+                Some((tpref.clone(), t))
+            })
+            .collect()
+    }
+
     pub fn block_param_tys(&self) -> Result<Vec<TermTy>> {
         let Some(block_ty) = self.param_tys.last() else {
             return Err(anyhow!("this method does not take a block"));
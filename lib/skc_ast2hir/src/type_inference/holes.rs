@@ -0,0 +1,70 @@
+use super::lvar_ty::has_hole;
+use shiika_core::ty::{self, TermTy, TyParamKind, TyParamRef};
+
+/// Types which are not known yet and should be inferred from the later
+/// part of the program.
+///
+/// eg. `T` of `Array<T>` is unknown at the first line but turns out to be
+/// `Int` at the second line.
+///
+/// ```sk
+/// let a = Array.new
+/// a.push(1)
+/// ```
+///
+/// A hole is represented as a `TyParamRef` named like `?0` so that it can
+/// be passed around like other types.
+#[derive(Debug, Default)]
+pub struct Holes {
+    items: Vec<(TyParamRef, Option<TermTy>)>,
+    /// Counter to give unique names to holes
+    ct: usize,
+}
+
+impl Holes {
+    /// Create `n` new holes and returns them as types
+    pub fn open(&mut self, n: usize) -> Vec<TermTy> {
+        (0..n)
+            .map(|_| {
+                let tpref = ty::typaram_ref(format!("?{}", self.ct), TyParamKind::Method, 0);
+                self.ct += 1;
+                self.items.push((tpref.clone(), None));
+                tpref.into_term_ty()
+            })
+            .collect()
+    }
+
+    /// Remove the holes and returns the inferred types (if any)
+    pub fn close(&mut self, holes: &[TermTy]) -> Vec<Option<TermTy>> {
+        holes
+            .iter()
+            .map(|t| {
+                let i = self.position(t);
+                self.items.remove(i).1
+            })
+            .collect()
+    }
+
+    fn position(&self, t: &TermTy) -> usize {
+        self.items
+            .iter()
+            .position(|(tpref, _)| t.equals_to(&tpref.clone().into_term_ty()))
+            .expect("[BUG] unknown hole")
+    }
+
+    /// Record the inferred type of a hole. Only the first one is taken.
+    pub fn resolve(&mut self, tpref: &TyParamRef, t: TermTy) {
+        if has_hole(&t) {
+            return;
+        }
+        if let Some((_, answer)) = self.items.iter_mut().find(|(x, _)| x == tpref) {
+            if answer.is_none() {
+                *answer = Some(t);
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(TyParamRef, Option<TermTy>)> {
+        self.items.iter()
+    }
+}
@@ -0,0 +1,225 @@
+//! Infers the type of a local variable from its later uses when it cannot
+//! be decided only from the right hand side.
+//!
+//! ```sk
+//! let a = Array.new   # `Array<Int>` because of the next line
+//! a.push(1)
+//!
+//! let f = fn(x){ x + 1 }   # `x` is `Int` because of the next line
+//! p f(2)
+//! ```
+//!
+//! This is done by converting the following expressions once with the
+//! variable typed with `Holes` (eg. `Array<?0>`), and then converting the
+//! declaration again with the inferred type. Other declarations which need
+//! inference found during this pass are typed with holes too instead of
+//! starting another pass, so that the compile time does not grow
+//! exponentially with the number of such declarations.
+//!
+//! It is an error if the type could not be inferred.
+use crate::error;
+use crate::hir_maker::HirMaker;
+use shiika_ast::{AstExpression, AstExpressionBody, AstMethodCall, BlockParam, LocationSpan};
+use shiika_core::ty::{self, TermTy, TyBody};
+use skc_hir::{Hir, HirExpression};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/// An lvar declaration whose type is being inferred
+#[derive(Debug)]
+pub struct HoledLVar {
+    key: (Rc<PathBuf>, usize),
+    hole_ty: TermTy,
+    holes: Vec<TermTy>,
+    /// For lambdas, whether each parameter has type annotation
+    typed_params: Option<Vec<bool>>,
+}
+
+impl<'hir_maker> HirMaker<'hir_maker> {
+    /// Convert an expression in a sequence. `rest` is the expressions after
+    /// `expr` in the sequence.
+    pub(crate) fn convert_expr_in_seq(
+        &mut self,
+        expr: &AstExpression,
+        rest: &[AstExpression],
+    ) -> anyhow::Result<HirExpression> {
+        let AstExpressionBody::LVarDecl {
+            name,
+            rhs,
+            readonly,
+        } = &expr.body
+        else {
+            return self.convert_expr(expr);
+        };
+        let Some(key) = decl_key(&expr.locs) else {
+            return self.convert_expr(expr);
+        };
+        if let Some(inferred) = self.inferred_lvar_tys.get(&key).cloned() {
+            return match inferred {
+                Some(t) => self.convert_lvar_decl(name, rhs, readonly, Some(&t), &expr.locs),
+                // Report the parameter whose type is unknown
+                None if matches!(rhs.body, AstExpressionBody::LambdaExpr { .. }) => {
+                    self.convert_expr(expr)
+                }
+                None => Err(error::cannot_infer_lvar_ty(name, &expr.locs)),
+            };
+        }
+        let Some((hole_ty, holes)) = self.lvar_hole_ty(rhs) else {
+            return self.convert_expr(expr);
+        };
+        let holed = HoledLVar {
+            key: key.clone(),
+            hole_ty: hole_ty.clone(),
+            holes,
+            typed_params: match &rhs.body {
+                AstExpressionBody::LambdaExpr { params, .. } => {
+                    Some(params.iter().map(|p| p.opt_typ.is_some()).collect())
+                }
+                _ => None,
+            },
+        };
+        if let Some(holed_lvars) = &mut self.lvar_inference {
+            // Another pass is running; just collect the uses of this lvar
+            let is_lambda = holed.typed_params.is_some();
+            holed_lvars.push(holed);
+            if is_lambda {
+                // The body may use other lvars being inferred
+                let snapshot = self.snapshot();
+                let _ = self.convert_expr_expecting(rhs, &hole_ty);
+                self.restore(snapshot);
+            }
+            self.ctx_stack
+                .declare_lvar(name, hole_ty.clone(), *readonly);
+            return Ok(Hir::lvar_ref(hole_ty, name.to_string(), expr.locs.clone()));
+        }
+        self.infer_lvar_tys(name, *readonly, holed, rest);
+        self.convert_expr_in_seq(expr, rest)
+    }
+
+    /// Convert `rest` speculatively to infer the type of the lvar and the
+    /// ones declared in `rest`. The results are stored in
+    /// `self.inferred_lvar_tys`.
+    fn infer_lvar_tys(
+        &mut self,
+        name: &str,
+        readonly: bool,
+        holed: HoledLVar,
+        rest: &[AstExpression],
+    ) {
+        let snapshot = self.snapshot();
+        self.ctx_stack
+            .declare_lvar(name, holed.hole_ty.clone(), readonly);
+        self.lvar_inference = Some(vec![holed]);
+        for (i, expr) in rest.iter().enumerate() {
+            if self.convert_expr_in_seq(expr, &rest[i + 1..]).is_err() {
+                break;
+            }
+        }
+        let holed_lvars = self.lvar_inference.take().unwrap();
+        self.restore(snapshot);
+        for (i, holed) in holed_lvars.into_iter().enumerate() {
+            let answers = self.holes.close(&holed.holes);
+            let inferred = inferred_ty(&holed, answers);
+            // The others may be inferred by their own pass (eg. when its
+            // uses depend on the parameter types of a lambda being inferred)
+            if i == 0 || inferred.is_some() {
+                self.inferred_lvar_tys.insert(holed.key, inferred);
+            }
+        }
+    }
+
+    /// Returns the type of the lvar with holes, if `rhs` is one of:
+    /// - `fn(x){ ... }` (a lambda without parameter types)
+    /// - `Foo.new` (`Foo` is a generic class)
+    fn lvar_hole_ty(&mut self, rhs: &AstExpression) -> Option<(TermTy, Vec<TermTy>)> {
+        match &rhs.body {
+            AstExpressionBody::LambdaExpr { params, is_fn, .. }
+                if *is_fn && lacks_param_ty(params) =>
+            {
+                let holes = self.holes.open(params.len() + 1);
+                let ty = ty::spe(format!("Fn{}", params.len()), holes.clone());
+                Some((ty, holes))
+            }
+            AstExpressionBody::MethodCall(AstMethodCall {
+                receiver_expr: Some(receiver_expr),
+                method_name,
+                args,
+                type_args,
+                ..
+            }) if method_name.0 == "new" && type_args.is_empty() && args.is_empty() => {
+                if !matches!(receiver_expr.body, AstExpressionBody::CapitalizedName(_)) {
+                    return None;
+                }
+                let snapshot = self.snapshot();
+                let receiver = self.convert_expr(receiver_expr);
+                self.restore(snapshot);
+                let receiver_ty = receiver.ok()?.ty;
+                if !receiver_ty.is_metaclass()
+                    || receiver_ty.type_args().iter().any(|t| !t.is_typaram_ref())
+                {
+                    return None;
+                }
+                let base_name = receiver_ty.erasure().base_name;
+                let n_typarams = self
+                    .class_dict
+                    .find_type(&shiika_core::names::type_fullname(&base_name))?
+                    .base()
+                    .typarams
+                    .len();
+                if n_typarams == 0 {
+                    return None;
+                }
+                let holes = self.holes.open(n_typarams);
+                Some((ty::spe(base_name, holes.clone()), holes))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns true if any of the parameters does not have type annotation
+pub(crate) fn lacks_param_ty(params: &[BlockParam]) -> bool {
+    params.iter().any(|p| p.opt_typ.is_none())
+}
+
+/// Returns true if `t` contains a hole
+pub(crate) fn has_hole(t: &TermTy) -> bool {
+    match &t.body {
+        TyBody::TyRaw(lit_ty) => lit_ty.type_args.iter().any(has_hole),
+        TyBody::TyPara(tpref) => tpref.name.starts_with('?'),
+    }
+}
+
+/// Returns the key to identify an lvar declaration
+fn decl_key(locs: &LocationSpan) -> Option<(Rc<PathBuf>, usize)> {
+    match locs {
+        LocationSpan::Just {
+            filepath, begin, ..
+        } => Some((filepath.clone(), begin.pos)),
+        LocationSpan::Empty => None,
+    }
+}
+
+/// Returns the type of the lvar made from the inferred types of the holes
+fn inferred_ty(holed: &HoledLVar, answers: Vec<Option<TermTy>>) -> Option<TermTy> {
+    match &holed.typed_params {
+        Some(typed_params) => {
+            // The return type is decided by the body
+            let mut tys = vec![];
+            for (typed, answer) in typed_params.iter().zip(answers) {
+                match answer {
+                    Some(t) => tys.push(t),
+                    // Has type annotation (so this will not be used)
+                    None if *typed => tys.push(ty::raw("Object")),
+                    None => return None,
+                }
+            }
+            tys.push(ty::raw("Object"));
+            Some(ty::spe(format!("Fn{}", typed_params.len()), tys))
+        }
+        None => {
+            let tyargs = answers.into_iter().collect::<Option<Vec<_>>>()?;
+            Some(ty::spe(holed.hole_ty.erasure().base_name, tyargs))
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Infers the return type of a method from its body.
+//!
+//! This is done only for private methods (i.e. defined with `private` or
+//! the name starts with `_`) without return type annotation. Other methods
+//! returns `Void` if the return type is omitted. It is an error if the
+//! return type could not be inferred (eg. recursive methods.)
+//!
+//! ```sk
+//! class A
+//!   def foo -> Int
+//!     bar + 1
+//!   end
+//!
+//!   private def bar   # Inferred as `-> Int`
+//!     2
+//!   end
+//! end
+//! ```
+use crate::ctx_stack::CtxStack;
+use crate::error;
+use crate::hir_maker::HirMaker;
+use crate::hir_maker_context::HirMakerContext;
+use anyhow::Result;
+use shiika_ast::{AstExpression, AstMethodSignature, Definition, Visibility};
+use shiika_core::names::{method_fullname, MethodFullname, Namespace, TypeFullname};
+use shiika_core::ty::{self, TermTy};
+
+/// A method whose return type is not inferred yet
+#[derive(Debug)]
+pub struct PendingMethod {
+    /// The context where the method is defined
+    ctx_stack: CtxStack,
+    sig: AstMethodSignature,
+    body_exprs: Vec<AstExpression>,
+}
+
+/// Returns true if the return type of the method should be inferred
+fn is_target(sig: &AstMethodSignature, body_exprs: &[AstExpression]) -> bool {
+    sig.ret_typ.is_none()
+        && (sig.visibility == Visibility::Private || sig.name.0.starts_with('_'))
+        && !body_exprs.is_empty()
+}
+
+impl<'hir_maker> HirMaker<'hir_maker> {
+    /// Collect the methods whose return type should be inferred.
+    /// Must be called before converting method bodies.
+    pub(crate) fn collect_pending_methods(
+        &mut self,
+        namespace: &Namespace,
+        opt_fullname: Option<&TypeFullname>,
+        defs: &[Definition],
+    ) {
+        for def in defs {
+            match def {
+                Definition::InstanceMethodDefinition { sig, body_exprs } => {
+                    if let Some(fullname) = opt_fullname {
+                        self.add_pending_method(fullname, sig, body_exprs);
+                    }
+                }
+                Definition::ClassMethodDefinition {
+                    sig, body_exprs, ..
+                } => {
+                    if let Some(fullname) = opt_fullname {
+                        let meta_name = fullname.meta_name().to_type_fullname();
+                        self.add_pending_method(&meta_name, sig, body_exprs);
+                    }
+                }
+                Definition::ClassDefinition { name, defs, .. }
+                | Definition::EnumDefinition { name, defs, .. } => {
+                    let fullname = namespace.class_fullname(name).to_type_fullname();
+                    self.collect_pending_methods_in(namespace, name.to_string(), &fullname, defs);
+                }
+                Definition::ModuleDefinition { name, defs, .. } => {
+                    let fullname = namespace.module_fullname(name).to_type_fullname();
+                    self.collect_pending_methods_in(namespace, name.to_string(), &fullname, defs);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn collect_pending_methods_in(
+        &mut self,
+        namespace: &Namespace,
+        firstname: String,
+        fullname: &TypeFullname,
+        defs: &[Definition],
+    ) {
+        let inner_namespace = namespace.add(firstname);
+        let typarams = self.class_dict.get_type(fullname).base().typarams.clone();
+        self.ctx_stack
+            .push(HirMakerContext::class(inner_namespace.clone(), typarams));
+        self.collect_pending_methods(&inner_namespace, Some(fullname), defs);
+        self.ctx_stack.pop_class_ctx();
+    }
+
+    fn add_pending_method(
+        &mut self,
+        type_fullname: &TypeFullname,
+        sig: &AstMethodSignature,
+        body_exprs: &[AstExpression],
+    ) {
+        if !is_target(sig, body_exprs) {
+            return;
+        }
+        let pending = PendingMethod {
+            ctx_stack: self.ctx_stack.clone(),
+            sig: sig.clone(),
+            body_exprs: body_exprs.to_vec(),
+        };
+        self.pending_methods
+            .insert(method_fullname(type_fullname.clone(), &sig.name.0), pending);
+    }
+
+    /// Decide the return type of the method if it is not yet. Returns true
+    /// if the signature is updated.
+    /// Returns error if the method is being inferred (i.e. recursive call.)
+    pub(crate) fn resolve_method_ret_ty(&mut self, fullname: &MethodFullname) -> Result<bool> {
+        if self.inferring_methods.contains(fullname) {
+            return Err(error::program_error(format!(
+                "cannot infer the return type of recursive method `{}'; please specify it",
+                fullname
+            )));
+        }
+        let Some(pending) = self.pending_methods.remove(fullname) else {
+            return Ok(false);
+        };
+        self.inferring_methods.push(fullname.clone());
+        let result = self.infer_method_ret_ty(fullname, &pending);
+        self.inferring_methods.pop();
+        let ret_ty = match result {
+            Ok(Some(t)) => t,
+            Ok(None) => {
+                return Err(error::program_error(format!(
+                    "cannot infer the return type of `{}'; please specify it",
+                    fullname
+                )))
+            }
+            // Fallback to the default return type. (The error will be
+            // reported when converting the method body.)
+            Err(_) => ty::raw("Void"),
+        };
+        self.class_dict.set_method_ret_ty(fullname, ret_ty);
+        Ok(true)
+    }
+
+    /// Convert the method body to know its type. The result is discarded.
+    fn infer_method_ret_ty(
+        &mut self,
+        fullname: &MethodFullname,
+        pending: &PendingMethod,
+    ) -> Result<Option<TermTy>> {
+        let signature = self
+            .class_dict
+            .find_method_sig(&fullname.type_name, &pending.sig.name)
+            .expect("[BUG] signature of pending method not found");
+        let snapshot = self.snapshot();
+        let lvar_inference = self.lvar_inference.take();
+        self.ctx_stack = pending.ctx_stack.clone();
+        let mut method_ctx = HirMakerContext::method(signature.clone(), None);
+        if let HirMakerContext::Method(c) = &mut method_ctx {
+            c.return_tys = Some(vec![]);
+        }
+        self.ctx_stack.push(method_ctx);
+        for param in &signature.params {
+            if param.has_default {
+                self.ctx_stack
+                    .declare_lvar(&param.name, param.ty.clone(), true);
+            }
+        }
+        let result = self.convert_exprs(&pending.body_exprs);
+        let return_tys = self
+            .ctx_stack
+            .method_ctx_mut()
+            .and_then(|c| c.return_tys.take())
+            .unwrap_or_default();
+        self.restore(snapshot);
+        self.lvar_inference = lvar_inference;
+
+        let body_ty = result?.ty;
+        let mut tys = return_tys;
+        if !body_ty.is_never_type() {
+            tys.push(body_ty);
+        }
+        if tys.is_empty() {
+            // The body never returns
+            return Ok(Some(ty::raw("Never")));
+        }
+        let mut ret_ty: Option<TermTy> = None;
+        for t in tys {
+            ret_ty = match ret_ty {
+                None => Some(t),
+                Some(u) => self.class_dict.nearest_common_ancestor(&u, &t),
+            };
+            if ret_ty.is_none() {
+                break;
+            }
+        }
+        Ok(ret_ty)
+    }
+
+    /// Record the type of `return` if inferring the return type of the
+    /// current method. Returns false otherwise.
+    pub(crate) fn record_return_ty(&mut self, arg_ty: &TermTy) -> bool {
+        if self.ctx_stack.lambda_ctx().is_some() {
+            return false;
+        }
+        match self
+            .ctx_stack
+            .method_ctx_mut()
+            .and_then(|c| c.return_tys.as_mut())
+        {
+            Some(tys) => {
+                tys.push(arg_ty.clone());
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -1,2 +1,4 @@
+pub mod sendable;
 pub mod subtyping;
 pub mod type_checking;
+pub mod variance;
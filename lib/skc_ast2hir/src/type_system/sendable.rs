@@ -0,0 +1,135 @@
+//! Checks the values shared with a task.
+//!
+//! A task created with `Task.spawn` may run on another thread when the
+//! multi-threaded runtime is used. So the lambda given to it must not
+//! capture `var` variables or objects which may be modified in parallel.
+use crate::class_dict::ClassDict;
+use crate::error;
+use anyhow::Result;
+use shiika_core::ty::TermTy;
+use skc_hir::*;
+use std::collections::HashSet;
+
+/// Methods which run the given lambda as a new task
+const SPAWNERS: [&str; 2] = ["Meta:Task#spawn", "Meta:Task#new"];
+
+/// Classes whose instances are never modified
+const IMMUTABLE_CLASSES: [&str; 6] = ["Bool", "Float", "Int", "Never", "String", "Void"];
+
+/// Classes which lock their state in Rust. Their type arguments must be
+/// sendable too because the values are passed to other tasks
+const SYNCHRONIZED_CLASSES: [&str; 7] = [
+    "Channel",
+    "File",
+    "Listener",
+    "Mutex",
+    "Process::Child",
+    "Socket",
+    "Task",
+];
+
+/// Classes which have mutable state in Rust (i.e. not visible as ivars)
+const RUST_MUTABLE_CLASSES: [&str; 3] = ["Array", "MutableString", "Random"];
+
+/// Returns true if the method starts a new task with its first argument.
+pub fn is_spawner(sig: &MethodSignature) -> bool {
+    SPAWNERS.contains(&sig.fullname.full_name.as_str())
+}
+
+/// Check the captures of the lambda passed to `Task.spawn`.
+/// The lambda must be written as a literal because the captures of a `Fn`
+/// value are not known from its type. The only exception is a spawner
+/// passing its own argument to another spawner (eg. `Task.spawn` calls
+/// `Task.new`), because the argument is checked at its call site.
+pub fn check_spawned_lambda(
+    class_dict: &ClassDict,
+    caller: Option<&MethodSignature>,
+    arg: &HirExpression,
+) -> Result<()> {
+    let captures = match &arg.node {
+        HirExpressionBase::HirLambdaExpr { captures, .. } => captures,
+        HirExpressionBase::HirArgRef {
+            idx: 0,
+            is_lambda: false,
+        } if caller.is_some_and(is_spawner) => return Ok(()),
+        _ => return Err(error::non_literal_lambda_for_task(&arg.locs)),
+    };
+    for cap in captures {
+        if !cap.readonly {
+            return Err(error::var_shared_with_task(&describe(cap), &arg.locs));
+        }
+        if !is_sendable(class_dict, &cap.ty, &mut HashSet::new()) {
+            return Err(error::non_sendable_shared_with_task(
+                &describe(cap),
+                &cap.ty,
+                &arg.locs,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns true if a value of `ty` can be used from multiple tasks at once.
+/// `visiting` is used to stop at recursive types.
+fn is_sendable(class_dict: &ClassDict, ty: &TermTy, visiting: &mut HashSet<String>) -> bool {
+    if ty.is_metaclass() {
+        return true;
+    }
+    // Type parameters may be anything and lambdas may capture anything.
+    if ty.is_typaram_ref() || ty.fn_x_info().is_some() {
+        return false;
+    }
+    let base_name = ty.base_type_name();
+    if IMMUTABLE_CLASSES.contains(&base_name.0.as_str()) {
+        return true;
+    }
+    if SYNCHRONIZED_CLASSES.contains(&base_name.0.as_str()) {
+        return ty
+            .type_args()
+            .iter()
+            .all(|t| is_sendable(class_dict, t, visiting));
+    }
+    if RUST_MUTABLE_CLASSES.contains(&base_name.0.as_str()) {
+        return false;
+    }
+    if !visiting.insert(ty.fullname.0.clone()) {
+        return true;
+    }
+    match class_dict.find_type(&base_name) {
+        Some(SkType::Class(sk_class)) => match &sk_class.enum_cases {
+            Some(cases) => cases.iter().all(|case| {
+                let case_class = class_dict.get_class(case);
+                ivars_are_sendable(class_dict, case_class, ty.type_args(), visiting)
+            }),
+            // Subclasses of a base class may have `var` ivars
+            None => {
+                !sk_class.inheritable
+                    && ivars_are_sendable(class_dict, sk_class, ty.type_args(), visiting)
+            }
+        },
+        // Modules may be implemented by any class
+        _ => false,
+    }
+}
+
+fn ivars_are_sendable(
+    class_dict: &ClassDict,
+    sk_class: &SkClass,
+    tyargs: &[TermTy],
+    visiting: &mut HashSet<String>,
+) -> bool {
+    sk_class.ivars.values().all(|ivar| {
+        ivar.readonly && is_sendable(class_dict, &ivar.ty.substitute(tyargs, &[]), visiting)
+    })
+}
+
+/// Returns a string like "`x'" to be used in error messages
+fn describe(cap: &HirLambdaCapture) -> String {
+    match &cap.detail {
+        HirLambdaCaptureDetail::CaptureLVar { name } => format!("`{}'", name),
+        HirLambdaCaptureDetail::CaptureArg { .. } => "an argument".to_string(),
+        HirLambdaCaptureDetail::CaptureMethodTyArg { .. } => "a type argument".to_string(),
+        HirLambdaCaptureDetail::CaptureFwd { .. } => "a variable of the outer block".to_string(),
+        HirLambdaCaptureDetail::CaptureSelf => "`self'".to_string(),
+    }
+}
@@ -1,4 +1,5 @@
 use crate::class_dict::ClassDict;
+use shiika_core::names::type_fullname;
 use shiika_core::{ty, ty::*};
 
 /// Return true if `ty1` conforms to `ty2` i.e.
@@ -57,7 +58,6 @@ fn includes(c: &ClassDict, class: &TermTy, module: &LitTy) -> bool {
     })
 }
 
-// TODO: implement variance
 #[allow(clippy::if_same_then_else)]
 fn class_conforms_to_class(c: &ClassDict, ty1: &TermTy, ty2: &TermTy) -> bool {
     let ancestors = ancestor_types(c, ty1);
@@ -70,14 +70,43 @@ fn class_conforms_to_class(c: &ClassDict, ty1: &TermTy, ty2: &TermTy) -> bool {
         } else if t1.tyargs().iter().all(|t| t.is_never_type()) {
             true
         } else {
-            // Special care for void funcs
-            is_void_fn(ty2)
+            tyargs_conform(c, ty2, t1.tyargs(), ty2.tyargs())
         }
     } else {
         false
     }
 }
 
+/// Return true if the type arguments `args1` conform to `args2` under the
+/// declared variance of the type parameters of `ty`.
+/// eg. `Fn1<Object, Int>` conforms to `Fn1<Int, Object>` because `Fn1` is
+/// declared as `Fn1<in S1, out T>`.
+fn tyargs_conform(c: &ClassDict, ty: &TermTy, args1: &[TermTy], args2: &[TermTy]) -> bool {
+    let Some(sk_type) = c.find_type(&type_fullname(ty.erasure().base_name)) else {
+        return false;
+    };
+    let typarams = &sk_type.base().typarams;
+    if typarams.len() != args1.len() || args1.len() != args2.len() {
+        return false;
+    }
+    let void_fn = is_void_fn(ty);
+    typarams
+        .iter()
+        .zip(args1.iter().zip(args2))
+        .enumerate()
+        .all(|(i, (typaram, (a1, a2)))| {
+            if void_fn && i == args2.len() - 1 {
+                // Any function can be used where a void function is expected
+                return true;
+            }
+            match typaram.variance {
+                Variance::Invariant => a1.equals_to(a2),
+                Variance::Covariant => conforms(c, a1, a2),
+                Variance::Contravariant => conforms(c, a2, a1),
+            }
+        })
+}
+
 /// Returns if `ty` is a void-returning function (eg. `Fn1<Int, Void>`)
 fn is_void_fn(ty: &TermTy) -> bool {
     if let Some(tys) = ty.fn_x_info() {
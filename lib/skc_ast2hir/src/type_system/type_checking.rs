@@ -219,6 +219,30 @@ pub fn check_block_arity(
     Err(type_error(report))
 }
 
+/// Check the type arguments satisfy the upper bounds of the type parameters.
+/// The bounds may refer to `class_tyargs` and `tyargs` (eg. `T : Comparable<T>`)
+pub fn check_tyarg_bounds(
+    class_dict: &ClassDict,
+    typarams: &[TyParam],
+    tyargs: &[TermTy],
+    class_tyargs: &[TermTy],
+    locs: &LocationSpan,
+) -> Result<()> {
+    for (typaram, tyarg) in typarams.iter().zip(tyargs) {
+        if !typaram.is_bounded() {
+            continue;
+        }
+        let bound = typaram
+            .upper_bound
+            .substitute(class_tyargs, tyargs)
+            .to_term_ty();
+        if !class_dict.conforms(tyarg, &bound) {
+            return Err(error::tyarg_bound_mismatch(typaram, tyarg, &bound, locs));
+        }
+    }
+    Ok(())
+}
+
 pub fn check_class_specialization(
     class: &SkType,
     given_tyargs: &[HirExpression],
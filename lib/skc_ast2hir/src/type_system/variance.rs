@@ -0,0 +1,115 @@
+//! Checks the variance of the class type parameters in method signatures.
+//!
+//! A covariant type parameter (`out T`) must not appear in an input position
+//! (eg. the type of a parameter) and a contravariant one (`in T`) must not
+//! appear in an output position (eg. the return type.)
+//!
+//! ```sk
+//! class Box<out T>
+//!   def get -> T          # ok
+//!   def set(value: T)     # error
+//!   def each(f: Fn1<T, Void>)  # ok (`Fn1<in S1, out T>` flips the position)
+//! end
+//! ```
+//!
+//! Initializers and private methods are not checked because they cannot be
+//! called on an upcasted object.
+use crate::class_dict::ClassDict;
+use crate::error;
+use anyhow::Result;
+use shiika_ast::LocationSpan;
+use shiika_core::names::type_fullname;
+use shiika_core::ty::{TermTy, TyBody, TyParam, TyParamKind, Variance};
+use skc_hir::MethodSignature;
+
+/// Where a type appears in a method signature
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Position {
+    Output,
+    Input,
+    /// Both input and output (eg. the type argument of `Array`)
+    Invariant,
+}
+
+impl Position {
+    /// Returns the position of a type argument for a type parameter with
+    /// `variance`.
+    fn nested(self, variance: &Variance) -> Position {
+        match (self, variance) {
+            (Position::Invariant, _) | (_, Variance::Invariant) => Position::Invariant,
+            (pos, Variance::Covariant) => pos,
+            (Position::Output, Variance::Contravariant) => Position::Input,
+            (Position::Input, Variance::Contravariant) => Position::Output,
+        }
+    }
+}
+
+/// Check that the class type parameters `typarams` appear in `sig` only in
+/// the positions allowed by their variance.
+pub fn check_signature(
+    class_dict: &ClassDict,
+    typarams: &[TyParam],
+    sig: &MethodSignature,
+    locs: &LocationSpan,
+) -> Result<()> {
+    if sig.is_private
+        || sig.first_name().0 == "initialize"
+        || typarams.iter().all(|t| t.variance == Variance::Invariant)
+    {
+        return Ok(());
+    }
+    for param in &sig.params {
+        check_type(class_dict, typarams, &param.ty, Position::Input, sig, locs)?;
+    }
+    check_type(
+        class_dict,
+        typarams,
+        &sig.ret_ty,
+        Position::Output,
+        sig,
+        locs,
+    )
+}
+
+fn check_type(
+    class_dict: &ClassDict,
+    typarams: &[TyParam],
+    ty: &TermTy,
+    pos: Position,
+    sig: &MethodSignature,
+    locs: &LocationSpan,
+) -> Result<()> {
+    match &ty.body {
+        TyBody::TyPara(typaram_ref) => {
+            if typaram_ref.kind != TyParamKind::Class {
+                return Ok(());
+            }
+            let Some(typaram) = typarams.get(typaram_ref.idx) else {
+                return Ok(());
+            };
+            let allowed = match typaram.variance {
+                Variance::Invariant => true,
+                Variance::Covariant => pos == Position::Output,
+                Variance::Contravariant => pos == Position::Input,
+            };
+            if allowed {
+                Ok(())
+            } else {
+                Err(error::variance_mismatch(typaram, sig, locs))
+            }
+        }
+        TyBody::TyRaw(lit_ty) => {
+            let arg_typarams = class_dict
+                .get_typarams(&type_fullname(&lit_ty.base_name))
+                .unwrap_or(&[]);
+            for (i, arg) in lit_ty.type_args.iter().enumerate() {
+                let variance = arg_typarams
+                    .get(i)
+                    .map(|t| &t.variance)
+                    .unwrap_or(&Variance::Invariant);
+                check_type(class_dict, typarams, arg, pos.nested(variance), sig, locs)?;
+            }
+            Ok(())
+        }
+    }
+}
@@ -4,9 +4,8 @@ pub mod compiler;
 pub mod exe_builder;
 pub mod lib_builder;
 pub mod linker;
-pub mod loader;
 pub mod package_builder;
-use crate::package::Package;
+use crate::package::{Package, RuntimeSpec};
 use skc_hir::Hir;
 use skc_mir::LibraryExports;
 use std::path::Path;
@@ -24,7 +23,8 @@ pub struct CompileTarget<'a> {
     pub entry_point: &'a Path,
     /// Directory to create the artifact
     pub out_dir: &'a Path,
-    /// Direct dependencies
+    /// Dependencies (including indirect ones, sorted so that dependencies
+    /// come first)
     pub deps: &'a [Package],
     /// Lib or Bin specific information
     pub detail: CompileTargetDetail<'a>,
@@ -38,6 +38,8 @@ pub enum CompileTargetDetail<'a> {
         package: Option<&'a Package>,
         /// Topologically sorted indirect dependencies
         total_deps: Vec<String>,
+        /// Runtime settings (from package.json5, if any)
+        runtime: RuntimeSpec,
     },
 }
 
@@ -1,9 +1,8 @@
-use crate::build::{self, bootstrap_classes, loader, CompileTarget};
+use crate::build::{self, bootstrap_classes, CompileTarget};
 use crate::codegen::prelude;
 use crate::{cli, codegen, mir, mir_lowering, mirgen, package};
 use anyhow::{Context, Result};
 use shiika_core::names::type_fullname;
-use shiika_parser::SourceFile;
 use skc_ast2hir::class_dict::RustMethods;
 use skc_mir::LibraryExports;
 use std::collections::HashMap;
@@ -15,11 +14,12 @@ pub fn compile(
     cli: &mut cli::Cli,
     target: &CompileTarget,
 ) -> Result<(PathBuf, mir::CompilationUnit)> {
-    let src = loader::load(target.entry_point)?;
-    let mut mir = generate_mir(cli, &src, target)?;
+    log::info!("Creating ast");
+    let ast = shiika_parser::loader::load(target.entry_point, &[])?;
+    let mut mir = generate_mir(cli, ast, target)?;
 
-    if target.is_bin() {
-        mir.program.funcs.append(&mut prelude::main_funcs());
+    if let build::CompileTargetDetail::Bin { runtime, .. } = &target.detail {
+        mir.program.funcs.append(&mut prelude::main_funcs(runtime));
     } else {
         for (name, fun_ty) in prelude::intrinsic_externs() {
             mir.program.externs.push(mir::Extern { name, fun_ty });
@@ -44,12 +44,9 @@ fn out_path(out_dir: &Path, entry_point: &Path, ext: &str) -> PathBuf {
 
 fn generate_mir(
     cli: &mut cli::Cli,
-    src: &[SourceFile],
+    ast: shiika_ast::Program,
     target: &CompileTarget,
 ) -> Result<mir::CompilationUnit> {
-    log::info!("Creating ast");
-    let ast = shiika_parser::Parser::parse_files(src)?;
-
     let uni = generate_hir(cli, ast, target)?;
     log::info!("Creating mir");
 
@@ -99,7 +96,7 @@ fn generate_mir(
 
 fn generate_hir(
     cli: &mut cli::Cli,
-    ast: shiika_ast::Program,
+    mut ast: shiika_ast::Program,
     target: &CompileTarget,
 ) -> Result<build::CompilationUnit> {
     let imports = {
@@ -114,10 +111,12 @@ fn generate_hir(
     };
 
     let class_dict = {
+        let imported_names = std::mem::take(&mut ast.imported_names);
         let defs = ast.defs();
         let type_index =
             skc_ast2hir::type_index::create(&defs, &Default::default(), &imports.sk_types);
         let mut class_dict = skc_ast2hir::class_dict::new(type_index, &imports.sk_types);
+        class_dict.imported_names = imported_names;
         if target.is_core_package() {
             bootstrap_classes::add_to(&mut class_dict);
         }
@@ -136,6 +135,9 @@ fn generate_hir(
         let (main_exprs, main_lvars) = hir_maker.convert_toplevel_items(ast.toplevel_items)?;
         hir_maker.extract_hir(main_exprs, main_lvars)
     };
+    for warning in &hir.warnings {
+        eprint!("{}", warning);
+    }
 
     Ok(build::CompilationUnit {
         package_name: target.package_name(),
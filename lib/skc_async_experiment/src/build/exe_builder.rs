@@ -5,11 +5,19 @@ use anyhow::Result;
 use std::path::PathBuf;
 
 /// Builds a single .sk file and generates an executable.
+/// If there is package.json5 in the same directory, the dependencies
+/// written in it are built and linked.
 /// Returns the path to the generated executable.
 pub fn run(cli: &mut Cli, entry_point: &PathBuf) -> Result<PathBuf> {
-    let deps = vec![Package::load_core(cli)?]; //TODO: load dependencies
-    let total_deps = deps.iter().map(|x| x.spec.name.clone()).collect();
     let out_dir = entry_point.parent().unwrap();
+    let (deps, runtime) = if out_dir.join("package.json5").exists() {
+        let p = Package::new(cli, &out_dir.to_path_buf())?;
+        let deps = build::package_builder::build_deps(cli, &p, false)?;
+        (deps, p.spec.runtime)
+    } else {
+        (vec![Package::load_core(cli)?], Default::default())
+    };
+    let total_deps = deps.iter().map(|x| x.spec.name.clone()).collect();
     let target = build::CompileTarget {
         entry_point,
         out_dir: &out_dir,
@@ -17,11 +25,14 @@ pub fn run(cli: &mut Cli, entry_point: &PathBuf) -> Result<PathBuf> {
         detail: build::CompileTargetDetail::Bin {
             package: None,
             total_deps,
+            runtime,
         },
     };
     let (bc_path, _) = build::compiler::compile(cli, &target)?;
+    // Put dependent packages first for the linker to resolve symbols
     let artifacts = deps
         .iter()
+        .rev()
         .flat_map(|pkg| pkg.artifacts.clone())
         .collect::<Vec<_>>();
     build::linker::run(bc_path, &artifacts)
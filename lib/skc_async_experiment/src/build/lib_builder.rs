@@ -8,13 +8,12 @@ use skc_mir::LibraryExports;
 use std::collections::HashMap;
 use std::io::Write;
 
-pub fn build(cli: &mut Cli, package: &Package) -> Result<()> {
-    let deps = vec![]; // TODO: get deps from package
+/// `deps` is the packages `package` depends on, directly or indirectly.
+pub fn build(cli: &mut Cli, package: &Package, deps: &[Package]) -> Result<()> {
     let target = build::CompileTarget {
         entry_point: &package.entry_point(),
         out_dir: &cli.lib_target_dir(&package.spec),
-
-        deps: &deps,
+        deps,
         detail: build::CompileTargetDetail::Lib { package },
     };
     let (_, mir) = build::compiler::compile(cli, &target)?;
@@ -39,14 +38,16 @@ fn create_exports(mir: &mir::CompilationUnit) -> Result<LibraryExports> {
         );
     }
     for (name, ty) in &mir.program.constants {
-        constants.insert(name.clone(), ty.clone());
+        if !mir.private_constants.contains(name) {
+            constants.insert(name.clone(), ty.clone());
+        }
     }
     debug_assert!(
         asyncness_is_set(&mir.sk_types),
         "Asyncness must be set for all methods",
     );
     Ok(LibraryExports {
-        sk_types: mir.sk_types.clone(),
+        sk_types: mir.sk_types.without_private_methods(),
         vtables: mir.vtables.clone(),
         constants,
     })
@@ -1,10 +1,110 @@
 use crate::build;
 use crate::cli::Cli;
-use crate::package::Package;
-use anyhow::Result;
+use crate::package::{self, Lockfile, Package};
+use anyhow::{bail, Result};
 
-pub fn run(cli: &mut Cli, p: &Package) -> Result<()> {
-    build::cargo_builder::run(cli, &p)?;
-    build::lib_builder::build(cli, &p)?;
+/// Build a package and its dependencies.
+pub fn run(cli: &mut Cli, p: &Package, locked: bool) -> Result<()> {
+    let deps = build_deps(cli, p, locked)?;
+    build_package(cli, p, &deps)?;
     Ok(())
 }
+
+/// Resolve the dependencies of a package and build them if needed.
+/// Also updates the lockfile of the package.
+/// If `locked` is true, fails instead of updating the lockfile.
+/// Returns the dependencies sorted so that dependencies come first.
+pub fn build_deps(cli: &mut Cli, p: &Package, locked: bool) -> Result<Vec<Package>> {
+    let deps = package::resolve(cli, p)?;
+    let lockfile_path = p.lockfile_path();
+    let lockfile = Lockfile::new(p, &deps)?;
+    let old_lockfile = Lockfile::load(&lockfile_path)?;
+    if locked {
+        match &old_lockfile {
+            None => bail!("{} not found", lockfile_path.display()),
+            Some(old) => {
+                if let Some(diff) = old.diff(&lockfile) {
+                    bail!("{} is out of date ({})", lockfile_path.display(), diff);
+                }
+            }
+        }
+    }
+
+    let mut rebuilt: Vec<String> = vec![];
+    for (i, dep) in deps.iter().enumerate() {
+        let dep_deps = package::transitive_deps(&deps[..i], dep);
+        let up_to_date = is_built(cli, dep)
+            && (dep.is_core() || is_locked(dep, &lockfile, old_lockfile.as_ref()))
+            && !dep_deps.iter().any(|d| rebuilt.contains(&d.spec.name));
+        if up_to_date {
+            continue;
+        }
+        log::info!("Building {} {}", dep.spec.name, dep.spec.version);
+        build_package(cli, dep, &dep_deps)?;
+        rebuilt.push(dep.spec.name.clone());
+    }
+
+    if !p.is_core() && old_lockfile.as_ref() != Some(&lockfile) {
+        lockfile.save(&lockfile_path)?;
+    }
+    Ok(deps)
+}
+
+/// Build a package (assuming the dependencies are already built)
+fn build_package(cli: &mut Cli, p: &Package, deps: &[Package]) -> Result<()> {
+    build::cargo_builder::run(cli, p)?;
+    build::lib_builder::build(cli, p, deps)?;
+    Ok(())
+}
+
+/// Returns true if the artifacts of the package exist
+fn is_built(cli: &Cli, p: &Package) -> bool {
+    cli.lib_exports_path(&p.spec).exists() && cli.lib_artifact_path(&p.spec).exists()
+}
+
+/// Returns true if the package is not changed since the last build
+fn is_locked(p: &Package, lockfile: &Lockfile, old_lockfile: Option<&Lockfile>) -> bool {
+    let Some(old) = old_lockfile else {
+        return false;
+    };
+    old.find(&p.spec.name).is_some() && old.find(&p.spec.name) == lockfile.find(&p.spec.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::testutil::TempDir;
+
+    fn build_app_locked(tmp: &TempDir) -> Result<Vec<Package>> {
+        let mut cli = tmp.cli();
+        let app = Package::new(&cli, &tmp.join("app"))?;
+        build_deps(&mut cli, &app, true)
+    }
+
+    #[test]
+    fn test_locked_without_lockfile() {
+        let tmp = TempDir::new();
+        tmp.package("app", "app", "0.1.0", &[]);
+        let err = build_app_locked(&tmp).unwrap_err();
+        assert!(err.to_string().ends_with("package-lock.json not found"));
+    }
+
+    #[test]
+    fn test_locked_with_outdated_lockfile() -> Result<()> {
+        let tmp = TempDir::new();
+        tmp.package("app", "app", "0.1.0", &[("a", "../a", "^0.1.0")]);
+        tmp.package("a", "a", "0.1.0", &[]);
+        let empty = Lockfile { packages: vec![] };
+        empty.save(&tmp.join("app/package-lock.json"))?;
+        let err = build_app_locked(&tmp).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("package-lock.json is out of date (`a' is added)"));
+        // The lockfile is not updated
+        assert_eq!(
+            Lockfile::load(&tmp.join("app/package-lock.json"))?,
+            Some(empty)
+        );
+        Ok(())
+    }
+}
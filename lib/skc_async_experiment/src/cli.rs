@@ -51,19 +51,21 @@ impl Cli {
         })
     }
 
-    /// Build a package.
-    pub fn build(&mut self, filepath: &PathBuf) -> Result<()> {
+    /// Build a package (and its dependencies.)
+    pub fn build(&mut self, filepath: &PathBuf, locked: bool) -> Result<()> {
         let p = package::Package::new(&self, filepath)?;
-        build::package_builder::run(self, &p)?;
+        build::package_builder::run(self, &p, locked)?;
         Ok(())
     }
 
-    /// Build and run a single .sk file.
-    pub fn run(&mut self, filepath: &PathBuf) -> Result<()> {
+    /// Build and run a single .sk file with `args`. Returns the exit status.
+    pub fn run(&mut self, filepath: &PathBuf, args: &[String]) -> Result<i32> {
         let bin_path = build::exe_builder::run(self, filepath)?;
         let mut cmd = std::process::Command::new(bin_path);
-        cmd.status()?;
-        Ok(())
+        cmd.args(args);
+        let status = cmd.status()?;
+        // `code` is None if killed by a signal
+        Ok(status.code().unwrap_or(1))
     }
 
     /// Like `run`, but only builds the executable.
@@ -11,7 +11,19 @@ pub struct CommandLineOptions {
 
 #[derive(Subcommand)]
 pub enum Command {
-    Build { path: PathBuf },
-    Compile { path: PathBuf },
-    Run { path: PathBuf },
+    Build {
+        path: PathBuf,
+        /// Fail if package-lock.json needs to be updated
+        #[arg(long)]
+        locked: bool,
+    },
+    Compile {
+        path: PathBuf,
+    },
+    Run {
+        path: PathBuf,
+        /// Arguments passed to the program (eg. `run a.sk -- foo bar`)
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
 }
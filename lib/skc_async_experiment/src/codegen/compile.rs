@@ -147,6 +147,7 @@ impl<'run, 'ictx: 'run> CodeGen<'run, 'ictx> {
             }
             mir::Expr::Unbox(expr) => self.compile_unbox(ctx, expr),
             mir::Expr::RawI64(n) => self.compile_raw_i64(*n),
+            mir::Expr::CStr(s) => self.compile_cstr(s),
             mir::Expr::Nop => None,
             mir::Expr::NullPtr => Some(self.ptr_type().const_null().into()),
             mir::Expr::IsNull(inner) => self.compile_is_null(ctx, inner),
@@ -166,6 +167,10 @@ impl<'run, 'ictx: 'run> CodeGen<'run, 'ictx> {
         Some(string_literal::generate(self, s))
     }
 
+    fn compile_cstr(&mut self, s: &str) -> Option<inkwell::values::BasicValueEnum<'run>> {
+        Some(string_literal::generate_cstr(self, s).into())
+    }
+
     fn compile_create_native_array(
         &mut self,
         ctx: &mut CodeGenContext<'run>,
@@ -1,5 +1,6 @@
 use crate::mir::{self, FunTy, Ty};
 use crate::names::FunctionName;
+use crate::package::RuntimeSpec;
 
 /// Functions defined as Shiika runtime (in packages/core/ext)
 pub fn core_externs() -> Vec<mir::Extern> {
@@ -48,7 +49,14 @@ pub fn core_externs() -> Vec<mir::Extern> {
             "chiika_spawn",
             FunTy::sync(vec![spawnee.into(), Ty::RustFuture], Ty::CVoid),
         ),
-        ("chiika_start_tokio", FunTy::sync(vec![], Ty::CVoid)),
+        (
+            "shiika_set_call_site",
+            FunTy::sync(vec![Ty::Ptr], Ty::CVoid),
+        ),
+        (
+            "chiika_start_tokio",
+            FunTy::sync(vec![Ty::Int64, Ty::Int64], Ty::CVoid),
+        ),
     ]
     .into_iter()
     .map(|(name, ty)| mir::Extern {
@@ -75,14 +83,14 @@ pub fn intrinsic_externs() -> Vec<(FunctionName, FunTy)> {
     .collect()
 }
 
-pub fn main_funcs() -> Vec<mir::Function> {
+pub fn main_funcs(runtime: &RuntimeSpec) -> Vec<mir::Function> {
     vec![
         mir::Function {
             name: FunctionName::mangled("main"),
             asyncness: mir::Asyncness::Lowered,
             params: vec![],
             ret_ty: Ty::Int64,
-            body_stmts: main_body(),
+            body_stmts: main_body(runtime),
             sig: None,
             lvar_count: None,
         },
@@ -107,11 +115,13 @@ pub fn main_funcs() -> Vec<mir::Function> {
     ]
 }
 
-fn main_body() -> mir::TypedExpr {
-    let t = FunTy::lowered(vec![], Ty::CVoid);
+fn main_body(runtime: &RuntimeSpec) -> mir::TypedExpr {
+    let t = FunTy::lowered(vec![Ty::Int64, Ty::Int64], Ty::CVoid);
     let chiika_start_tokio = mir::Expr::func_ref(FunctionName::mangled("chiika_start_tokio"), t);
+    let flavor = mir::Expr::raw_i64(runtime.flavor.to_i64());
+    let worker_threads = mir::Expr::raw_i64(runtime.worker_threads.unwrap_or(0) as i64);
     mir::Expr::exprs(vec![
-        mir::Expr::fun_call(chiika_start_tokio, vec![]),
+        mir::Expr::fun_call(chiika_start_tokio, vec![flavor, worker_threads]),
         // TODO: pass the resulting int to the user's main
         mir::Expr::return_(mir::Expr::unbox(mir::Expr::number(0))),
     ])
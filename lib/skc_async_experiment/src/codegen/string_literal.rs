@@ -32,6 +32,14 @@ pub fn generate<'run>(
     call_result.as_any_value_enum().try_into().unwrap()
 }
 
+/// Returns a pointer to a null-terminated copy of `s`.
+pub fn generate_cstr<'run>(
+    gen: &mut CodeGen<'run, '_>,
+    s: &str,
+) -> inkwell::values::PointerValue<'run> {
+    declare_global(gen, &format!("{}\0", s))
+}
+
 /// Defines a global i8 array for the string literal and returns a pointer to it.
 fn declare_global<'run>(
     gen: &mut CodeGen<'run, '_>,
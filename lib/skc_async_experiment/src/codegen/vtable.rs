@@ -1,4 +1,5 @@
 use crate::codegen::{item, CodeGen};
+use crate::names::FunctionName;
 use shiika_core::ty::Erasure;
 use skc_mir;
 
@@ -25,8 +26,16 @@ pub fn define_body(gen: &mut CodeGen, vtables: &skc_mir::VTables, _: item::Metho
         let func_ptrs = method_names
             .iter()
             .map(|name| {
+                let func_name = FunctionName::from(name).mangle();
+                // Private methods of imported classes are not declared
+                // because they are not exported
                 let func = gen
-                    .get_llvm_func(&name.into())
+                    .module
+                    .get_function(&func_name)
+                    .unwrap_or_else(|| {
+                        let fn_type = gen.context.void_type().fn_type(&[], false);
+                        gen.module.add_function(&func_name, fn_type, None)
+                    })
                     .as_global_value()
                     .as_pointer_value();
                 gen.builder
@@ -9,6 +9,7 @@ pub use expr::{CastType, Expr, PseudoVar, Typed, TypedExpr};
 use shiika_core::{names::ConstFullname, ty::TermTy};
 use skc_hir::{MethodSignature, SkTypes};
 use skc_mir::VTables;
+use std::collections::HashSet;
 use std::fmt;
 pub use ty::{FunTy, Ty};
 
@@ -24,6 +25,8 @@ pub struct CompilationUnit {
     pub program: Program,
     pub sk_types: SkTypes,
     pub vtables: VTables,
+    /// Constants defined with `private` (not exported)
+    pub private_constants: HashSet<ConstFullname>,
     pub imported_constants: Vec<(ConstFullname, TermTy)>,
     pub imported_vtables: VTables,
     pub imported_sk_types: SkTypes,
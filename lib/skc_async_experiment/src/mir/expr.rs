@@ -59,6 +59,9 @@ pub enum Expr {
     // Unbox Shiika's Int to Rust's i64. Only used in `main()`
     Unbox(Box<Typed<Expr>>),
     RawI64(i64),
+    /// Null-terminated string constant (eg. the argument of
+    /// `shiika_set_call_site`)
+    CStr(String),
     Nop,
     /// Null pointer constant (lowered from NullPtr to LLVM null)
     NullPtr,
@@ -336,6 +339,10 @@ impl Expr {
         (Expr::RawI64(n), Ty::Int64)
     }
 
+    pub fn cstr(s: impl Into<String>) -> TypedExpr {
+        (Expr::CStr(s.into()), Ty::Ptr)
+    }
+
     pub fn nop() -> TypedExpr {
         (Expr::Nop, Ty::raw("Void"))
     }
@@ -430,6 +437,7 @@ impl Expr {
             }
             Expr::Unbox(e) => e.0.contains_async_call(),
             Expr::RawI64(_) => false,
+            Expr::CStr(_) => false,
             Expr::Nop => false,
             Expr::NullPtr => false,
             Expr::IsNull(e) => e.0.contains_async_call(),
@@ -572,6 +580,7 @@ fn pretty_print(node: &Expr, lv: usize, as_stmt: bool) -> String {
         }
         Expr::Unbox(e) => format!("%Unbox({})", pretty_print(&e.0, lv, false)),
         Expr::RawI64(n) => format!("{}", n),
+        Expr::CStr(s) => format!("c{:?}", s),
         Expr::Nop => "%Nop".to_string(),
         Expr::StringLiteral(s) => format!("\"{}\"", s),
         Expr::CreateNativeArray(elems) => {
@@ -82,6 +82,7 @@ pub trait MirRewriter {
             mir::Expr::CreateTypeObject(_) => expr,
             mir::Expr::Unbox(e) => mir::Expr::unbox(self.walk_expr(*e)?),
             mir::Expr::RawI64(_) => expr,
+            mir::Expr::CStr(_) => expr,
             mir::Expr::Nop => expr,
             mir::Expr::StringLiteral(_) => expr,
             mir::Expr::CreateNativeArray(elem_exprs) => {
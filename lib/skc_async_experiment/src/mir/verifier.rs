@@ -229,6 +229,7 @@ impl<'a> Verifier<'a> {
                 assert(&e, "result", &mir::Ty::Int64)?;
             }
             mir::Expr::RawI64(_) => assert(&e, "raw i64", &mir::Ty::Int64)?,
+            mir::Expr::CStr(_) => assert(&e, "c string", &mir::Ty::Ptr)?,
             mir::Expr::Nop => (),
             mir::Expr::StringLiteral(_) => (),
             mir::Expr::CreateNativeArray(elem_exprs) => {
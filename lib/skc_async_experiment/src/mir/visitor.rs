@@ -99,6 +99,7 @@ pub trait MirVisitor {
                 self.walk_expr(expr)?;
             }
             mir::Expr::RawI64(_) => {}
+            mir::Expr::CStr(_) => {}
             mir::Expr::Nop => {}
             mir::Expr::StringLiteral(_) => {}
             mir::Expr::CreateNativeArray(elem_exprs) => {
@@ -275,6 +275,7 @@ impl<'a> Compiler<'a> {
                 mir::Expr::cell_set(new_cell, new_value)
             }
             mir::Expr::Unbox(_) | mir::Expr::RawI64(_) | mir::Expr::Nop => e,
+            mir::Expr::CStr(_) => e,
             mir::Expr::WTableKey(_) | mir::Expr::WTableRow(_, _) => e,
             mir::Expr::NullPtr | mir::Expr::ClassVTable(_) => e,
             mir::Expr::IsNull(inner) => {
@@ -438,7 +439,9 @@ impl<'a> Compiler<'a> {
 
         // Create endwhile chapter
         self.chapters.add(endwhile_chap);
-        Ok(None)
+        // The value of `while` (needed when it is the last expr of an `if`
+        // clause)
+        Ok(Some(mir::Expr::pseudo_var(mir::PseudoVar::Void)))
     }
 
     fn while_jump(&self, chap_name: &FunctionName) -> mir::TypedExpr {
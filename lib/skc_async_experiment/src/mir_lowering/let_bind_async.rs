@@ -181,6 +181,7 @@ impl Compiler {
                 mir::Expr::unbox(new_e)
             }
             mir::Expr::RawI64(_) => expr,
+            mir::Expr::CStr(_) => expr,
             mir::Expr::Nop => expr,
             mir::Expr::NullPtr => expr,
             mir::Expr::IsNull(inner) => {
@@ -165,6 +165,7 @@ fn splice(expr: mir::TypedExpr, new_exprs: &mut Vec<mir::TypedExpr>) -> mir::Typ
             mir::Expr::unbox(new_inner)
         }
         mir::Expr::RawI64(_) => expr,
+        mir::Expr::CStr(_) => expr,
         mir::Expr::Nop => expr,
         mir::Expr::NullPtr => expr,
         mir::Expr::IsNull(inner) => {
@@ -9,6 +9,7 @@ use crate::gensym;
 use crate::mir;
 use crate::names::FunctionName;
 use anyhow::Result;
+use shiika_ast::LocationSpan;
 use shiika_core::names::{ConstFullname, MethodFullname, TypeFullname};
 use shiika_core::ty;
 use shiika_core::ty::TermTy;
@@ -16,6 +17,10 @@ use skc_hir::{HirExpression, HirExpressionBase, SkMethod};
 use skc_hir::{MethodParam, MethodSignature, SkMethodBody, SkTypes};
 use std::collections::HashSet;
 
+/// Methods which may call `runtime::fault_at_call_site` of packages/core/ext.
+/// Calls to them are preceded by `shiika_set_call_site`.
+const FAULTING_METHODS: &[&str] = &["Int#-@", "Int#+", "Int#-", "Int#*", "Int#/", "Int#%"];
+
 pub fn run(
     mut uni: build::CompilationUnit,
     target: &build::CompileTarget,
@@ -104,6 +109,7 @@ pub fn run(
         program,
         sk_types: uni.hir.sk_types,
         vtables,
+        private_constants: uni.hir.private_consts,
         imported_constants: uni.imports.constants.into_iter().collect(),
         imported_vtables: uni.imports.vtables,
         imported_sk_types: uni.imports.sk_types,
@@ -203,6 +209,54 @@ impl<'a> Compiler<'a> {
         (decl, r1, r2)
     }
 
+    /// Build a call to one of `FAULTING_METHODS`. The arguments are evaluated
+    /// before `shiika_set_call_site` so that the call site is not overwritten
+    /// by the calls in them.
+    fn call_with_site(
+        &mut self,
+        func_ref: mir::TypedExpr,
+        args: Vec<mir::TypedExpr>,
+        result_ty: mir::Ty,
+        locs: &LocationSpan,
+    ) -> mir::TypedExpr {
+        let LocationSpan::Just {
+            filepath, begin, ..
+        } = locs
+        else {
+            return (mir::Expr::FunCall(Box::new(func_ref), args), result_ty);
+        };
+        let mut exprs = vec![];
+        let args = args
+            .into_iter()
+            .map(|arg| match arg.0 {
+                mir::Expr::Number(_)
+                | mir::Expr::LVarRef(_)
+                | mir::Expr::ArgRef(_, _)
+                | mir::Expr::EnvRef(_, _) => arg,
+                _ => {
+                    let (decl, arg_ref, _) = self.bind_receiver(arg);
+                    exprs.push(decl);
+                    arg_ref
+                }
+            })
+            .collect();
+        // `begin.line` is 0-origin
+        let line = begin.line + 1;
+        let site = match &self.current_method_sig {
+            Some(sig) => format!("{}:{}:in `{}'", filepath.display(), line, sig.fullname),
+            None => format!("{}:{}", filepath.display(), line),
+        };
+        exprs.push(mir::Expr::fun_call(
+            mir::Expr::func_ref(
+                FunctionName::mangled("shiika_set_call_site"),
+                mir::FunTy::sync(vec![mir::Ty::Ptr], mir::Ty::CVoid),
+            ),
+            vec![mir::Expr::cstr(site)],
+        ));
+        exprs.push((mir::Expr::FunCall(Box::new(func_ref), args), result_ty));
+        mir::Expr::exprs(exprs)
+    }
+
     /// Build a runtime `Class` object expression for a type used as a tyarg
     /// to `Meta:FnN#new` (i.e. lambda type parameters).
     fn build_class_obj_for_tyarg(&self, t: &TermTy) -> mir::TypedExpr {
@@ -552,6 +606,7 @@ impl<'a> Compiler<'a> {
                 let receiver_ty = receiver_expr.ty.clone();
                 let mir_receiver = self.convert_expr(*receiver_expr);
                 let method_name = &method_fullname.first_name;
+                let may_fault = FAULTING_METHODS.contains(&method_fullname.full_name.as_str());
 
                 let fun_ty = {
                     let mut param_tys = arg_exprs
@@ -616,7 +671,11 @@ impl<'a> Compiler<'a> {
                     mir_args.push(casted);
                 }
 
-                let call = (mir::Expr::FunCall(Box::new(func_ref), mir_args), result_ty);
+                let call = if may_fault {
+                    self.call_with_site(func_ref, mir_args, result_ty, &expr.locs)
+                } else {
+                    (mir::Expr::FunCall(Box::new(func_ref), mir_args), result_ty)
+                };
                 if let Some(decl) = recv_decl {
                     mir::Expr::exprs(vec![decl, call])
                 } else {
@@ -5,6 +5,9 @@
 //! to a chain of nested `If`s; each clause's components fold into a single
 //! Bool-typed test chain that interleaves binds (as `LVarDecl`) and tests
 //! (as `If(t, rest, false)`), so no expression is duplicated.
+//! Alternatives (`A | B`) and guards (`when x if cond`) are already
+//! lowered by skc_ast2hir into `Test`s, so the old and new backends share
+//! the same semantics.
 use crate::mir;
 use skc_hir::pattern_match::{Component, MatchClause};
 use skc_hir::{HirExpression, HirExpressionBase};
@@ -1,9 +1,17 @@
+mod lockfile;
+mod resolver;
+#[cfg(test)]
+pub(crate) mod testutil;
 use crate::cli::Cli;
 use anyhow::{Context, Result};
+pub use lockfile::Lockfile;
+pub use resolver::{resolve, transitive_deps};
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::io::Read;
 use std::path::PathBuf;
 
+#[derive(Debug, Clone)]
 pub struct Package {
     pub dir: PathBuf,
     /// Path to the package.json5 file
@@ -16,10 +24,53 @@ pub struct Package {
 #[derive(Debug, PartialEq, Deserialize, Clone)]
 pub struct PackageSpec {
     pub name: String,
-    pub version: String, // TODO: parse
+    pub version: semver::Version,
     pub rust_libs: Option<Vec<String>>,
-    //#[serde(default)]
-    //pub deps: Vec<String>, // TODO: parse
+    /// Packages this package depends on (`core` is implicitly added)
+    #[serde(default)]
+    pub deps: BTreeMap<String, DepSpec>,
+    /// Settings of the runtime for the executables built with this package
+    #[serde(default)]
+    pub runtime: RuntimeSpec,
+}
+
+/// eg. `deps: { foo: { path: "../foo", version: "^0.1.0" } }`
+#[derive(Debug, PartialEq, Deserialize, Clone)]
+pub struct DepSpec {
+    /// Path to the package directory (relative to package.json5)
+    pub path: PathBuf,
+    /// Acceptable versions of the package
+    pub version: semver::VersionReq,
+}
+
+/// eg. `runtime: { flavor: "multi_thread", worker_threads: 4 }`
+#[derive(Debug, PartialEq, Deserialize, Clone, Default)]
+pub struct RuntimeSpec {
+    #[serde(default)]
+    pub flavor: RuntimeFlavor,
+    /// Number of worker threads of the multi-threaded runtime
+    /// (tokio's default if omitted)
+    pub worker_threads: Option<u64>,
+}
+
+/// The tokio scheduler to run the program.
+/// Can be overridden with the env var `SHIIKA_RUNTIME` at run time.
+#[derive(Debug, PartialEq, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RuntimeFlavor {
+    #[default]
+    CurrentThread,
+    MultiThread,
+}
+
+impl RuntimeFlavor {
+    /// The value passed to `chiika_start_tokio` (in packages/core/ext)
+    pub fn to_i64(self) -> i64 {
+        match self {
+            RuntimeFlavor::CurrentThread => 0,
+            RuntimeFlavor::MultiThread => 1,
+        }
+    }
 }
 
 impl Package {
@@ -27,21 +78,11 @@ impl Package {
     /// If the path is a file, it will be used as the package.json5 file.
     pub fn new(cli: &Cli, path: &PathBuf) -> Result<Self> {
         let (spec_path, spec) = load_spec(path)?;
-        let artifacts = spec
-            .rust_libs
-            .as_ref()
-            .map(|libs| {
-                libs.iter()
-                    .flat_map(|lib| {
-                        vec![
-                            // Note: order matters (passed to the linker in this order)
-                            cli.lib_artifact_path(&spec),
-                            cli.rust_artifact_path(&spec, lib),
-                        ]
-                    })
-                    .collect()
-            })
-            .unwrap_or_default();
+        // Note: order matters (passed to the linker in this order)
+        let mut artifacts = vec![cli.lib_artifact_path(&spec)];
+        for lib in spec.rust_libs.iter().flatten() {
+            artifacts.push(cli.rust_artifact_path(&spec, lib));
+        }
         Ok(Package {
             dir: spec_path.parent().unwrap().to_path_buf(),
             spec_path,
@@ -64,6 +105,10 @@ impl Package {
         self.dir.join("index.sk")
     }
 
+    pub fn lockfile_path(&self) -> PathBuf {
+        self.dir.join("package-lock.json")
+    }
+
     /// Returns the paths of exports.json5 files of Rust libraries in this package.
     pub fn export_files(&self) -> Vec<PathBuf> {
         let mut v: Vec<PathBuf> = vec![];
@@ -0,0 +1,283 @@
+//! package-lock.json records the resolved dependencies of a package so that
+//! repeated builds use exactly the same packages.
+use crate::package::Package;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    pub packages: Vec<LockedPackage>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: semver::Version,
+    /// Path to the package directory (relative to the root package)
+    pub path: PathBuf,
+    /// Hash of the files in the package directory
+    pub checksum: String,
+}
+
+impl Lockfile {
+    /// Create the lockfile of `root` which depends on `deps`.
+    /// `core` is not included because it is bundled with the compiler.
+    pub fn new(root: &Package, deps: &[Package]) -> Result<Self> {
+        let packages = deps
+            .iter()
+            .filter(|p| !p.is_core())
+            .map(|p| {
+                Ok(LockedPackage {
+                    name: p.spec.name.clone(),
+                    version: p.spec.version.clone(),
+                    path: relative_path(&root.dir, &p.dir)?,
+                    checksum: checksum(&p.dir)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Lockfile { packages })
+    }
+
+    /// Read the lockfile. Returns None if it does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content =
+            fs::read_to_string(path).context(format!("failed to read {}", path.display()))?;
+        let lockfile =
+            serde_json::from_str(&content).context(format!("{} is broken", path.display()))?;
+        Ok(Some(lockfile))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n").context(format!("failed to write {}", path.display()))
+    }
+
+    pub fn find(&self, name: &str) -> Option<&LockedPackage> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    /// Returns a description of the first difference from `other`, if any.
+    pub fn diff(&self, other: &Lockfile) -> Option<String> {
+        for p in &self.packages {
+            match other.find(&p.name) {
+                None => return Some(format!("`{}' is removed", p.name)),
+                Some(q) if q.version != p.version => {
+                    return Some(format!(
+                        "`{}' is changed from {} to {}",
+                        p.name, p.version, q.version
+                    ))
+                }
+                Some(q) if q.path != p.path => {
+                    return Some(format!(
+                        "`{}' is moved from {} to {}",
+                        p.name,
+                        p.path.display(),
+                        q.path.display()
+                    ))
+                }
+                Some(q) if q.checksum != p.checksum => {
+                    return Some(format!("files in `{}' are modified", p.name))
+                }
+                _ => (),
+            }
+        }
+        other
+            .packages
+            .iter()
+            .find(|q| self.find(&q.name).is_none())
+            .map(|q| format!("`{}' is added", q.name))
+    }
+}
+
+/// Returns `path` relative to `base`.
+/// Both are canonicalized first so that the result does not depend on how
+/// they are spelled (eg. `./a/../b` or via symlinks.)
+fn relative_path(base: &Path, path: &Path) -> Result<PathBuf> {
+    let base = base
+        .canonicalize()
+        .context(format!("{} not found", base.display()))?;
+    let path = path
+        .canonicalize()
+        .context(format!("{} not found", path.display()))?;
+    let n_common = base
+        .components()
+        .zip(path.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut ret = PathBuf::new();
+    for _ in base.components().skip(n_common) {
+        ret.push(Component::ParentDir);
+    }
+    for c in path.components().skip(n_common) {
+        ret.push(c);
+    }
+    Ok(ret)
+}
+
+/// Calculate the FNV-1a hash of the files in the package directory
+fn checksum(dir: &Path) -> Result<String> {
+    let mut files = vec![];
+    collect_files(dir, &mut files)?;
+    files.sort();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for b in bytes {
+            hash ^= *b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    for file in files {
+        let rel = file.strip_prefix(dir).unwrap();
+        feed(rel.to_string_lossy().as_bytes());
+        feed(&[0]);
+        feed(&fs::read(&file).context(format!("failed to read {}", file.display()))?);
+    }
+    Ok(format!("{:016x}", hash))
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).context(format!("failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy();
+        // Skip build artifacts and the lockfile itself
+        if name.starts_with('.') || name == "target" || name == "package-lock.json" {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::testutil::TempDir;
+
+    fn locked(name: &str, version: &str, path: &str, checksum: &str) -> LockedPackage {
+        LockedPackage {
+            name: name.to_string(),
+            version: semver::Version::parse(version).unwrap(),
+            path: PathBuf::from(path),
+            checksum: checksum.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_relative_path() -> Result<()> {
+        let tmp = TempDir::new();
+        for dir in ["app/vendor/a", "b", "c/d"] {
+            fs::create_dir_all(tmp.join(dir))?;
+        }
+        let app = tmp.join("app");
+        let rel = |path: &str| relative_path(&app, &app.join(path));
+        assert_eq!(rel("vendor/a")?, PathBuf::from("vendor/a"));
+        assert_eq!(rel("./vendor/../vendor/a")?, PathBuf::from("vendor/a"));
+        assert_eq!(rel("../b")?, PathBuf::from("../b"));
+        assert_eq!(rel("../c/./d")?, PathBuf::from("../c/d"));
+        assert_eq!(rel(".")?, PathBuf::new());
+        // `base` is canonicalized too
+        let app2 = tmp.join("b/../app");
+        assert_eq!(relative_path(&app2, &tmp.join("b"))?, PathBuf::from("../b"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_relative_path_not_found() {
+        let tmp = TempDir::new();
+        assert!(relative_path(&tmp.path, &tmp.join("no_such_dir")).is_err());
+    }
+
+    #[test]
+    fn test_new_and_checksum() -> Result<()> {
+        let tmp = TempDir::new();
+        tmp.package("app", "app", "0.1.0", &[("a", "../a", "^0.1.0")]);
+        tmp.package("a", "a", "0.1.0", &[]);
+        let cli = tmp.cli();
+        let app = Package::new(&cli, &tmp.join("app"))?;
+        let deps = crate::package::resolve(&cli, &app)?;
+
+        let lockfile = Lockfile::new(&app, &deps)?;
+        assert_eq!(lockfile.packages.len(), 1);
+        assert_eq!(lockfile.packages[0].name, "a");
+        assert_eq!(lockfile.packages[0].path, PathBuf::from("../a"));
+
+        // Build artifacts do not affect the checksum
+        fs::create_dir_all(tmp.join("a/target"))?;
+        fs::write(tmp.join("a/target/x"), "x")?;
+        assert_eq!(Lockfile::new(&app, &deps)?, lockfile);
+
+        fs::write(tmp.join("a/index.sk"), "# modified")?;
+        assert_eq!(
+            Lockfile::new(&app, &deps)?.diff(&lockfile),
+            Some("files in `a' are modified".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_load() -> Result<()> {
+        let tmp = TempDir::new();
+        let path = tmp.join("package-lock.json");
+        assert_eq!(Lockfile::load(&path)?, None);
+        let lockfile = Lockfile {
+            packages: vec![locked("a", "0.1.0", "../a", "0123")],
+        };
+        lockfile.save(&path)?;
+        assert_eq!(Lockfile::load(&path)?, Some(lockfile));
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff() {
+        let base = Lockfile {
+            packages: vec![
+                locked("a", "0.1.0", "../a", "0123"),
+                locked("b", "0.2.0", "../b", "4567"),
+            ],
+        };
+        let with = |packages| Lockfile { packages };
+        assert_eq!(base.diff(&base), None);
+        assert_eq!(
+            base.diff(&with(vec![locked("a", "0.1.0", "../a", "0123")])),
+            Some("`b' is removed".to_string())
+        );
+        assert_eq!(
+            base.diff(&with(vec![
+                locked("a", "0.1.0", "../a", "0123"),
+                locked("b", "0.2.0", "../b", "4567"),
+                locked("c", "0.1.0", "../c", "89ab"),
+            ])),
+            Some("`c' is added".to_string())
+        );
+        assert_eq!(
+            base.diff(&with(vec![
+                locked("a", "0.1.1", "../a", "0123"),
+                locked("b", "0.2.0", "../b", "4567"),
+            ])),
+            Some("`a' is changed from 0.1.0 to 0.1.1".to_string())
+        );
+        assert_eq!(
+            base.diff(&with(vec![
+                locked("a", "0.1.0", "../a", "0123"),
+                locked("b", "0.2.0", "vendor/b", "4567"),
+            ])),
+            Some("`b' is moved from ../b to vendor/b".to_string())
+        );
+        assert_eq!(
+            base.diff(&with(vec![
+                locked("a", "0.1.0", "../a", "0000"),
+                locked("b", "0.2.0", "../b", "4567"),
+            ])),
+            Some("files in `a' are modified".to_string())
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Resolve the dependency graph of a package.
+use crate::cli::Cli;
+use crate::package::{DepSpec, Package};
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+/// Load the dependencies of `root` recursively.
+/// Returns the packages sorted so that dependencies come first. `core` is
+/// always the first one (unless `root` is `core` itself.)
+pub fn resolve(cli: &Cli, root: &Package) -> Result<Vec<Package>> {
+    let mut resolver = Resolver {
+        cli,
+        resolved: vec![],
+        resolving: vec![],
+    };
+    if !root.is_core() {
+        resolver.resolved.push(Package::load_core(cli)?);
+    }
+    resolver.resolve_deps(root)?;
+    Ok(resolver.resolved)
+}
+
+/// Returns the packages in `all` which `pkg` depends on, directly or
+/// indirectly. The order is preserved.
+pub fn transitive_deps(all: &[Package], pkg: &Package) -> Vec<Package> {
+    let mut names = vec![];
+    collect_dep_names(all, pkg, &mut names);
+    all.iter()
+        .filter(|p| p.is_core() || names.contains(&p.spec.name))
+        .cloned()
+        .collect()
+}
+
+fn collect_dep_names(all: &[Package], pkg: &Package, names: &mut Vec<String>) {
+    for name in pkg.spec.deps.keys() {
+        if names.contains(name) {
+            continue;
+        }
+        names.push(name.clone());
+        if let Some(dep) = all.iter().find(|p| &p.spec.name == name) {
+            collect_dep_names(all, dep, names);
+        }
+    }
+}
+
+struct Resolver<'a> {
+    cli: &'a Cli,
+    /// Packages resolved so far (dependencies come first)
+    resolved: Vec<Package>,
+    /// Names of the packages being resolved (used to detect cycles)
+    resolving: Vec<String>,
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve_deps(&mut self, pkg: &Package) -> Result<()> {
+        self.resolving.push(pkg.spec.name.clone());
+        for (name, dep_spec) in &pkg.spec.deps {
+            if self.resolving.contains(name) {
+                bail!(
+                    "circular dependency: {} -> {}",
+                    self.resolving.join(" -> "),
+                    name
+                );
+            }
+            let dir = pkg.dir.join(&dep_spec.path);
+            if let Some(existing) = self.resolved.iter().find(|p| &p.spec.name == name) {
+                if !same_dir(&existing.dir, &dir)? {
+                    bail!(
+                        "package `{}' is found at both {} and {}",
+                        name,
+                        existing.dir.display(),
+                        dir.display()
+                    );
+                }
+                check_version(existing, dep_spec, pkg)?;
+                continue;
+            }
+            let dep = Package::new(self.cli, &dir).context(format!(
+                "failed to load dependency `{}' of {}",
+                name, pkg.spec.name
+            ))?;
+            if dep.spec.name != *name {
+                bail!(
+                    "{} is expected to be `{}' but is `{}'",
+                    dep.spec_path.display(),
+                    name,
+                    dep.spec.name
+                );
+            }
+            check_version(&dep, dep_spec, pkg)?;
+            self.resolve_deps(&dep)?;
+            self.resolved.push(dep);
+        }
+        self.resolving.pop();
+        Ok(())
+    }
+}
+
+fn check_version(dep: &Package, dep_spec: &DepSpec, depender: &Package) -> Result<()> {
+    if !dep_spec.version.matches(&dep.spec.version) {
+        bail!(
+            "{} requires {} {} but found {}",
+            depender.spec.name,
+            dep.spec.name,
+            dep_spec.version,
+            dep.spec.version
+        );
+    }
+    Ok(())
+}
+
+fn same_dir(a: &Path, b: &Path) -> Result<bool> {
+    Ok(a.canonicalize()? == b.canonicalize()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::testutil::TempDir;
+
+    fn resolve_app(tmp: &TempDir) -> Result<Vec<Package>> {
+        let cli = tmp.cli();
+        let app = Package::new(&cli, &tmp.join("app"))?;
+        resolve(&cli, &app)
+    }
+
+    fn names(pkgs: &[Package]) -> Vec<&str> {
+        pkgs.iter().map(|p| p.spec.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_resolve_order() -> Result<()> {
+        let tmp = TempDir::new();
+        tmp.package(
+            "app",
+            "app",
+            "0.1.0",
+            &[("b", "../b", "^0.1.0"), ("a", "../a", "^0.1.0")],
+        );
+        tmp.package("a", "a", "0.1.0", &[("b", "../b", "^0.1.0")]);
+        tmp.package("b", "b", "0.1.2", &[]);
+        let pkgs = resolve_app(&tmp)?;
+        assert_eq!(names(&pkgs), vec!["core", "b", "a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_circular_dependency() {
+        let tmp = TempDir::new();
+        tmp.package("app", "app", "0.1.0", &[("a", "../a", "^0.1.0")]);
+        tmp.package("a", "a", "0.1.0", &[("b", "../b", "^0.1.0")]);
+        tmp.package("b", "b", "0.1.0", &[("a", "../a", "^0.1.0")]);
+        let err = resolve_app(&tmp).unwrap_err();
+        assert_eq!(err.to_string(), "circular dependency: app -> a -> b -> a");
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        let tmp = TempDir::new();
+        tmp.package("app", "app", "0.1.0", &[("a", "../a", "^0.2.0")]);
+        tmp.package("a", "a", "0.1.0", &[]);
+        let err = resolve_app(&tmp).unwrap_err();
+        assert_eq!(err.to_string(), "app requires a ^0.2.0 but found 0.1.0");
+    }
+
+    #[test]
+    fn test_version_mismatch_of_resolved_package() {
+        let tmp = TempDir::new();
+        tmp.package(
+            "app",
+            "app",
+            "0.1.0",
+            &[("a", "../a", "^0.1.0"), ("b", "../b", "^0.1.0")],
+        );
+        tmp.package("a", "a", "0.1.0", &[]);
+        tmp.package("b", "b", "0.1.0", &[("a", "../a", "^0.3.0")]);
+        let err = resolve_app(&tmp).unwrap_err();
+        assert_eq!(err.to_string(), "b requires a ^0.3.0 but found 0.1.0");
+    }
+
+    #[test]
+    fn test_same_name_at_two_paths() {
+        let tmp = TempDir::new();
+        tmp.package(
+            "app",
+            "app",
+            "0.1.0",
+            &[("a", "../a", "^0.1.0"), ("b", "../b", "^0.1.0")],
+        );
+        tmp.package("a", "a", "0.1.0", &[]);
+        tmp.package("b", "b", "0.1.0", &[("a", "../a2", "^0.1.0")]);
+        tmp.package("a2", "a", "0.1.0", &[]);
+        let err = resolve_app(&tmp).unwrap_err();
+        assert!(err.to_string().starts_with("package `a' is found at both "));
+    }
+
+    #[test]
+    fn test_same_dir_spelled_differently() -> Result<()> {
+        let tmp = TempDir::new();
+        tmp.package(
+            "app",
+            "app",
+            "0.1.0",
+            &[("a", "../a", "^0.1.0"), ("b", "../b", "^0.1.0")],
+        );
+        tmp.package("a", "a", "0.1.0", &[]);
+        tmp.package("b", "b", "0.1.0", &[("a", "./../b/../a", "^0.1.0")]);
+        let pkgs = resolve_app(&tmp)?;
+        assert_eq!(names(&pkgs), vec!["core", "a", "b"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_name_mismatch() {
+        let tmp = TempDir::new();
+        tmp.package("app", "app", "0.1.0", &[("a", "../a", "^0.1.0")]);
+        tmp.package("a", "not_a", "0.1.0", &[]);
+        let err = resolve_app(&tmp).unwrap_err();
+        assert!(err
+            .to_string()
+            .ends_with("is expected to be `a' but is `not_a'"));
+    }
+
+    #[test]
+    fn test_transitive_deps() -> Result<()> {
+        let tmp = TempDir::new();
+        tmp.package(
+            "app",
+            "app",
+            "0.1.0",
+            &[("a", "../a", "^0.1.0"), ("c", "../c", "^0.1.0")],
+        );
+        tmp.package("a", "a", "0.1.0", &[("b", "../b", "^0.1.0")]);
+        tmp.package("b", "b", "0.1.0", &[]);
+        tmp.package("c", "c", "0.1.0", &[]);
+        let pkgs = resolve_app(&tmp)?;
+        let a = pkgs.iter().find(|p| p.spec.name == "a").unwrap();
+        assert_eq!(names(&transitive_deps(&pkgs, a)), vec!["core", "b"]);
+        Ok(())
+    }
+}
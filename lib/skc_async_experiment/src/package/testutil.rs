@@ -0,0 +1,68 @@
+//! Helpers to create packages in a temporary directory for unit tests.
+use crate::cli::Cli;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory which is removed when dropped.
+/// Also serves as $SHIIKA_ROOT and $SHIIKA_WORK (with a dummy `core`.)
+pub struct TempDir {
+    pub path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "shiika-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        let tmp = TempDir { path };
+        tmp.package("packages/core", "core", "0.1.0", &[]);
+        tmp
+    }
+
+    pub fn cli(&self) -> Cli {
+        Cli {
+            log_dir: self.path.join("debug_logs"),
+            shiika_root: self.path.clone(),
+            shiika_work: self.path.join("work"),
+        }
+    }
+
+    /// Create a package at `dir` (relative to this directory.)
+    /// `deps` is a list of `(name, path, version requirement)`.
+    pub fn package(&self, dir: &str, name: &str, version: &str, deps: &[(&str, &str, &str)]) {
+        let deps = deps
+            .iter()
+            .map(|(name, path, req)| {
+                format!("{}: {{ path: \"{}\", version: \"{}\" }}", name, path, req)
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let dir = self.path.join(dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("package.json5"),
+            format!(
+                "{{ name: \"{}\", version: \"{}\", deps: {{ {} }} }}\n",
+                name, version, deps
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("index.sk"), "").unwrap();
+    }
+
+    pub fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
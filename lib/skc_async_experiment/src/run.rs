@@ -7,14 +7,17 @@ pub fn main() -> Result<()> {
     let mut cli = cli::Cli::init()?;
     let options = cli::CommandLineOptions::try_parse()?;
     match &options.command {
-        Some(cli::Command::Build { path }) => {
-            cli.build(path)?;
+        Some(cli::Command::Build { path, locked }) => {
+            cli.build(path, *locked)?;
         }
         Some(cli::Command::Compile { path }) => {
             cli.compile(path)?;
         }
-        Some(cli::Command::Run { path }) => {
-            cli.run(path)?;
+        Some(cli::Command::Run { path, args }) => {
+            let code = cli.run(path, args)?;
+            if code != 0 {
+                std::process::exit(code);
+            }
         }
         None => {}
     }
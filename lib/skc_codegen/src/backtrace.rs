@@ -0,0 +1,126 @@
+//! Frames for Shiika-level backtraces.
+//!
+//! Each method (and lambda) allocates a frame `{ prev, name, file, line }`
+//! on the stack and links it to `@shiika_backtrace_top`, which is defined
+//! in skc_rustlib and read on `panic` or runtime errors.
+use crate::code_gen_context::CodeGenContext;
+use crate::CodeGen;
+use anyhow::Result;
+use inkwell::values::PointerValue;
+use shiika_ast::LocationSpan;
+
+/// Name of the llvm global that holds the innermost frame
+const BACKTRACE_TOP: &str = "shiika_backtrace_top";
+/// Name of the frame of the toplevel
+pub const MAIN_FRAME_NAME: &str = "<main>";
+/// Index of `prev` in the frame struct
+const FRAME_PREV_IDX: u32 = 0;
+/// Index of `line` in the frame struct
+const FRAME_LINE_IDX: u32 = 3;
+
+impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
+    /// Declare `@shiika_backtrace_top`
+    pub fn gen_backtrace_declares(&self) {
+        self.module.add_global(self.ptr_type, None, BACKTRACE_TOP);
+    }
+
+    /// Allocate the frame of the current llvm function and make it the
+    /// innermost one
+    pub fn gen_frame_push(&self, name: &str, locs: &LocationSpan) -> Result<PointerValue<'run>> {
+        let (file, line) = match locs {
+            LocationSpan::Just {
+                filepath, begin, ..
+            } => (filepath.to_string_lossy().to_string(), begin.line + 1),
+            LocationSpan::Empty => (String::new(), 0),
+        };
+        let frame_type = self.frame_type();
+        let frame = self.builder.build_alloca(frame_type, "frame")?;
+        let top = self.backtrace_top();
+        let prev = self.builder.build_load(self.ptr_type, top, "prev_frame")?;
+        let name_str = self.builder.build_global_string_ptr(name, "frame_name")?;
+        let file_str = self.builder.build_global_string_ptr(&file, "frame_file")?;
+        let fields = [
+            prev,
+            name_str.as_pointer_value().into(),
+            file_str.as_pointer_value().into(),
+            self.i64_type.const_int(line as u64, false).into(),
+        ];
+        for (i, value) in fields.iter().enumerate() {
+            let ptr = self
+                .builder
+                .build_struct_gep(frame_type, frame, i as u32, "")?;
+            self.builder.build_store(ptr, *value)?;
+        }
+        self.builder.build_store(top, frame)?;
+        Ok(frame)
+    }
+
+    /// Remove the frame of the current llvm function (if any)
+    pub fn gen_frame_pop(&self, ctx: &CodeGenContext<'hir, 'run>) -> Result<()> {
+        let Some(frame) = ctx.frame else {
+            return Ok(());
+        };
+        let ptr = self
+            .builder
+            .build_struct_gep(self.frame_type(), frame, FRAME_PREV_IDX, "")?;
+        let prev = self.builder.build_load(self.ptr_type, ptr, "prev_frame")?;
+        self.builder.build_store(self.backtrace_top(), prev)?;
+        Ok(())
+    }
+
+    /// Record the line number of `locs` in the current frame (1-origin, as
+    /// `Location::line` is 0-origin)
+    pub fn gen_set_line(
+        &self,
+        ctx: &CodeGenContext<'hir, 'run>,
+        locs: &LocationSpan,
+    ) -> Result<()> {
+        let (Some(frame), LocationSpan::Just { begin, .. }) = (ctx.frame, locs) else {
+            return Ok(());
+        };
+        let ptr =
+            self.builder
+                .build_struct_gep(self.frame_type(), frame, FRAME_LINE_IDX, "line")?;
+        self.builder
+            .build_store(ptr, self.i64_type.const_int(begin.line as u64 + 1, false))?;
+        Ok(())
+    }
+
+    fn frame_type(&self) -> inkwell::types::StructType<'ictx> {
+        self.context.struct_type(
+            &[
+                self.ptr_type.into(),
+                self.ptr_type.into(),
+                self.ptr_type.into(),
+                self.i64_type.into(),
+            ],
+            false,
+        )
+    }
+
+    fn backtrace_top(&self) -> PointerValue<'run> {
+        self.module
+            .get_global(BACKTRACE_TOP)
+            .expect("[BUG] shiika_backtrace_top not declared")
+            .as_pointer_value()
+    }
+}
+
+/// Returns the name shown in the backtrace for a method
+/// (eg. "Foo#bar" or "Foo.baz" for "Meta:Foo#baz")
+pub fn method_frame_name(method_name: &str) -> String {
+    match method_name.strip_prefix("Meta:") {
+        Some(s) => s.replacen('#', ".", 1),
+        None => method_name.to_string(),
+    }
+}
+
+/// Returns the name shown in the backtrace for a lambda
+/// (eg. "block in Foo#bar" for "lambda_1_in_Foo#bar")
+pub fn lambda_frame_name(lambda_name: &str) -> String {
+    match lambda_name.split_once("_in_") {
+        Some((_, "toplevel")) => format!("block in {}", MAIN_FRAME_NAME),
+        Some((_, place)) => format!("block in {}", method_frame_name(place)),
+        None => lambda_name.to_string(),
+    }
+}
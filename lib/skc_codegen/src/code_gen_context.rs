@@ -10,13 +10,26 @@ pub struct CodeGenContext<'hir: 'run, 'run> {
     /// If `function` corresponds to a lambda or a method
     pub function_origin: FunctionOrigin<'hir>,
     /// Ptr of local variables
-    pub lvars: HashMap<String, inkwell::values::PointerValue<'run>>,
+    pub lvars: HashMap<String, LVarPtr<'run>>,
     /// End of `while`, if any
     pub current_loop_end: Option<Rc<inkwell::basic_block::BasicBlock<'run>>>,
     /// End of the current llvm function. Only used for lambdas
     pub current_func_end: Rc<inkwell::basic_block::BasicBlock<'run>>,
     /// Arguments of `return` found in this context
     pub returns: Vec<(SkObj<'run>, inkwell::basic_block::BasicBlock<'run>)>,
+    /// Frame of the current llvm function for backtraces, if any
+    pub frame: Option<inkwell::values::PointerValue<'run>>,
+    /// Debug info of the current llvm function, if enabled
+    pub di_subprogram: Option<inkwell::debug_info::DISubprogram<'run>>,
+}
+
+/// Where a local variable is stored
+#[derive(Debug, Clone, Copy)]
+pub struct LVarPtr<'run> {
+    pub ptr: inkwell::values::PointerValue<'run>,
+    /// `true` if the value is stored unboxed (i64, double or i1.) This is the
+    /// case for `Int`, `Float` and `Bool` variables not captured by lambdas
+    pub raw: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -36,7 +49,7 @@ impl<'hir, 'run> CodeGenContext<'hir, 'run> {
         function: inkwell::values::FunctionValue<'run>,
         function_end: Rc<inkwell::basic_block::BasicBlock<'run>>,
         function_origin: FunctionOrigin<'hir>,
-        lvars: HashMap<String, inkwell::values::PointerValue<'run>>,
+        lvars: HashMap<String, LVarPtr<'run>>,
     ) -> CodeGenContext<'hir, 'run> {
         CodeGenContext {
             function,
@@ -45,6 +58,8 @@ impl<'hir, 'run> CodeGenContext<'hir, 'run> {
             current_loop_end: None,
             current_func_end: function_end,
             returns: Default::default(),
+            frame: None,
+            di_subprogram: None,
         }
     }
 
@@ -52,8 +67,8 @@ impl<'hir, 'run> CodeGenContext<'hir, 'run> {
     /// Returns the original HashMap.
     pub fn inject_lvars(
         &mut self,
-        lvars: HashMap<String, inkwell::values::PointerValue<'run>>,
-    ) -> HashMap<String, inkwell::values::PointerValue<'run>> {
+        lvars: HashMap<String, LVarPtr<'run>>,
+    ) -> HashMap<String, LVarPtr<'run>> {
         let mut new_lvars = self
             .lvars
             .clone()
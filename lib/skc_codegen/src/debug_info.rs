@@ -0,0 +1,372 @@
+//! DWARF debug info (enabled by `shiika compile --debug`.)
+//!
+//! Each method and lambda gets a `DISubprogram` and every expression sets
+//! the current `DILocation` from its `LocationSpan`. Parameters and local
+//! variables are described with `DILocalVariable` so that they can be
+//! inspected in gdb/lldb.
+use crate::code_gen_context::{CodeGenContext, LVarPtr};
+use crate::CodeGen;
+use anyhow::Result;
+use inkwell::debug_info::*;
+use inkwell::module::{FlagBehavior, Module};
+use inkwell::values::FunctionValue;
+use inkwell::AddressSpace;
+use shiika_ast::LocationSpan;
+use shiika_core::ty::TermTy;
+use skc_hir::{Hir, HirLVar, MethodParam, SkMethodBody};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Type encodings (`DW_ATE_*`)
+const DW_ATE_BOOLEAN: u32 = 0x02;
+const DW_ATE_FLOAT: u32 = 0x04;
+const DW_ATE_SIGNED: u32 = 0x05;
+/// Size of a pointer in bits
+const PTR_BITS: u64 = 64;
+const DWARF_VERSION: u64 = 4;
+
+pub struct DebugInfo<'ictx> {
+    builder: DebugInfoBuilder<'ictx>,
+    compile_unit: DICompileUnit<'ictx>,
+    /// Cache of `DIFile`s
+    files: RefCell<HashMap<PathBuf, DIFile<'ictx>>>,
+    /// Cache of `DIType`s (key: Shiika type name)
+    types: RefCell<HashMap<String, DIType<'ictx>>>,
+}
+
+/// Debug info of a llvm function
+#[derive(Clone, Copy)]
+pub struct DIFunction<'ictx> {
+    pub subprogram: DISubprogram<'ictx>,
+    file: DIFile<'ictx>,
+    line: u32,
+}
+
+impl<'ictx> DebugInfo<'ictx> {
+    pub fn new(module: &Module<'ictx>, hir: &Hir) -> DebugInfo<'ictx> {
+        let (filename, directory) = split_path(&main_source_file(hir));
+        let (builder, compile_unit) = module.create_debug_info_builder(
+            true,
+            DWARFSourceLanguage::C,
+            &filename,
+            &directory,
+            "shiika",
+            false,
+            "",
+            0,
+            "",
+            DWARFEmissionKind::Full,
+            0,
+            false,
+            false,
+            "",
+            "",
+        );
+        add_module_flags(module);
+        DebugInfo {
+            builder,
+            compile_unit,
+            files: Default::default(),
+            types: Default::default(),
+        }
+    }
+
+    /// Resolve the metadata. Must be called before writing the module
+    pub fn finalize(&self) {
+        self.builder.finalize();
+    }
+
+    fn file(&self, path: &Path) -> DIFile<'ictx> {
+        *self
+            .files
+            .borrow_mut()
+            .entry(path.to_path_buf())
+            .or_insert_with(|| {
+                let (filename, directory) = split_path(path);
+                self.builder.create_file(&filename, &directory)
+            })
+    }
+}
+
+impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
+    /// Attach a `DISubprogram` to `function` and set the debug location to
+    /// its beginning. Returns None if debug info is disabled.
+    pub fn gen_di_subprogram(
+        &self,
+        function: FunctionValue<'run>,
+        name: &str,
+        locs: &LocationSpan,
+        params: &[MethodParam],
+        ret_ty: &TermTy,
+    ) -> Option<DIFunction<'ictx>> {
+        let di = self.debug_info.as_ref()?;
+        let LocationSpan::Just {
+            filepath, begin, ..
+        } = locs
+        else {
+            return None;
+        };
+        let file = di.file(filepath);
+        let line = begin.line as u32;
+        let param_types = params
+            .iter()
+            .map(|p| self.di_type(&p.ty))
+            .collect::<Vec<_>>();
+        let ret_type = if ret_ty.is_never_type() {
+            None
+        } else {
+            Some(self.di_type(ret_ty))
+        };
+        let subroutine_type =
+            di.builder
+                .create_subroutine_type(file, ret_type, &param_types, DIFlags::ZERO);
+        let linkage_name = function.get_name().to_string_lossy();
+        let subprogram = di.builder.create_function(
+            di.compile_unit.as_debug_info_scope(),
+            name,
+            Some(&linkage_name),
+            file,
+            line,
+            subroutine_type,
+            false,
+            true,
+            line,
+            DIFlags::ZERO,
+            false,
+        );
+        function.set_subprogram(subprogram);
+        let loc = di.builder.create_debug_location(
+            self.context,
+            line,
+            0,
+            subprogram.as_debug_info_scope(),
+            None,
+        );
+        self.builder.set_current_debug_location(loc);
+        Some(DIFunction {
+            subprogram,
+            file,
+            line,
+        })
+    }
+
+    /// Describe the parameters and the local variables of the current
+    /// function. Parameters are copied to stack slots for the debugger.
+    pub fn gen_di_variables(
+        &self,
+        di_func: DIFunction<'ictx>,
+        function: FunctionValue<'run>,
+        params: &[MethodParam],
+        lvars: &[HirLVar],
+        lvar_ptrs: &HashMap<String, LVarPtr<'run>>,
+    ) -> Result<()> {
+        let Some(di) = self.debug_info.as_ref() else {
+            return Ok(());
+        };
+        let scope = di_func.subprogram.as_debug_info_scope();
+        let DIFunction { file, line, .. } = di_func;
+        let loc = di
+            .builder
+            .create_debug_location(self.context, line, 0, scope, None);
+        let block = self.builder.get_insert_block().unwrap();
+        for (i, param) in params.iter().enumerate() {
+            // +1 for the receiver (or the lambda itself)
+            let Some(value) = function.get_nth_param(i as u32 + 1) else {
+                continue;
+            };
+            let ptr = self.builder.build_alloca(self.llvm_type(), &param.name)?;
+            self.builder.build_store(ptr, value)?;
+            let var = di.builder.create_parameter_variable(
+                scope,
+                &param.name,
+                i as u32 + 1,
+                file,
+                line,
+                self.di_type(&param.ty),
+                true,
+                DIFlags::ZERO,
+            );
+            di.builder
+                .insert_declare_at_end(ptr, Some(var), None, loc, block);
+        }
+        for lvar in lvars {
+            let Some(LVarPtr { ptr, raw }) = lvar_ptrs.get(&lvar.name) else {
+                continue;
+            };
+            let di_ty = if *raw {
+                self.di_value_type(&lvar.ty).unwrap()
+            } else {
+                self.di_type(&lvar.ty)
+            };
+            let var = di.builder.create_auto_variable(
+                scope,
+                &lvar.name,
+                file,
+                line,
+                di_ty,
+                true,
+                DIFlags::ZERO,
+                PTR_BITS as u32,
+            );
+            di.builder
+                .insert_declare_at_end(*ptr, Some(var), None, loc, block);
+        }
+        Ok(())
+    }
+
+    /// Set the debug location to the beginning of `locs`
+    pub fn set_debug_location(&self, ctx: &CodeGenContext<'hir, 'run>, locs: &LocationSpan) {
+        let (Some(di), Some(subprogram), LocationSpan::Just { begin, .. }) =
+            (self.debug_info.as_ref(), ctx.di_subprogram, locs)
+        else {
+            return;
+        };
+        let loc = di.builder.create_debug_location(
+            self.context,
+            begin.line as u32,
+            begin.col as u32,
+            subprogram.as_debug_info_scope(),
+            None,
+        );
+        self.builder.set_current_debug_location(loc);
+    }
+
+    /// Stop attaching debug locations (called after generating a function
+    /// so that other functions will not refer to its `DISubprogram`)
+    pub fn unset_debug_location(&self) {
+        if self.debug_info.is_some() {
+            self.builder.unset_current_debug_location();
+        }
+    }
+
+    /// Returns the `DIType` for a Shiika type, which is a pointer to an
+    /// object struct
+    fn di_type(&self, ty: &TermTy) -> DIType<'ictx> {
+        let di = self.debug_info.as_ref().unwrap();
+        let name = ty.to_string();
+        if let Some(t) = di.types.borrow().get(&name) {
+            return *t;
+        }
+        let t = di
+            .builder
+            .create_pointer_type(
+                &name,
+                self.di_struct_type(ty),
+                PTR_BITS,
+                PTR_BITS as u32,
+                AddressSpace::default(),
+            )
+            .as_type();
+        di.types.borrow_mut().insert(name, t);
+        t
+    }
+
+    /// Returns the `DIType` for the unboxed value of Int, Float and Bool
+    fn di_value_type(&self, ty: &TermTy) -> Option<DIType<'ictx>> {
+        let di = self.debug_info.as_ref().unwrap();
+        let (name, bits, encoding) = match ty.fullname.0.as_str() {
+            "Int" => ("i64", 64, DW_ATE_SIGNED),
+            "Float" => ("f64", 64, DW_ATE_FLOAT),
+            "Bool" => ("bool", 8, DW_ATE_BOOLEAN),
+            _ => return None,
+        };
+        let basic = di
+            .builder
+            .create_basic_type(name, bits, encoding, DIFlags::ZERO)
+            .unwrap();
+        Some(basic.as_type())
+    }
+
+    /// Returns the `DIType` for the struct of a Shiika object.
+    /// Only the value of Int, Float and Bool is described as a member.
+    fn di_struct_type(&self, ty: &TermTy) -> DIType<'ictx> {
+        let di = self.debug_info.as_ref().unwrap();
+        let scope = di.compile_unit.as_debug_info_scope();
+        let file = di.compile_unit.get_file();
+        // vtable and class object
+        let header_bits = PTR_BITS * 2;
+        let mut size = header_bits;
+        let mut members = vec![];
+        if let Some(basic) = self.di_value_type(ty) {
+            let bits = basic.get_size_in_bits();
+            let member = di.builder.create_member_type(
+                scope,
+                "value",
+                file,
+                0,
+                bits,
+                bits as u32,
+                header_bits,
+                DIFlags::ZERO,
+                basic,
+            );
+            members.push(member.as_type());
+            size += PTR_BITS;
+        }
+        di.builder
+            .create_struct_type(
+                scope,
+                &ty.fullname.0,
+                file,
+                0,
+                size,
+                PTR_BITS as u32,
+                DIFlags::ZERO,
+                None,
+                &members,
+                0,
+                None,
+                "",
+            )
+            .as_type()
+    }
+}
+
+fn add_module_flags(module: &Module) {
+    let i32_type = module.get_context().i32_type();
+    module.add_basic_value_flag(
+        "Debug Info Version",
+        FlagBehavior::Warning,
+        i32_type.const_int(debug_metadata_version() as u64, false),
+    );
+    module.add_basic_value_flag(
+        "Dwarf Version",
+        FlagBehavior::Warning,
+        i32_type.const_int(DWARF_VERSION, false),
+    );
+}
+
+/// Returns the path of the first source file found in the hir
+fn main_source_file(hir: &Hir) -> PathBuf {
+    let main_locs = hir.main_exprs.iter().map(|e| &e.locs);
+    let method_locs = hir
+        .sk_methods
+        .values()
+        .flatten()
+        .filter_map(|m| match &m.body {
+            SkMethodBody::Normal { exprs } => Some(&exprs.locs),
+            _ => None,
+        });
+    main_locs
+        .chain(method_locs)
+        .find_map(|locs| match locs {
+            LocationSpan::Just { filepath, .. } => Some(filepath.to_path_buf()),
+            LocationSpan::Empty => None,
+        })
+        .unwrap_or_default()
+}
+
+/// Split a path into the file name and the (absolute) directory
+fn split_path(path: &Path) -> (String, String) {
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let filename = path
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let directory = path
+        .parent()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    (filename, directory)
+}
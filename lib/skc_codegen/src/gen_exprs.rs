@@ -1,5 +1,6 @@
 use crate::code_gen_context::*;
 use crate::lambda::LambdaCapture;
+use crate::primitives;
 use crate::utils::*;
 use crate::values::*;
 use crate::vtable::VTableRef;
@@ -8,6 +9,7 @@ use crate::CodeGen;
 use anyhow::Result;
 use inkwell::types::*;
 use inkwell::values::*;
+use shiika_ast::LocationSpan;
 use shiika_core::{names::*, ty, ty::*};
 use skc_corelib::fn_x;
 use skc_hir::pattern_match;
@@ -32,6 +34,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         //        r.with_label(skc_error::Label::new(locs_span).with_message(msg))
         //    })
         //);
+        self.set_debug_location(ctx, &expr.locs);
         match &expr.node {
             HirLogicalNot { expr } => self.gen_logical_not(ctx, expr),
             HirLogicalAnd { left, right } => self.gen_logical_and(ctx, left, right),
@@ -64,6 +67,14 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
                 ..
             } => self.gen_ivar_assign(ctx, idx, name, rhs, self_ty),
             HirConstAssign { fullname, rhs } => self.gen_const_assign(ctx, fullname, rhs),
+            HirMethodCall {
+                receiver_expr,
+                method_fullname,
+                arg_exprs,
+                ..
+            } if primitives::is_primitive_call(method_fullname, receiver_expr, arg_exprs) => {
+                self.gen_primitive_op(ctx, expr)
+            }
             HirMethodCall {
                 receiver_expr,
                 method_fullname,
@@ -77,6 +88,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
                 arg_exprs,
                 tyarg_exprs,
                 &expr.ty,
+                &expr.locs,
             ),
             HirModuleMethodCall {
                 receiver_expr,
@@ -94,11 +106,12 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
                 arg_exprs,
                 tyarg_exprs,
                 &expr.ty,
+                &expr.locs,
             ),
             HirLambdaInvocation {
                 lambda_expr,
                 arg_exprs,
-            } => self.gen_lambda_invocation(ctx, lambda_expr, arg_exprs, &expr.ty),
+            } => self.gen_lambda_invocation(ctx, lambda_expr, arg_exprs, &expr.ty, &expr.locs),
             HirArgRef { idx, .. } => Ok(Some(self.gen_arg_ref(ctx, idx))),
             HirLVarRef { name } => Ok(Some(self.gen_lvar_ref(ctx, &expr.ty, name))),
             HirIVarRef {
@@ -249,8 +262,9 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         // IfBegin:
         self.builder.build_unconditional_branch(begin_block)?;
         self.builder.position_at_end(begin_block);
-        let cond_value = self.gen_expr(ctx, cond_expr)?.unwrap();
-        self.gen_conditional_branch(cond_value, then_block, else_block)?;
+        let cond_value = self.gen_raw_bool(ctx, cond_expr)?.unwrap();
+        self.builder
+            .build_conditional_branch(cond_value, then_block, else_block)?;
         // IfThen:
         self.builder.position_at_end(then_block);
         let then_value = self.gen_expr(ctx, then_exprs)?;
@@ -390,17 +404,18 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         self.builder.build_unconditional_branch(begin_block)?;
         // WhileBegin:
         self.builder.position_at_end(begin_block);
-        let cond_value = self.gen_expr(ctx, cond_expr)?.unwrap();
+        let cond_value = self.gen_raw_bool(ctx, cond_expr)?.unwrap();
         let body_block = self.context.append_basic_block(ctx.function, "WhileBody");
         let end_block = self.context.append_basic_block(ctx.function, "WhileEnd");
-        self.gen_conditional_branch(cond_value, body_block, end_block)?;
+        self.builder
+            .build_conditional_branch(cond_value, body_block, end_block)?;
         // WhileBody:
         self.builder.position_at_end(body_block);
         let rc1 = Rc::new(end_block);
         let rc2 = Rc::clone(&rc1);
         let orig_loop_end = ctx.current_loop_end.as_ref().map(Rc::clone);
         ctx.current_loop_end = Some(rc1);
-        self.gen_expr(ctx, body_exprs)?;
+        self.gen_void_expr(ctx, body_exprs)?;
         ctx.current_loop_end = orig_loop_end;
         self.builder.build_unconditional_branch(begin_block)?;
 
@@ -459,12 +474,19 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         name: &str,
         rhs: &'hir HirExpression,
     ) -> Result<Option<SkObj<'run>>> {
-        let value = self.gen_expr(ctx, rhs)?.unwrap();
-        let ptr = ctx
+        let lvar = *ctx
             .lvars
             .get(name)
             .unwrap_or_else(|| panic!("[BUG] lvar `{}' not found in ctx.lvars", name));
-        self.builder.build_store(*ptr, value.0)?;
+        if lvar.raw {
+            return Ok(self
+                .gen_raw_lvar_assign(ctx, name, rhs)?
+                .map(|v| self.box_raw(v)));
+        }
+        let Some(value) = self.gen_expr(ctx, rhs)? else {
+            return Ok(None);
+        };
+        self.builder.build_store(lvar.ptr, value.0)?;
         Ok(Some(value))
     }
 
@@ -500,6 +522,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
     }
 
     /// Generate method call
+    #[allow(clippy::too_many_arguments)]
     fn gen_method_call(
         &'run self,
         ctx: &mut CodeGenContext<'hir, 'run>,
@@ -508,6 +531,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         arg_exprs: &'hir [HirExpression],
         tyarg_exprs: &'hir [HirExpression],
         ret_ty: &TermTy,
+        locs: &LocationSpan,
     ) -> Result<Option<SkObj<'run>>> {
         // Prepare arguments
         let receiver_value = self.gen_expr(ctx, receiver_expr)?.unwrap();
@@ -528,6 +552,8 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             .append_basic_block(ctx.function, &format!("Invoke_{}", method_fullname));
         self.builder.build_unconditional_branch(start_block)?;
         self.builder.position_at_end(start_block);
+        self.gen_set_line(ctx, locs)?;
+        self.set_debug_location(ctx, locs);
 
         // Get the llvm function from vtable of the class of the object
         let func_type = self.llvm_func_type(Some(&receiver_expr.ty), &arg_tys, ret_ty);
@@ -598,6 +624,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         arg_exprs: &'hir [HirExpression],
         tyarg_exprs: &'hir [HirExpression],
         ret_ty: &TermTy,
+        locs: &LocationSpan,
     ) -> Result<Option<SkObj<'run>>> {
         // Prepare arguments
         let receiver_value = self.gen_expr(ctx, receiver_expr)?.unwrap();
@@ -618,6 +645,8 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             .append_basic_block(ctx.function, &format!("Invoke_{}", method_name));
         self.builder.build_unconditional_branch(start_block)?;
         self.builder.position_at_end(start_block);
+        self.gen_set_line(ctx, locs)?;
+        self.set_debug_location(ctx, locs);
 
         // Get the llvm function via wtable
         let key = self.get_const_addr_int(&module_fullname.to_const_fullname());
@@ -675,6 +704,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         lambda_expr: &'hir HirExpression,
         arg_exprs: &'hir [HirExpression],
         ret_ty: &TermTy,
+        locs: &LocationSpan,
     ) -> Result<Option<SkObj<'run>>> {
         let lambda_obj = self.gen_expr(ctx, lambda_expr)?.unwrap();
 
@@ -690,6 +720,8 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
             .append_basic_block(ctx.function, "Invoke_lambda");
         self.builder.build_unconditional_branch(start_block)?;
         self.builder.position_at_end(start_block);
+        self.gen_set_line(ctx, locs)?;
+        self.set_debug_location(ctx, locs);
         let end_block = self
             .context
             .append_basic_block(ctx.function, "Invoke_lambda_end");
@@ -839,14 +871,18 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         ty: &TermTy,
         name: &str,
     ) -> SkObj<'run> {
-        let ptr = ctx
+        let lvar = ctx
             .lvars
             .get(name)
             .unwrap_or_else(|| panic!("[BUG] lvar `{}' not found in ctx.lvars", name));
+        if lvar.raw {
+            let v = self.build_raw_lvar_load(lvar.ptr, ty, name);
+            return self.box_raw(v);
+        }
         SkObj::new(
             ty.clone(),
             self.builder
-                .build_load(self.llvm_type(), *ptr, name)
+                .build_load(self.llvm_type(), lvar.ptr, name)
                 .unwrap(),
         )
     }
@@ -978,7 +1014,7 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
                         self.gen_lvar_ref(ctx, &cap.ty, name)
                     } else {
                         // Captured by pointer to be reassigned
-                        SkObj::new(cap.ty.clone(), *ctx.lvars.get(name).unwrap())
+                        SkObj::new(cap.ty.clone(), ctx.lvars.get(name).unwrap().ptr)
                     }
                 }
                 HirLambdaCaptureDetail::CaptureArg { idx } => {
@@ -1263,18 +1299,41 @@ impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
         exprs: &'hir [HirExpression],
     ) -> Result<Option<SkObj<'run>>> {
         debug_assert!(!exprs.is_empty());
-        let mut last_value = None;
-        for expr in exprs {
-            let value = self.gen_expr(ctx, expr)?;
-            if value.is_none() {
+        let (last, init) = exprs.split_last().unwrap();
+        for expr in init {
+            if self.gen_void_expr(ctx, expr)?.is_none() {
                 // Found `return`, `panic` or something. The rest of `exprs`
                 // will never be executed
                 return Ok(None);
-            } else {
-                last_value = Some(value);
             }
         }
-        Ok(last_value.unwrap())
+        self.gen_expr(ctx, last)
+    }
+
+    /// Generate code for an expression whose value is not used.
+    /// Assignments to unboxed lvars are done without boxing the value.
+    /// Returns `None` if the control never reaches the end of `expr`.
+    pub fn gen_void_expr(
+        &'run self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        expr: &'hir HirExpression,
+    ) -> Result<Option<()>> {
+        match &expr.node {
+            HirLVarDecl { name, rhs, .. } | HirLVarAssign { name, rhs }
+                if ctx.lvars.get(name).is_some_and(|lvar| lvar.raw) =>
+            {
+                Ok(self.gen_raw_lvar_assign(ctx, name, rhs)?.map(|_| ()))
+            }
+            HirParenthesizedExpr { exprs } => {
+                for expr in exprs {
+                    if self.gen_void_expr(ctx, expr)?.is_none() {
+                        return Ok(None);
+                    }
+                }
+                Ok(Some(()))
+            }
+            _ => Ok(self.gen_expr(ctx, expr)?.map(|_| ())),
+        }
     }
 
     /// Returns a special value (currently a nullptr) that denotes using the default argument value.
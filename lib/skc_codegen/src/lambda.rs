@@ -1,3 +1,4 @@
+use crate::backtrace;
 use crate::utils::{lambda_capture_struct_name, LlvmFuncName};
 use crate::values::{I8Ptr, SkObj};
 use crate::CodeGen;
@@ -187,6 +188,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> HirVisitor<'hir> for GenLambdaFuncVisitor<'
                     lvars,
                     ret_ty,
                     Some(name.to_string()),
+                    &backtrace::lambda_frame_name(name),
                 )?;
             }
             _ => (),
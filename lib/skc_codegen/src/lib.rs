@@ -1,7 +1,10 @@
+mod backtrace;
 mod boxing;
 mod code_gen_context;
+mod debug_info;
 mod gen_exprs;
 mod lambda;
+mod primitives;
 mod utils;
 pub mod values;
 mod vtable;
@@ -48,6 +51,8 @@ pub struct CodeGen<'hir: 'ictx, 'run, 'ictx: 'run> {
     imported_vtables: &'hir VTables,
     /// Toplevel `self`
     the_main: Option<SkObj<'run>>,
+    /// Enabled by `--debug`
+    debug_info: Option<debug_info::DebugInfo<'ictx>>,
 }
 
 /// Compile hir and dump it to `outpath`
@@ -58,6 +63,8 @@ pub fn run<P: AsRef<Path>>(
     opt_ll_path: Option<P>,
     generate_main: bool,
     opt_target_triple: Option<&inkwell::targets::TargetTriple>,
+    // Emit DWARF debug info if true
+    debug: bool,
 ) -> Result<()> {
     let context = inkwell::context::Context::create();
     let module = context.create_module("main");
@@ -66,7 +73,13 @@ pub fn run<P: AsRef<Path>>(
     }
     let builder = context.create_builder();
     let mut code_gen = CodeGen::new(mir, &context, &module, &builder, &generate_main);
+    if debug {
+        code_gen.debug_info = Some(debug_info::DebugInfo::new(&module, &mir.hir));
+    }
     code_gen.gen_program(&mir.hir, &mir.imports)?;
+    if let Some(di) = &code_gen.debug_info {
+        di.finalize();
+    }
     code_gen.module.write_bitcode_to_path(bc_path.as_ref());
     if let Some(ll_path) = opt_ll_path {
         code_gen
@@ -102,6 +115,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             vtables: &mir.vtables,
             imported_vtables: &mir.imports.vtables,
             the_main: None,
+            debug_info: None,
         }
     }
 
@@ -165,6 +179,12 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         );
         self.module
             .add_function("shiika_insert_wtable", fn_type, None);
+
+        let fn_type = self.void_type.fn_type(&[self.ptr_type.into()], false);
+        self.module
+            .add_function("shiika_int_overflow", fn_type, None);
+
+        self.gen_backtrace_declares();
     }
 
     /// Define llvm struct type for `Class` in advance
@@ -235,8 +255,16 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             let func_ptrs = method_names
                 .iter()
                 .map(|name| {
+                    let func_name = method_func_name(name);
+                    // Private methods of imported classes are not declared
+                    // because they are not exported
                     let func = self
-                        .get_llvm_func(&method_func_name(name))
+                        .module
+                        .get_function(&func_name.0)
+                        .unwrap_or_else(|| {
+                            let fn_type = self.void_type.fn_type(&[], false);
+                            self.module.add_function(&func_name.0, fn_type, None)
+                        })
                         .as_global_value()
                         .as_pointer_value();
                     func.const_cast(self.ptr_type)
@@ -327,6 +355,17 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         let function = self.module.add_function("user_main", user_main_type, None);
         let block = self.context.append_basic_block(function, "");
         self.builder.position_at_end(block);
+        let main_locs = main_exprs
+            .first()
+            .map(|e| e.locs.clone())
+            .unwrap_or_else(shiika_ast::LocationSpan::internal);
+        let di_func = self.gen_di_subprogram(
+            function,
+            backtrace::MAIN_FRAME_NAME,
+            &main_locs,
+            &[],
+            &ty::raw("Void"),
+        );
 
         // alloca
         let mut lvars = main_lvars.clone();
@@ -334,6 +373,9 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             lvars.append(&mut CollectLVarsVisitor::run(expr)?);
         }
         let lvar_ptrs = self.gen_alloca_lvars(function, &lvars)?;
+        if let Some(f) = di_func {
+            self.gen_di_variables(f, function, &[], &lvars, &lvar_ptrs)?;
+        }
 
         // CreateMain:
         let create_main_block = self.context.append_basic_block(function, "CreateMain");
@@ -347,6 +389,8 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         self.builder.position_at_end(user_main_block);
 
         let (end_block, mut ctx) = self.new_ctx(FunctionOrigin::Other, function, lvar_ptrs);
+        ctx.di_subprogram = di_func.map(|f| f.subprogram);
+        ctx.frame = Some(self.gen_frame_push(backtrace::MAIN_FRAME_NAME, &main_locs)?);
         for expr in main_exprs {
             let value = self.gen_expr(&mut ctx, expr)?;
             if value.is_none() {
@@ -357,7 +401,9 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         }
         self.builder.build_unconditional_branch(*end_block)?;
         self.builder.position_at_end(*end_block);
+        self.gen_frame_pop(&ctx)?;
         self.builder.build_return(None)?;
+        self.unset_debug_location();
 
         Ok(())
     }
@@ -584,6 +630,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             &method.lvars,
             &signature.ret_ty,
             None,
+            &backtrace::method_frame_name(&method.fullname.full_name),
         )
     }
 
@@ -599,6 +646,8 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         lvars: &HirLVars,
         ret_ty: &TermTy,
         lambda_name: Option<String>,
+        // Name shown in backtraces
+        frame_name: &str,
     ) -> Result<()> {
         let is_lambda = lambda_name.is_some();
         // LLVM function
@@ -612,6 +661,12 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
             let name = llvm_func_param_name(is_lambda, params, typarams, i);
             inkwell_set_name(param, name);
         }
+        let di_func = match body {
+            Left(SkMethodBody::Normal { exprs }) | Right(exprs) => {
+                self.gen_di_subprogram(function, frame_name, &exprs.locs, params, ret_ty)
+            }
+            _ => None,
+        };
 
         // alloca
         let mut lvars = lvars.clone();
@@ -621,6 +676,9 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         };
         lvars.append(&mut more_lvars);
         let lvar_ptrs = self.gen_alloca_lvars(function, &lvars)?;
+        if let Some(f) = di_func {
+            self.gen_di_variables(f, function, params, &lvars, &lvar_ptrs)?;
+        }
 
         // Method body
         match body {
@@ -631,6 +689,8 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
                     ret_ty,
                     exprs,
                     lvar_ptrs,
+                    frame_name,
+                    di_func.map(|f| f.subprogram),
                 )?,
                 SkMethodBody::RustLib => (),
                 SkMethodBody::New {
@@ -677,9 +737,12 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
                     ret_ty,
                     exprs,
                     lvar_ptrs,
+                    frame_name,
+                    di_func.map(|f| f.subprogram),
                 )?;
             }
         }
+        self.unset_debug_location();
         Ok(())
     }
 
@@ -688,7 +751,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         &self,
         function: inkwell::values::FunctionValue,
         lvars: &[HirLVar],
-    ) -> Result<HashMap<String, inkwell::values::PointerValue<'run>>> {
+    ) -> Result<HashMap<String, LVarPtr<'run>>> {
         if lvars.is_empty() {
             return Ok(HashMap::new());
         }
@@ -696,16 +759,20 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         let alloca_start = self.context.append_basic_block(function, "alloca");
         self.builder.build_unconditional_branch(alloca_start)?;
         self.builder.position_at_end(alloca_start);
-        for HirLVar { name, captured, .. } in lvars {
-            let obj_ty = self.llvm_type();
-            if *captured {
+        for HirLVar { name, ty, captured } in lvars {
+            let lvar_ptr = if *captured {
                 // Allocate memory on heap in case it lives longer than the method call.
-                let ptrptr = self.allocate_llvm_obj(&obj_ty).into_pointer_value();
-                lvar_ptrs.insert(name.to_string(), ptrptr);
+                let obj_ty = self.llvm_type();
+                let ptr = self.allocate_llvm_obj(&obj_ty).into_pointer_value();
+                LVarPtr { ptr, raw: false }
+            } else if let Some(raw_ty) = self.raw_llvm_type(ty) {
+                let ptr = self.builder.build_alloca(raw_ty, name)?;
+                LVarPtr { ptr, raw: true }
             } else {
-                let ptr = self.builder.build_alloca(obj_ty, name).unwrap();
-                lvar_ptrs.insert(name.to_string(), ptr);
-            }
+                let ptr = self.builder.build_alloca(self.llvm_type(), name)?;
+                LVarPtr { ptr, raw: false }
+            };
+            lvar_ptrs.insert(name.to_string(), lvar_ptr);
         }
         let alloca_end = self.context.append_basic_block(function, "alloca_End");
         self.builder.build_unconditional_branch(alloca_end)?;
@@ -714,15 +781,20 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
     }
 
     /// Generate body of llvm function of Shiika method or lambda
+    #[allow(clippy::too_many_arguments)]
     fn gen_shiika_function_body(
         &self,
         function: inkwell::values::FunctionValue<'run>,
         function_origin: FunctionOrigin<'hir>,
         ret_ty: &TermTy,
         exprs: &'hir HirExpression,
-        lvars: HashMap<String, inkwell::values::PointerValue<'run>>,
+        lvars: HashMap<String, LVarPtr<'run>>,
+        frame_name: &str,
+        subprogram: Option<inkwell::debug_info::DISubprogram<'ictx>>,
     ) -> Result<()> {
         let (end_block, mut ctx) = self.new_ctx(function_origin, function, lvars);
+        ctx.di_subprogram = subprogram;
+        ctx.frame = Some(self.gen_frame_push(frame_name, &exprs.locs)?);
         let (last_value, last_value_block) = if let Some(v) = self.gen_expr(&mut ctx, exprs)? {
             let b = self.context.append_basic_block(ctx.function, "Ret");
             self.builder.build_unconditional_branch(b)?;
@@ -735,6 +807,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         };
 
         self.builder.position_at_end(*end_block);
+        self.gen_frame_pop(&ctx)?;
 
         if ret_ty.is_never_type() {
             // `Never` does not have an instance
@@ -825,7 +898,7 @@ impl<'hir: 'ictx, 'run, 'ictx: 'run> CodeGen<'hir, 'run, 'ictx> {
         &self,
         origin: FunctionOrigin<'hir>,
         function: inkwell::values::FunctionValue<'run>,
-        lvars: HashMap<String, inkwell::values::PointerValue<'run>>,
+        lvars: HashMap<String, LVarPtr<'run>>,
     ) -> (
         Rc<inkwell::basic_block::BasicBlock<'run>>,
         CodeGenContext<'hir, 'run>,
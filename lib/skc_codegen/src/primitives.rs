@@ -0,0 +1,371 @@
+//! Compile arithmetic and comparison on `Int`, `Float` and `Bool` into
+//! LLVM instructions.
+//!
+//! Since these classes are not inheritable, a call like `a + b` (where `a`
+//! is an `Int`) always invokes `Int#+`. Instead of calling the method via
+//! vtable (which boxes the result), we unbox the operands and emit `add`
+//! directly. Nested expressions like `a * b + c` are computed without
+//! intermediate boxing.
+//!
+//! Local variables of these types are also stored unboxed (unless captured
+//! by a lambda; see `LVarPtr`), so a loop like `while i < n; i = i + 1; end`
+//! does not allocate at all. Method parameters, return values, ivars and
+//! lambda captures are still boxed; the value is boxed when it is passed to
+//! them.
+//!
+//! TODO: Pass these values unboxed across method calls too. This needs a
+//! second calling convention for methods whose parameter or return type is
+//! `Int`, `Float` or `Bool`, since they may also be called via vtable with the
+//! boxed ABI.
+//!
+//! `Int#+`, `Int#-`, `Int#*` and `Int#-@` are checked for overflow like the
+//! methods in skc_rustlib; the program is terminated with a runtime error.
+use crate::code_gen_context::*;
+use crate::utils::llvm_func_name;
+use crate::values::*;
+use crate::CodeGen;
+use anyhow::Result;
+use inkwell::intrinsics::Intrinsic;
+use inkwell::types::BasicTypeEnum;
+use inkwell::values::*;
+use inkwell::{FloatPredicate, IntPredicate};
+use shiika_ast::LocationSpan;
+use shiika_core::names::*;
+use shiika_core::ty::TermTy;
+use skc_hir::HirExpressionBase::*;
+use skc_hir::*;
+
+/// Unboxed value of `Int`, `Float` or `Bool`
+#[derive(Debug, Clone, Copy)]
+pub enum RawValue<'run> {
+    Int(IntValue<'run>),
+    Float(FloatValue<'run>),
+    Bool(IntValue<'run>),
+}
+
+impl<'run> RawValue<'run> {
+    fn into_int(self) -> IntValue<'run> {
+        match self {
+            RawValue::Int(i) => i,
+            _ => panic!("[BUG] expected Int but got {:?}", self),
+        }
+    }
+
+    fn into_float(self) -> FloatValue<'run> {
+        match self {
+            RawValue::Float(f) => f,
+            _ => panic!("[BUG] expected Float but got {:?}", self),
+        }
+    }
+
+    fn into_bool(self) -> IntValue<'run> {
+        match self {
+            RawValue::Bool(b) => b,
+            _ => panic!("[BUG] expected Bool but got {:?}", self),
+        }
+    }
+}
+
+/// Returns true if the method call can be compiled into LLVM instructions.
+pub fn is_primitive_call(
+    method_fullname: &MethodFullname,
+    receiver_expr: &HirExpression,
+    arg_exprs: &[HirExpression],
+) -> bool {
+    is_primitive_op(method_fullname)
+        && is_primitive_type(&receiver_expr.ty)
+        && arg_exprs.iter().all(|x| is_primitive_type(&x.ty))
+}
+
+fn is_primitive_op(method_fullname: &MethodFullname) -> bool {
+    matches!(
+        method_fullname.full_name.as_str(),
+        "Int#+"
+            | "Int#-"
+            | "Int#*"
+            | "Int#-@"
+            | "Int#and"
+            | "Int#or"
+            | "Int#xor"
+            | "Int#<"
+            | "Int#<="
+            | "Int#>"
+            | "Int#>="
+            | "Int#=="
+            | "Int#to_f"
+            | "Float#+"
+            | "Float#-"
+            | "Float#*"
+            | "Float#/"
+            | "Float#-@"
+            | "Float#<"
+            | "Float#<="
+            | "Float#>"
+            | "Float#>="
+            | "Float#=="
+            | "Bool#=="
+    )
+}
+
+/// Returns true if `ty` is one of the types which `RawValue` represents
+fn is_primitive_type(ty: &TermTy) -> bool {
+    !ty.is_typaram_ref() && matches!(ty.fullname.0.as_str(), "Int" | "Float" | "Bool")
+}
+
+impl<'hir, 'run, 'ictx> CodeGen<'hir, 'run, 'ictx> {
+    /// Generate a call of primitive op and box the result
+    pub fn gen_primitive_op(
+        &'run self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        expr: &'hir HirExpression,
+    ) -> Result<Option<SkObj<'run>>> {
+        Ok(self.gen_raw_expr(ctx, expr)?.map(|v| self.box_raw(v)))
+    }
+
+    /// Generate `Bool` expression as i1 (used for conditional branches)
+    pub fn gen_raw_bool(
+        &'run self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        expr: &'hir HirExpression,
+    ) -> Result<Option<IntValue<'run>>> {
+        Ok(self.gen_raw_expr(ctx, expr)?.map(|v| v.into_bool()))
+    }
+
+    /// Box the value with `Int`, `Float` or `Bool`
+    pub fn box_raw(&self, v: RawValue<'run>) -> SkObj<'run> {
+        match v {
+            RawValue::Int(i) => self.box_int(&i),
+            RawValue::Float(f) => self.box_float(&f),
+            RawValue::Bool(b) => self.box_bool(b),
+        }
+    }
+
+    /// Returns the LLVM type to store a value of `ty` unboxed, if possible
+    pub fn raw_llvm_type(&self, ty: &TermTy) -> Option<BasicTypeEnum<'ictx>> {
+        if ty.is_typaram_ref() {
+            return None;
+        }
+        match ty.fullname.0.as_str() {
+            "Int" => Some(self.i64_type.into()),
+            "Float" => Some(self.f64_type.into()),
+            "Bool" => Some(self.i1_type.into()),
+            _ => None,
+        }
+    }
+
+    /// Load the value of an unboxed lvar
+    pub fn build_raw_lvar_load(
+        &self,
+        ptr: PointerValue<'run>,
+        ty: &TermTy,
+        name: &str,
+    ) -> RawValue<'run> {
+        let raw_ty = self.raw_llvm_type(ty).unwrap();
+        let v = self.builder.build_load(raw_ty, ptr, name).unwrap();
+        match ty.fullname.0.as_str() {
+            "Int" => RawValue::Int(v.into_int_value()),
+            "Float" => RawValue::Float(v.into_float_value()),
+            _ => RawValue::Bool(v.into_int_value()),
+        }
+    }
+
+    /// Store the value of `rhs` to an unboxed lvar
+    pub fn gen_raw_lvar_assign(
+        &'run self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        name: &str,
+        rhs: &'hir HirExpression,
+    ) -> Result<Option<RawValue<'run>>> {
+        let Some(v) = self.gen_raw_expr(ctx, rhs)? else {
+            return Ok(None);
+        };
+        let ptr = ctx.lvars.get(name).unwrap().ptr;
+        match v {
+            RawValue::Int(i) => self.builder.build_store(ptr, i)?,
+            RawValue::Float(f) => self.builder.build_store(ptr, f)?,
+            RawValue::Bool(b) => self.builder.build_store(ptr, b)?,
+        };
+        Ok(Some(v))
+    }
+
+    /// Generate `expr` (whose type is `Int`, `Float` or `Bool`) without boxing
+    /// as much as possible.
+    /// Returns `None` if `expr` never returns.
+    fn gen_raw_expr(
+        &'run self,
+        ctx: &mut CodeGenContext<'hir, 'run>,
+        expr: &'hir HirExpression,
+    ) -> Result<Option<RawValue<'run>>> {
+        let v = match &expr.node {
+            HirDecimalLiteral { value } => {
+                RawValue::Int(self.i64_type.const_int(*value as u64, false))
+            }
+            HirFloatLiteral { value } => RawValue::Float(self.f64_type.const_float(*value)),
+            HirBooleanLiteral { value } => {
+                RawValue::Bool(self.i1_type.const_int(*value as u64, false))
+            }
+            HirLogicalNot { expr } if is_primitive_type(&expr.ty) => {
+                let Some(b) = self.gen_raw_bool(ctx, expr)? else {
+                    return Ok(None);
+                };
+                RawValue::Bool(self.builder.build_not(b, "not")?)
+            }
+            HirLVarRef { name } if ctx.lvars.get(name).is_some_and(|lvar| lvar.raw) => {
+                let ptr = ctx.lvars.get(name).unwrap().ptr;
+                self.build_raw_lvar_load(ptr, &expr.ty, name)
+            }
+            HirMethodCall {
+                receiver_expr,
+                method_fullname,
+                arg_exprs,
+                ..
+            } if is_primitive_call(method_fullname, receiver_expr, arg_exprs) => {
+                let Some(receiver) = self.gen_raw_expr(ctx, receiver_expr)? else {
+                    return Ok(None);
+                };
+                let arg = if let Some(arg_expr) = arg_exprs.first() {
+                    let Some(a) = self.gen_raw_expr(ctx, arg_expr)? else {
+                        return Ok(None);
+                    };
+                    Some(a)
+                } else {
+                    None
+                };
+                self.build_primitive_op(ctx, method_fullname, receiver, arg, &expr.locs)?
+            }
+            _ => {
+                let Some(obj) = self.gen_expr(ctx, expr)? else {
+                    return Ok(None);
+                };
+                match expr.ty.fullname.0.as_str() {
+                    "Int" => RawValue::Int(self.unbox_int(obj)),
+                    "Float" => RawValue::Float(self.unbox_float(obj)),
+                    "Bool" => RawValue::Bool(self.unbox_bool(obj)),
+                    _ => panic!("[BUG] not a primitive type: {}", expr.ty),
+                }
+            }
+        };
+        Ok(Some(v))
+    }
+
+    /// Emit the LLVM instruction(s) for the method
+    fn build_primitive_op(
+        &self,
+        ctx: &CodeGenContext<'hir, 'run>,
+        method_fullname: &MethodFullname,
+        receiver: RawValue<'run>,
+        arg: Option<RawValue<'run>>,
+        locs: &LocationSpan,
+    ) -> Result<RawValue<'run>> {
+        let b = self.builder;
+        let v = match method_fullname.full_name.as_str() {
+            "Int#-@" => {
+                let zero = self.i64_type.const_zero();
+                let l = receiver.into_int();
+                RawValue::Int(self.build_checked_int_op(ctx, "ssub", zero, l, "Int#-@", locs)?)
+            }
+            "Int#to_f" => RawValue::Float(b.build_signed_int_to_float(
+                receiver.into_int(),
+                self.f64_type,
+                "to_f",
+            )?),
+            "Float#-@" => RawValue::Float(b.build_float_neg(receiver.into_float(), "neg")?),
+            "Bool#==" => {
+                let (l, r) = (receiver.into_bool(), arg.unwrap().into_bool());
+                RawValue::Bool(b.build_int_compare(IntPredicate::EQ, l, r, "eq")?)
+            }
+            name if name.starts_with("Int#") => {
+                let (l, r) = (receiver.into_int(), arg.unwrap().into_int());
+                match name {
+                    "Int#+" | "Int#-" | "Int#*" => {
+                        let op = match name {
+                            "Int#+" => "sadd",
+                            "Int#-" => "ssub",
+                            _ => "smul",
+                        };
+                        RawValue::Int(self.build_checked_int_op(ctx, op, l, r, name, locs)?)
+                    }
+                    "Int#and" => RawValue::Int(b.build_and(l, r, "and")?),
+                    "Int#or" => RawValue::Int(b.build_or(l, r, "or")?),
+                    "Int#xor" => RawValue::Int(b.build_xor(l, r, "xor")?),
+                    _ => {
+                        let pred = match name {
+                            "Int#<" => IntPredicate::SLT,
+                            "Int#<=" => IntPredicate::SLE,
+                            "Int#>" => IntPredicate::SGT,
+                            "Int#>=" => IntPredicate::SGE,
+                            "Int#==" => IntPredicate::EQ,
+                            _ => panic!("[BUG] unknown primitive op: {}", name),
+                        };
+                        RawValue::Bool(b.build_int_compare(pred, l, r, "cmp")?)
+                    }
+                }
+            }
+            name if name.starts_with("Float#") => {
+                let (l, r) = (receiver.into_float(), arg.unwrap().into_float());
+                match name {
+                    "Float#+" => RawValue::Float(b.build_float_add(l, r, "add")?),
+                    "Float#-" => RawValue::Float(b.build_float_sub(l, r, "sub")?),
+                    "Float#*" => RawValue::Float(b.build_float_mul(l, r, "mul")?),
+                    "Float#/" => RawValue::Float(b.build_float_div(l, r, "div")?),
+                    _ => {
+                        let pred = match name {
+                            "Float#<" => FloatPredicate::OLT,
+                            "Float#<=" => FloatPredicate::OLE,
+                            "Float#>" => FloatPredicate::OGT,
+                            "Float#>=" => FloatPredicate::OGE,
+                            "Float#==" => FloatPredicate::OEQ,
+                            _ => panic!("[BUG] unknown primitive op: {}", name),
+                        };
+                        RawValue::Bool(b.build_float_compare(pred, l, r, "cmp")?)
+                    }
+                }
+            }
+            name => panic!("[BUG] unknown primitive op: {}", name),
+        };
+        Ok(v)
+    }
+
+    /// Emit `llvm.{op}.with.overflow.i64` and a branch which terminates the
+    /// program when the result overflowed.
+    fn build_checked_int_op(
+        &self,
+        ctx: &CodeGenContext<'hir, 'run>,
+        op: &str,
+        l: IntValue<'run>,
+        r: IntValue<'run>,
+        method_name: &str,
+        locs: &LocationSpan,
+    ) -> Result<IntValue<'run>> {
+        let b = self.builder;
+        let intrinsic = Intrinsic::find(&format!("llvm.{}.with.overflow", op))
+            .unwrap_or_else(|| panic!("[BUG] intrinsic for {} not found", op));
+        let func = intrinsic
+            .get_declaration(&self.module, &[self.i64_type.into()])
+            .unwrap();
+        let result = b
+            .build_direct_call(func, &[l.into(), r.into()], op)?
+            .try_as_basic_value()
+            .unwrap_basic()
+            .into_struct_value();
+        let value = b.build_extract_value(result, 0, "value")?.into_int_value();
+        let overflow = b
+            .build_extract_value(result, 1, "overflow")?
+            .into_int_value();
+
+        let overflow_block = self.context.append_basic_block(ctx.function, "overflow");
+        let ok_block = self.context.append_basic_block(ctx.function, "no_overflow");
+        b.build_conditional_branch(overflow, overflow_block, ok_block)?;
+        b.position_at_end(overflow_block);
+        self.gen_set_line(ctx, locs)?;
+        let name = b.build_global_string_ptr(method_name, "method_name")?;
+        self.call_void_llvm_func(
+            &llvm_func_name("shiika_int_overflow"),
+            &[name.as_pointer_value().into()],
+            "",
+        );
+        b.build_unreachable()?;
+        b.position_at_end(ok_block);
+        Ok(value)
+    }
+}
@@ -1,16 +1,42 @@
+mod report;
 mod report_builder;
 pub use ariadne::Label;
-use ariadne::{Report, ReportBuilder, ReportKind, Source};
+use ariadne::{ReportBuilder, ReportKind, Source};
+pub use report::{Report, ReportLocation};
 use report_builder::Builder;
 use shiika_ast::LocationSpan;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs;
 use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 type AriadneSpan<'a> = (&'a String, Range<usize>);
 
+thread_local! {
+    /// Source texts which should be used instead of the files on the disk
+    static SOURCE_OVERRIDES: RefCell<HashMap<PathBuf, String>> = RefCell::new(HashMap::new());
+}
+
+/// Use `content` instead of the file on the disk when rendering reports about
+/// `path`. (The language server uses this for buffers not saved yet.)
+pub fn set_source_override(path: &Path, content: &str) {
+    SOURCE_OVERRIDES.with(|m| {
+        m.borrow_mut()
+            .insert(path.to_path_buf(), content.to_string());
+    });
+}
+
+/// Read the source text of `path`
+fn read_source(path: &Path) -> String {
+    SOURCE_OVERRIDES
+        .with(|m| m.borrow().get(path).cloned())
+        .unwrap_or_else(|| fs::read_to_string(path).unwrap_or_default())
+}
+
 /// Helper for building report with ariadne crate.
 /// (TODO: migrate to `report_builder`)
-pub fn build_report<F>(main_msg: String, locs: &LocationSpan, f: F) -> String
+pub fn build_report<F>(main_msg: String, locs: &LocationSpan, f: F) -> Report
 where
     F: for<'b> FnOnce(
         ReportBuilder<'b, AriadneSpan<'b>>,
@@ -29,12 +55,15 @@ where
         // ariadne::Span equivalent to `locs`
         let locs_span = (&id, begin.pos..end.pos);
 
-        let src = Source::from(fs::read_to_string(&**filepath).unwrap_or_default());
-        let report = f(Report::build(ReportKind::Error, &id, begin.pos), locs_span)
-            .with_message(main_msg.clone())
-            .finish();
+        let src = Source::from(read_source(filepath));
+        let report = f(
+            ariadne::Report::build(ReportKind::Error, &id, begin.pos),
+            locs_span,
+        )
+        .with_message(main_msg.clone())
+        .finish();
 
-        match std::panic::catch_unwind(|| {
+        let rendered = match std::panic::catch_unwind(|| {
             let mut rendered = vec![];
             report.write((&id, src), &mut rendered).unwrap();
             String::from_utf8_lossy(&rendered).to_string()
@@ -43,12 +72,18 @@ where
             Err(e) => {
                 println!("[BUG] ariadne crate crashed!");
                 dbg!(&e);
-                main_msg
+                main_msg.clone()
             }
+        };
+        Report {
+            msg: main_msg,
+            location: ReportLocation::new(locs),
+            is_warning: false,
+            rendered,
         }
     } else {
         // No location information available
-        main_msg
+        Report::plain(main_msg)
     }
 }
 
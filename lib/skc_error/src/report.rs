@@ -0,0 +1,79 @@
+use shiika_ast::{Location, LocationSpan};
+use std::fmt;
+use std::path::PathBuf;
+
+/// An error (or warning) report with the location it is about
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// The main message (without the location)
+    pub msg: String,
+    /// None if the location is unknown
+    pub location: Option<ReportLocation>,
+    pub is_warning: bool,
+    /// The report rendered by ariadne (or just `msg` if the location is
+    /// unknown)
+    pub rendered: String,
+}
+
+/// Same as `LocationSpan::Just` but can be sent between threads (errors
+/// are passed around as `anyhow::Error`, which must be `Send`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportLocation {
+    pub path: PathBuf,
+    pub begin: Location,
+    pub end: Location,
+}
+
+impl Report {
+    /// Create a report without location information
+    pub fn plain(msg: impl Into<String>) -> Report {
+        let msg = msg.into();
+        Report {
+            rendered: msg.clone(),
+            msg,
+            location: None,
+            is_warning: false,
+        }
+    }
+}
+
+impl ReportLocation {
+    pub fn new(locs: &LocationSpan) -> Option<ReportLocation> {
+        match locs {
+            LocationSpan::Just {
+                filepath,
+                begin,
+                end,
+            } => Some(ReportLocation {
+                path: filepath.to_path_buf(),
+                begin: begin.clone(),
+                end: end.clone(),
+            }),
+            LocationSpan::Empty => None,
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.rendered)
+    }
+}
+
+impl From<String> for Report {
+    fn from(msg: String) -> Report {
+        Report::plain(msg)
+    }
+}
+
+impl From<&String> for Report {
+    fn from(msg: &String) -> Report {
+        Report::plain(msg.as_str())
+    }
+}
+
+impl From<&str> for Report {
+    fn from(msg: &str) -> Report {
+        Report::plain(msg)
+    }
+}
@@ -1,26 +1,34 @@
-use ariadne::{Label, Report, ReportBuilder, ReportKind, Source};
+use crate::{Report, ReportLocation};
+use ariadne::{Label, ReportBuilder, ReportKind, Source};
 use shiika_ast::LocationSpan;
-use std::fs;
 
 type AriadneSpan<'a> = (&'a String, std::ops::Range<usize>);
 
 pub struct Builder {
     annotations: Vec<(LocationSpan, String)>,
+    is_warning: bool,
 }
 
 impl Builder {
     pub fn new() -> Builder {
         Builder {
             annotations: vec![],
+            is_warning: false,
         }
     }
 
+    /// Make the report a warning instead of an error
+    pub fn warning(mut self) -> Self {
+        self.is_warning = true;
+        self
+    }
+
     pub fn annotate(mut self, locs: LocationSpan, msg: String) -> Self {
         self.annotations.push((locs, msg));
         self
     }
 
-    pub fn build(self, main_msg: String, main_locs: &LocationSpan) -> String {
+    pub fn build(self, main_msg: String, main_locs: &LocationSpan) -> Report {
         if let LocationSpan::Just {
             filepath, begin, ..
         } = main_locs
@@ -29,9 +37,13 @@ impl Builder {
             // ariadne 0.1.5 needs Id: Display (zesterer/ariadne#12)
             let id = format!("{}", filepath.display());
 
-            let src = Source::from(fs::read_to_string(&**filepath).unwrap_or_default());
-            let mut r: ReportBuilder<AriadneSpan> =
-                Report::build(ReportKind::Error, &id, begin.pos);
+            let src = Source::from(crate::read_source(filepath));
+            let kind = if self.is_warning {
+                ReportKind::Warning
+            } else {
+                ReportKind::Error
+            };
+            let mut r: ReportBuilder<AriadneSpan> = ariadne::Report::build(kind, &id, begin.pos);
             for (locs, msg) in self.annotations {
                 let LocationSpan::Just { begin, end, .. } = locs else {
                     panic!("got LocationSpan::None");
@@ -41,7 +53,7 @@ impl Builder {
             }
             let report = r.with_message(main_msg.clone()).finish();
 
-            match std::panic::catch_unwind(|| {
+            let rendered = match std::panic::catch_unwind(|| {
                 let mut rendered = vec![];
                 report.write((&id, src), &mut rendered).unwrap();
                 String::from_utf8_lossy(&rendered).to_string()
@@ -50,12 +62,21 @@ impl Builder {
                 Err(e) => {
                     println!("[BUG] ariadne crate crashed!");
                     dbg!(&e);
-                    main_msg
+                    main_msg.clone()
                 }
+            };
+            Report {
+                msg: main_msg,
+                location: ReportLocation::new(main_locs),
+                is_warning: self.is_warning,
+                rendered,
             }
         } else {
             // No location information available
-            main_msg
+            Report {
+                is_warning: self.is_warning,
+                ..Report::plain(main_msg)
+            }
         }
     }
 }
@@ -15,18 +15,24 @@ pub use crate::supertype::Supertype;
 use serde::{Deserialize, Serialize};
 use shiika_ast::LocationSpan;
 use shiika_core::{names::*, ty, ty::*};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug)]
 pub struct Hir {
     pub sk_types: SkTypes,
     pub sk_methods: SkMethods,
     pub constants: HashMap<ConstFullname, TermTy>,
+    /// Constants defined with `private`. They are not exported.
+    /// (Private types are not included here because they may appear in
+    /// the signatures of public methods.)
+    pub private_consts: HashSet<ConstFullname>,
     pub str_literals: Vec<String>,
     pub const_inits: Vec<HirExpression>,
     pub main_exprs: Vec<HirExpression>,
     /// Local variables in `main_exprs`
     pub main_lvars: HirLVars,
+    /// Warnings found while creating the hir
+    pub warnings: Vec<skc_error::Report>,
 }
 
 impl Hir {
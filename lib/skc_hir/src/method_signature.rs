@@ -20,6 +20,9 @@ pub struct MethodSignature {
     /// True if this method is written in Rust and thus requires
     /// `declare` in LLVM IR.
     pub is_rust: bool,
+    /// True if this method is declared with `private`.
+    /// Private methods are not included in the exports of a library.
+    pub is_private: bool,
 }
 
 impl fmt::Display for MethodSignature {
@@ -77,6 +80,7 @@ impl MethodSignature {
             asyncness: self.asyncness.clone(),
             is_virtual: self.is_virtual,
             is_rust: self.is_rust,
+            is_private: self.is_private,
         }
     }
 
@@ -180,6 +184,7 @@ impl MethodSignature {
                 asyncness,
                 is_virtual: is_virtual.is_some(),
                 is_rust: is_rust.is_some(),
+                is_private: false,
             },
         ))
     }
@@ -314,6 +319,7 @@ pub fn signature_of_new(
         asyncness: Asyncness::Unknown,
         is_virtual: false,
         is_rust: false,
+        is_private: false,
     }
 }
 
@@ -330,6 +336,7 @@ pub fn signature_of_enum_initialize(
         asyncness: Asyncness::Unknown,
         is_virtual: false,
         is_rust: false,
+        is_private: false,
     }
 }
 
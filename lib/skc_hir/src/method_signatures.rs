@@ -43,6 +43,11 @@ impl MethodSignatures {
         self.0.insert(key, (sig, n));
     }
 
+    /// Remove the signatures for which `f` returns false.
+    pub fn retain(&mut self, f: impl Fn(&MethodSignature) -> bool) {
+        self.0.retain(|_, (sig, _)| f(sig));
+    }
+
     /// Destructively append `other` to `self`.
     pub fn append(&mut self, other: MethodSignatures) {
         other
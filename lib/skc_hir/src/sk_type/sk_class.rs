@@ -21,6 +21,9 @@ pub struct SkClass {
     pub const_is_obj: bool,
     /// Witness table
     pub wtable: WTable,
+    /// Cases of the enum (None if this class is not an enum)
+    #[serde(default)]
+    pub enum_cases: Option<Vec<ClassFullname>>,
 }
 
 impl SkClass {
@@ -33,6 +36,7 @@ impl SkClass {
             inheritable: Default::default(),
             const_is_obj: false,
             wtable: Default::default(),
+            enum_cases: None,
         }
     }
 
@@ -45,6 +49,7 @@ impl SkClass {
             inheritable: Default::default(),
             const_is_obj: false,
             wtable: Default::default(),
+            enum_cases: None,
         }
     }
 
@@ -68,6 +68,19 @@ impl SkTypes {
         sk_type.base_mut().method_sigs.insert(method_sig);
     }
 
+    /// Returns a copy of self without the private methods of classes.
+    /// (Private methods of modules are kept because the witness tables of
+    /// the classes which include the module need them.)
+    pub fn without_private_methods(&self) -> SkTypes {
+        let mut sk_types = self.clone();
+        for sk_type in sk_types.types.values_mut() {
+            if let SkType::Class(sk_class) = sk_type {
+                sk_class.base.method_sigs.retain(|sig| !sig.is_private);
+            }
+        }
+        sk_types
+    }
+
     /// Merges(copies) `other` into `self`.
     pub fn merge(&mut self, other: &SkTypes) {
         for (name, sk_type) in &other.types {
@@ -0,0 +1,640 @@
+use shiika_ast::{Definition, LocationSpan};
+use shiika_parser::{loader, SourceFile};
+use skc_error::{Report, ReportLocation};
+use skc_hir::visitor::{walk_expr, HirVisitor};
+use skc_hir::HirExpressionBase::*;
+use skc_hir::{Hir, HirExpression, SkMethodBody};
+use skc_mir::LibraryExports;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::*;
+
+/// A definition of a class, module, method or constant in a source file
+pub struct DefEntry {
+    /// eg. `A::B`, `A::B#foo`, `Meta:A::B#bar`, `::A::B::C`
+    pub key: String,
+    /// Name shown to the user (eg. `foo`)
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Location of the name
+    pub locs: LocationSpan,
+}
+
+/// The result of analyzing a source file
+pub struct Analysis {
+    pub path: PathBuf,
+    pub text: String,
+    pub diagnostics: Vec<Diagnostic>,
+    pub defs: Vec<DefEntry>,
+    /// None if failed to create hir
+    pub hir: Option<Hir>,
+}
+
+/// Something an expression refers to
+#[derive(Debug, PartialEq)]
+enum Target {
+    Method(String),
+    Const(String),
+    /// A local variable in the `root`th method body (see `roots`)
+    LVar {
+        root: usize,
+        name: String,
+    },
+    /// A method parameter in the `root`th method body
+    Arg {
+        root: usize,
+        idx: usize,
+    },
+}
+
+impl Analysis {
+    /// Parse `text` and convert it into hir, collecting the errors as diagnostics.
+    /// `imports` is None if the exports of the core library are not available.
+    pub fn new(path: PathBuf, text: String, imports: Option<&LibraryExports>) -> Analysis {
+        let mut analysis = Analysis {
+            path,
+            text,
+            diagnostics: vec![],
+            defs: vec![],
+            hir: None,
+        };
+        skc_error::set_source_override(&analysis.path, &analysis.text);
+        let file = SourceFile::new(analysis.path.clone(), analysis.text.clone());
+        let ast = match loader::load_source(file, &[]) {
+            Ok(ast) => ast,
+            Err(shiika_parser::Error::LexError { msg, location }) => {
+                let loc = location.location();
+                let pos = Position::new(loc.line as u32, loc.col as u32);
+                analysis.add_diagnostic(pos, msg);
+                return analysis;
+            }
+            Err(shiika_parser::Error::ParseError(report))
+            | Err(shiika_parser::Error::LoadError(report)) => {
+                analysis.add_report(&report);
+                return analysis;
+            }
+        };
+        let mut namespace = vec![];
+        for def in ast.defs() {
+            index_def(&mut analysis.defs, &mut namespace, def);
+        }
+
+        let Some(imports) = imports else {
+            return analysis;
+        };
+        match skc_ast2hir::make_hir(ast, imports) {
+            Ok(hir) => {
+                for warning in &hir.warnings {
+                    analysis.add_report(warning);
+                }
+                analysis.hir = Some(hir);
+            }
+            Err(e) => match e.downcast_ref::<skc_ast2hir::error::Error>() {
+                Some(err) => analysis.add_report(err.report()),
+                None => analysis.add_diagnostic(Position::new(0, 0), e.to_string()),
+            },
+        }
+        analysis
+    }
+
+    /// Returns the type of the expression at `pos`
+    pub fn hover(&self, pos: Position) -> Option<Hover> {
+        let (expr, _) = self.find_expr(pos)?;
+        Some(Hover {
+            contents: HoverContents::Scalar(MarkedString::LanguageString(LanguageString {
+                language: "shiika".to_string(),
+                value: expr.ty.to_string(),
+            })),
+            range: self.range(&expr.locs),
+        })
+    }
+
+    /// Returns where the thing at `pos` is defined
+    pub fn definition(&self, pos: Position) -> Option<Location> {
+        let (expr, root) = self.find_expr(pos)?;
+        let target = target_of(expr, root)?;
+        let locs = self.definition_of(&target, pos)?;
+        self.location(&locs)
+    }
+
+    /// Returns the locations where the thing at `pos` is referred
+    pub fn references(&self, pos: Position) -> Vec<Location> {
+        let Some(target) = self
+            .find_expr(pos)
+            .and_then(|(expr, root)| target_of(expr, root))
+        else {
+            return vec![];
+        };
+        let mut locations = vec![];
+        if let Some(loc) = self
+            .definition_of(&target, pos)
+            .and_then(|locs| self.location(&locs))
+        {
+            locations.push(loc);
+        }
+        for (i, (_, exprs)) in self.roots().iter().enumerate() {
+            for expr in collect_exprs(exprs) {
+                if matches!(&expr.node, HirLVarDecl { .. }) {
+                    // Already added as the definition
+                    continue;
+                }
+                if target_of(expr, i).as_ref() == Some(&target) {
+                    if let Some(loc) = self.location(&expr.locs) {
+                        locations.push(loc);
+                    }
+                }
+            }
+        }
+        locations
+    }
+
+    /// Returns the definitions whose name contains `query`
+    pub fn symbols(&self, query: &str) -> Vec<SymbolInformation> {
+        let query = query.to_lowercase();
+        self.defs
+            .iter()
+            .filter(|d| d.name.to_lowercase().contains(&query))
+            // Exclude the definitions in the imported files
+            .filter(|d| self.range(&d.locs).is_some())
+            .filter_map(|d| {
+                #[allow(deprecated)]
+                Some(SymbolInformation {
+                    name: d.name.clone(),
+                    kind: d.kind,
+                    tags: None,
+                    deprecated: None,
+                    location: self.location(&d.locs)?,
+                    container_name: Some(d.key.clone()),
+                })
+            })
+            .collect()
+    }
+
+    fn definition_of(&self, target: &Target, pos: Position) -> Option<LocationSpan> {
+        match target {
+            Target::Method(key) => self.find_def(key).or_else(|| {
+                // `Foo.new` is defined by `class Foo` or `Foo#initialize`
+                let cls = key.strip_prefix("Meta:")?.strip_suffix("#new")?;
+                self.find_def(&format!("{}#initialize", cls))
+                    .or_else(|| self.find_def(cls))
+            }),
+            Target::Const(key) => self
+                .find_def(key)
+                // Class constants (eg. `::Foo` for `class Foo`)
+                .or_else(|| self.find_def(key.strip_prefix("::")?)),
+            Target::Arg { root, .. } => {
+                let (method, _) = &self.roots()[*root];
+                self.find_def(method.as_ref()?)
+            }
+            Target::LVar { root, name } => {
+                let (_, exprs) = &self.roots()[*root];
+                let decls = collect_exprs(exprs)
+                    .into_iter()
+                    .filter(|e| matches!(&e.node, HirLVarDecl { name: n, .. } if n == name))
+                    .collect::<Vec<_>>();
+                // Prefer the nearest one before `pos`
+                decls
+                    .iter()
+                    .rev()
+                    .find(|e| begin_of(&e.locs).is_some_and(|b| b <= pos))
+                    .or(decls.first())
+                    .map(|e| e.locs.clone())
+            }
+        }
+    }
+
+    fn find_def(&self, key: &str) -> Option<LocationSpan> {
+        self.defs
+            .iter()
+            .find(|d| d.key == key)
+            .map(|d| d.locs.clone())
+    }
+
+    /// Returns the innermost expression at `pos` and the index of its root
+    fn find_expr(&self, pos: Position) -> Option<(&HirExpression, usize)> {
+        let mut finder = ExprFinder {
+            path: &self.path,
+            pos,
+            found: None,
+        };
+        let mut found_root = 0;
+        for (i, (_, exprs)) in self.roots().iter().enumerate() {
+            let before = finder.found.map(|e| e as *const _);
+            for expr in exprs {
+                let _ = walk_expr(&mut finder, expr);
+            }
+            if finder.found.map(|e| e as *const _) != before {
+                found_root = i;
+            }
+        }
+        finder.found.map(|e| (e, found_root))
+    }
+
+    /// Returns the scopes of local variables, ie. method bodies (with the
+    /// method name), constant initializers and the toplevel.
+    fn roots(&self) -> Vec<(Option<String>, Vec<&HirExpression>)> {
+        let Some(hir) = &self.hir else {
+            return vec![];
+        };
+        let mut roots = vec![];
+        for methods in hir.sk_methods.values() {
+            for method in methods {
+                if let SkMethodBody::Normal { exprs } = &method.body {
+                    roots.push((Some(method.fullname.full_name.clone()), vec![exprs]));
+                }
+            }
+        }
+        for expr in &hir.const_inits {
+            roots.push((None, vec![expr]));
+        }
+        // Make the indices stable
+        roots.sort_by_key(|(name, e)| (begin_of(&e[0].locs), name.clone()));
+        roots.push((None, hir.main_exprs.iter().collect()));
+        roots
+    }
+
+    fn location(&self, locs: &LocationSpan) -> Option<Location> {
+        let LocationSpan::Just {
+            filepath,
+            begin,
+            end,
+        } = locs
+        else {
+            return None;
+        };
+        Some(Location {
+            uri: Url::from_file_path(filepath.as_ref()).ok()?,
+            range: Range {
+                start: Position::new(begin.line as u32, begin.col as u32),
+                end: Position::new(end.line as u32, end.col as u32),
+            },
+        })
+    }
+
+    fn range(&self, locs: &LocationSpan) -> Option<Range> {
+        match locs {
+            LocationSpan::Just {
+                filepath,
+                begin,
+                end,
+            } if **filepath == self.path => Some(Range {
+                start: Position::new(begin.line as u32, begin.col as u32),
+                end: Position::new(end.line as u32, end.col as u32),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Add a diagnostic from an error (or warning) report
+    fn add_report(&mut self, report: &Report) {
+        let severity = if report.is_warning {
+            DiagnosticSeverity::WARNING
+        } else {
+            DiagnosticSeverity::ERROR
+        };
+        match &report.location {
+            // The error may be in an imported file
+            Some(location) if location.path == self.path => {
+                self.diagnostics.push(Diagnostic {
+                    range: report_range(location),
+                    severity: Some(severity),
+                    source: Some("shiika".to_string()),
+                    message: report.msg.clone(),
+                    ..Default::default()
+                });
+            }
+            _ => self.push_diagnostic(Position::new(0, 0), report.msg.clone(), severity),
+        }
+    }
+
+    fn add_diagnostic(&mut self, start: Position, message: String) {
+        self.push_diagnostic(start, message, DiagnosticSeverity::ERROR);
+    }
+
+    fn push_diagnostic(&mut self, start: Position, message: String, severity: DiagnosticSeverity) {
+        // Underline the word at `start`
+        let len = self
+            .text
+            .lines()
+            .nth(start.line as usize)
+            .map(|line| {
+                line.chars()
+                    .skip(start.character as usize)
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .count()
+            })
+            .unwrap_or_default()
+            .max(1);
+        let end = Position::new(start.line, start.character + len as u32);
+        self.diagnostics.push(Diagnostic {
+            range: Range { start, end },
+            severity: Some(severity),
+            source: Some("shiika".to_string()),
+            message,
+            ..Default::default()
+        });
+    }
+}
+
+/// Register `def` (and its inner definitions) to `defs`
+fn index_def(defs: &mut Vec<DefEntry>, namespace: &mut Vec<String>, def: &Definition) {
+    let prefix = namespace.join("::");
+    let type_name = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}::{}", prefix, name)
+        }
+    };
+    let mut add = |key: String, name: &str, kind, locs: &LocationSpan| {
+        defs.push(DefEntry {
+            key,
+            name: name.to_string(),
+            kind,
+            locs: locs.clone(),
+        })
+    };
+    match def {
+        Definition::ClassDefinition {
+            name,
+            defs: d,
+            locs,
+            ..
+        }
+        | Definition::EnumDefinition {
+            name,
+            defs: d,
+            locs,
+            ..
+        } => {
+            let fullname = type_name(&name.0);
+            add(fullname.clone(), &name.0, SymbolKind::CLASS, locs);
+            if let Definition::EnumDefinition { cases, .. } = def {
+                for case in cases {
+                    let case_name = format!("{}::{}", fullname, case.name.0);
+                    add(case_name, &case.name.0, SymbolKind::ENUM_MEMBER, &case.locs);
+                }
+            }
+            namespace.push(name.0.clone());
+            for inner in d {
+                index_def(defs, namespace, inner);
+            }
+            namespace.pop();
+        }
+        Definition::ModuleDefinition {
+            name,
+            defs: d,
+            locs,
+            ..
+        } => {
+            let fullname = type_name(&name.0);
+            add(fullname.clone(), &name.0, SymbolKind::MODULE, locs);
+            namespace.push(name.0.clone());
+            for inner in d {
+                index_def(defs, namespace, inner);
+            }
+            namespace.pop();
+        }
+        Definition::InstanceMethodDefinition { sig, .. }
+        | Definition::InitializerDefinition(shiika_ast::InitializerDefinition { sig, .. })
+        | Definition::MethodRequirementDefinition { sig } => {
+            let key = format!("{}#{}", prefix, sig.name.0);
+            add(key, &sig.name.0, SymbolKind::METHOD, &sig.locs);
+        }
+        Definition::ClassMethodDefinition { sig, .. }
+        | Definition::ClassInitializerDefinition(shiika_ast::InitializerDefinition {
+            sig, ..
+        }) => {
+            let key = format!("Meta:{}#{}", prefix, sig.name.0);
+            add(key, &sig.name.0, SymbolKind::METHOD, &sig.locs);
+        }
+        Definition::ConstDefinition { name, locs, .. } => {
+            let key = format!("::{}", type_name(name));
+            add(key, name, SymbolKind::CONSTANT, locs);
+        }
+    }
+}
+
+/// Returns what `expr` refers to
+fn target_of(expr: &HirExpression, root: usize) -> Option<Target> {
+    match &expr.node {
+        HirMethodCall {
+            method_fullname, ..
+        } => Some(Target::Method(method_fullname.full_name.clone())),
+        HirModuleMethodCall {
+            module_fullname,
+            method_name,
+            ..
+        } => Some(Target::Method(format!(
+            "{}#{}",
+            module_fullname.0, method_name.0
+        ))),
+        HirConstRef { fullname } => Some(Target::Const(fullname.0.clone())),
+        HirLVarRef { name } | HirLVarDecl { name, .. } | HirLVarAssign { name, .. } => {
+            Some(Target::LVar {
+                root,
+                name: name.clone(),
+            })
+        }
+        HirArgRef {
+            idx,
+            is_lambda: false,
+        } => Some(Target::Arg { root, idx: *idx }),
+        _ => None,
+    }
+}
+
+fn begin_of(locs: &LocationSpan) -> Option<Position> {
+    match locs {
+        LocationSpan::Just { begin, .. } => {
+            Some(Position::new(begin.line as u32, begin.col as u32))
+        }
+        _ => None,
+    }
+}
+
+/// Find the innermost expression at `pos`
+struct ExprFinder<'a, 'hir> {
+    path: &'a Path,
+    pos: Position,
+    found: Option<&'hir HirExpression>,
+}
+
+impl<'a, 'hir> HirVisitor<'hir> for ExprFinder<'a, 'hir> {
+    fn visit_expr(&mut self, expr: &'hir HirExpression) -> anyhow::Result<()> {
+        let LocationSpan::Just {
+            filepath,
+            begin,
+            end,
+        } = &expr.locs
+        else {
+            return Ok(());
+        };
+        let b = Position::new(begin.line as u32, begin.col as u32);
+        let e = Position::new(end.line as u32, end.col as u32);
+        if **filepath != self.path || self.pos < b || e <= self.pos {
+            return Ok(());
+        }
+        let size = end.pos - begin.pos;
+        let smaller = match self.found.map(|f| &f.locs) {
+            Some(LocationSpan::Just { begin, end, .. }) => size <= end.pos - begin.pos,
+            _ => true,
+        };
+        if smaller {
+            self.found = Some(expr);
+        }
+        Ok(())
+    }
+}
+
+/// Returns `exprs` and all the expressions in them
+fn collect_exprs<'hir>(exprs: &[&'hir HirExpression]) -> Vec<&'hir HirExpression> {
+    let mut c = Collector::default();
+    for expr in exprs {
+        let _ = walk_expr(&mut c, expr);
+    }
+    c.exprs
+}
+
+/// Collect all the expressions
+#[derive(Default)]
+struct Collector<'hir> {
+    exprs: Vec<&'hir HirExpression>,
+}
+
+impl<'hir> HirVisitor<'hir> for Collector<'hir> {
+    fn visit_expr(&mut self, expr: &'hir HirExpression) -> anyhow::Result<()> {
+        self.exprs.push(expr);
+        Ok(())
+    }
+}
+
+fn report_range(location: &ReportLocation) -> Range {
+    let ReportLocation { begin, end, .. } = location;
+    Range {
+        start: Position::new(begin.line as u32, begin.col as u32),
+        end: Position::new(end.line as u32, end.col as u32),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SRC: &str = "\
+class A
+  def self.foo(n: Int) -> Int
+    let x = n + 1
+    x * x
+  end
+end
+p A.foo(1)
+";
+
+    fn core_exports() -> LibraryExports {
+        // skc_corelib reads lib/skc_rustlib/provided_methods.json5
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+        std::env::set_current_dir(&root).unwrap();
+        let ast = loader::load(Path::new("builtin/index.sk"), &[]).unwrap();
+        let hir = skc_ast2hir::make_corelib_hir(ast, skc_corelib::create()).unwrap();
+        LibraryExports::new(&skc_mir::build(hir, Default::default()))
+    }
+
+    fn analyze(text: &str) -> Analysis {
+        let path = PathBuf::from("/test.sk");
+        Analysis::new(path, text.to_string(), Some(&core_exports()))
+    }
+
+    fn range(l1: u32, c1: u32, l2: u32, c2: u32) -> Range {
+        Range {
+            start: Position::new(l1, c1),
+            end: Position::new(l2, c2),
+        }
+    }
+
+    #[test]
+    fn test_no_diagnostics() {
+        let a = analyze(SRC);
+        assert!(a.diagnostics.is_empty());
+        assert!(a.hir.is_some());
+    }
+
+    #[test]
+    fn test_type_error() {
+        let a = analyze("let a = 1\na.foo\n");
+        assert_eq!(a.diagnostics.len(), 1);
+        let d = &a.diagnostics[0];
+        assert_eq!(d.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(
+            d.message,
+            "method MethodFirstname(\"foo\") not found on TypeFullname(\"Int\")"
+        );
+        assert_eq!(d.range, range(1, 1, 1, 5));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let a = analyze("p 1\np(;)\n");
+        assert_eq!(a.diagnostics.len(), 1);
+        assert_eq!(a.diagnostics[0].message, "unexpected token: Semicolon");
+        assert_eq!(a.diagnostics[0].range, range(1, 2, 1, 3));
+    }
+
+    #[test]
+    fn test_warning() {
+        let a = analyze("match 1\nwhen n then n\nelse 2\nend\n");
+        assert_eq!(a.diagnostics.len(), 1);
+        let d = &a.diagnostics[0];
+        assert_eq!(d.severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(d.message, "unreachable match clause");
+        assert_eq!(d.range, range(2, 0, 2, 4));
+    }
+
+    #[test]
+    fn test_hover() {
+        let a = analyze(SRC);
+        // `x` of `x * x`
+        let hover = a.hover(Position::new(3, 4)).unwrap();
+        let HoverContents::Scalar(MarkedString::LanguageString(s)) = hover.contents else {
+            panic!("unexpected hover: {:?}", hover.contents);
+        };
+        assert_eq!(s.value, "Int");
+        assert_eq!(hover.range, Some(range(3, 4, 3, 5)));
+    }
+
+    #[test]
+    fn test_hover_after_error() {
+        // Edit `x * x` into `x * y`
+        let a = analyze(&SRC.replace("x * x", "x * y"));
+        assert_eq!(a.diagnostics.len(), 1);
+        assert!(a.hir.is_none());
+        // Nothing is returned (instead of the results of the previous text)
+        assert!(a.hover(Position::new(3, 4)).is_none());
+        assert!(a.definition(Position::new(6, 5)).is_none());
+        assert!(a.references(Position::new(2, 8)).is_empty());
+    }
+
+    #[test]
+    fn test_definition() {
+        let a = analyze(SRC);
+        // `foo` of `A.foo(1)`
+        let loc = a.definition(Position::new(6, 5)).unwrap();
+        assert_eq!(loc.uri.path(), "/test.sk");
+        assert_eq!(loc.range.start.line, 1);
+    }
+
+    #[test]
+    fn test_references() {
+        let a = analyze(SRC);
+        // `x` of `let x`
+        let refs = a.references(Position::new(2, 8));
+        let lines = refs.iter().map(|l| l.range.start).collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            vec![
+                Position::new(2, 4),
+                Position::new(3, 4),
+                Position::new(3, 8)
+            ]
+        );
+    }
+}
@@ -19,8 +19,10 @@ impl Backend {
     pub fn new(client: Client) -> Self {
         let (tx_from, rx_from) = unbounded();
         let (tx_to, rx_to) = unbounded();
-        let mut server = Server::new(client.clone(), rx_to, tx_from);
-        std::thread::spawn(move || server.serve());
+        let client2 = client.clone();
+        let rt = tokio::runtime::Handle::current();
+        // Server is not Send (hir contains Rc) so create it in the thread
+        std::thread::spawn(move || Server::new(client2, rx_to, tx_from, rt).serve());
 
         Self {
             client,
@@ -61,9 +63,9 @@ impl LanguageServer for Backend {
         Backend::log("- Backend::initialize");
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
-                //                text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                //                    TextDocumentSyncKind::FULL,
-                //                )),
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
                 //                workspace: Some(WorkspaceServerCapabilities {
                 //                    workspace_folders: Some(WorkspaceFoldersServerCapabilities {
                 //                        supported: Some(true),
@@ -72,8 +74,8 @@ impl LanguageServer for Backend {
                 //                    file_operations: None,
                 //                }),
                 definition_provider: Some(OneOf::Left(true)),
-                //                document_formatting_provider: Some(OneOf::Left(true)),
-                //                workspace_symbol_provider: Some(OneOf::Left(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
                 hover_provider: Some(HoverProviderCapability::Simple(true)),
                 references_provider: Some(OneOf::Left(true)),
                 //                semantic_tokens_provider: Some(
@@ -1,5 +1,6 @@
 use tower_lsp::{LspService, Server};
 
+mod analysis;
 mod backend;
 mod server;
 use backend::Backend;
@@ -1,4 +1,13 @@
+use crate::analysis::Analysis;
+use anyhow::Context;
 use async_channel::{Receiver, Sender};
+use shiika_parser::SourceFile;
+use skc_mir::LibraryExports;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use tokio::runtime::Handle;
 use tower_lsp::lsp_types::*;
 use tower_lsp::Client;
 
@@ -59,113 +68,182 @@ pub struct Server {
     client: Client,
     rcv: Receiver<MsgToServer>,
     snd: Sender<MsgFromServer>,
-    //    document_map: DashMap<String, Rope>,
-    //    parser_map: DashMap<String, Parser>,
-    //    metadata_map: DashMap<String, Metadata>,
-    //    cache_dir: String,
-    //    lsp_token: i32,
-    //    background_tasks: VecDeque<BackgroundTask>,
+    /// Used to call async methods of `client`
+    rt: Handle,
+    /// Exports of the core library (None if not found)
+    imports: Option<LibraryExports>,
+    /// Analysis of opened files
+    documents: HashMap<Url, Analysis>,
 }
 
 impl Server {
-    pub fn new(client: Client, rcv: Receiver<MsgToServer>, snd: Sender<MsgFromServer>) -> Self {
+    pub fn new(
+        client: Client,
+        rcv: Receiver<MsgToServer>,
+        snd: Sender<MsgFromServer>,
+        rt: Handle,
+    ) -> Self {
+        let imports = match load_builtin_exports() {
+            Ok(x) => Some(x),
+            Err(e) => {
+                let msg = format!(
+                    "failed to load builtin exports ({}); only syntax errors are reported",
+                    e
+                );
+                rt.block_on(client.log_message(MessageType::WARNING, msg));
+                None
+            }
+        };
         Server {
             client,
             rcv,
             snd,
-            //            document_map: DashMap::new(),
-            //            parser_map: DashMap::new(),
-            //            metadata_map: DashMap::new(),
-            //            cache_dir: Metadata::cache_dir().to_string_lossy().to_string(),
-            //            lsp_token: 0,
-            //            background_tasks: VecDeque::new(),
+            rt,
+            imports,
+            documents: HashMap::new(),
         }
     }
 
     pub fn serve(&mut self) {
-        loop {
-            if let Ok(msg) = self.rcv.recv_blocking() {
-                match msg {
-                    MsgToServer::DidOpen { url, text, version } => {
-                        self.did_open(&url, &text, version)
-                    }
-                    MsgToServer::DidChange { url, text, version } => {
-                        self.did_change(&url, &text, version)
-                    }
-                    MsgToServer::Completion {
-                        url,
-                        line,
-                        column,
-                        context,
-                    } => self.completion(&url, line, column, &context),
-                    MsgToServer::GotoDefinition { url, line, column } => {
-                        self.goto_definition(&url, line, column)
-                    }
-                    MsgToServer::Symbol { query } => self.symbol(&query),
-                    MsgToServer::Hover { url, line, column } => self.hover(&url, line, column),
-                    MsgToServer::References { url, line, column } => {
-                        self.references(&url, line, column)
-                    }
-                    MsgToServer::SemanticTokens { url } => self.semantic_tokens(&url),
-                    MsgToServer::Formatting { url } => self.formatting(&url),
+        while let Ok(msg) = self.rcv.recv_blocking() {
+            match msg {
+                MsgToServer::DidOpen { url, text, version } => self.did_open(&url, &text, version),
+                MsgToServer::DidChange { url, text, version } => {
+                    self.did_change(&url, &text, version)
+                }
+                MsgToServer::Completion {
+                    url,
+                    line,
+                    column,
+                    context,
+                } => self.completion(&url, line, column, &context),
+                MsgToServer::GotoDefinition { url, line, column } => {
+                    self.goto_definition(&url, line, column)
+                }
+                MsgToServer::Symbol { query } => self.symbol(&query),
+                MsgToServer::Hover { url, line, column } => self.hover(&url, line, column),
+                MsgToServer::References { url, line, column } => {
+                    self.references(&url, line, column)
                 }
+                MsgToServer::SemanticTokens { url } => self.semantic_tokens(&url),
+                MsgToServer::Formatting { url } => self.formatting(&url),
             }
-
-            //            while self.rcv.is_empty() && !self.background_tasks.is_empty() {
-            //                if let Some(mut task) = self.background_tasks.pop_front() {
-            //                    if !task.progress {
-            //                        self.progress_start("background analyze");
-            //                        task.progress = true;
-            //                    }
-            //                    if let Some(path) = task.paths.pop() {
-            //                        self.background_analyze(&path, &task.metadata);
-            //                        let pcnt = (task.total - task.paths.len()) * 100 / task.total;
-            //                        self.progress_report(
-            //                            &format!("{}", path.src.file_name().unwrap().to_string_lossy()),
-            //                            pcnt as u32,
-            //                        );
-            //                    }
-            //                    if task.paths.is_empty() {
-            //                        self.progress_done("background analyze done");
-            //                    } else {
-            //                        self.background_tasks.push_front(task);
-            //                    }
-            //                }
-            //            }
         }
     }
 
-    fn did_open(&mut self, url: &Url, text: &str, version: i32) {}
+    fn did_open(&mut self, url: &Url, text: &str, version: i32) {
+        self.analyze(url, text, version);
+    }
+
+    fn did_change(&mut self, url: &Url, text: &str, version: i32) {
+        self.analyze(url, text, version);
+    }
 
-    fn did_change(&mut self, url: &Url, text: &str, version: i32) {}
+    /// Analyze the file and publish the diagnostics.
+    /// The previous analysis is discarded even if this one failed, because
+    /// its positions do not match the new text.
+    fn analyze(&mut self, url: &Url, text: &str, version: i32) {
+        let Ok(path) = url.to_file_path() else {
+            return;
+        };
+        let analysis = Analysis::new(path, text.to_string(), self.imports.as_ref());
+        let diagnostics = analysis.diagnostics.clone();
+        self.documents.insert(url.clone(), analysis);
+        self.rt.block_on(
+            self.client
+                .publish_diagnostics(url.clone(), diagnostics, Some(version)),
+        );
+    }
 
     fn completion(
         &mut self,
-        url: &Url,
-        line: usize,
-        column: usize,
-        context: &Option<CompletionContext>,
+        _url: &Url,
+        _line: usize,
+        _column: usize,
+        _context: &Option<CompletionContext>,
     ) {
+        self.reply(MsgFromServer::Completion(None));
     }
 
-    fn goto_definition(&mut self, url: &Url, line: usize, column: usize) {}
+    fn goto_definition(&mut self, url: &Url, line: usize, column: usize) {
+        let location = self
+            .documents
+            .get(url)
+            .and_then(|a| a.definition(position(line, column)));
+        self.reply(MsgFromServer::GotoDefinition(location));
+    }
 
-    fn symbol(&mut self, query: &str) {}
+    fn symbol(&mut self, query: &str) {
+        let symbols = self
+            .documents
+            .values()
+            .flat_map(|a| a.symbols(query))
+            .collect();
+        self.reply(MsgFromServer::Symbol(symbols));
+    }
 
     fn hover(&mut self, url: &Url, line: usize, column: usize) {
-        let msg = format!("{}:{}", &line, &column);
-        let hover = Hover {
-            contents: HoverContents::Scalar(MarkedString::String(msg)),
-            range: None,
-        };
-        self.snd
-            .send_blocking(MsgFromServer::Hover(Some(hover)))
-            .unwrap();
+        let hover = self
+            .documents
+            .get(url)
+            .and_then(|a| a.hover(position(line, column)));
+        self.reply(MsgFromServer::Hover(hover));
+    }
+
+    fn references(&mut self, url: &Url, line: usize, column: usize) {
+        let locations = self
+            .documents
+            .get(url)
+            .map(|a| a.references(position(line, column)))
+            .unwrap_or_default();
+        self.reply(MsgFromServer::References(locations));
     }
 
-    fn references(&mut self, url: &Url, line: usize, column: usize) {}
+    fn semantic_tokens(&mut self, _url: &Url) {
+        self.reply(MsgFromServer::SemanticTokens(None));
+    }
+
+    fn formatting(&mut self, url: &Url) {
+        let edits = self.documents.get(url).and_then(|a| {
+            let file = SourceFile::new(a.path.clone(), a.text.clone());
+            // Do nothing if the file has a syntax error
+            let formatted = shiika_fmt::format(&file).ok()?;
+            if formatted == a.text {
+                Some(vec![])
+            } else {
+                Some(vec![TextEdit::new(whole_range(&a.text), formatted)])
+            }
+        });
+        self.reply(MsgFromServer::Formatting(edits));
+    }
 
-    fn semantic_tokens(&mut self, url: &Url) {}
+    /// Send the response to the backend
+    fn reply(&self, msg: MsgFromServer) {
+        self.snd.send_blocking(msg).unwrap();
+    }
+}
+
+/// Returns the range which covers the entire `text`
+fn whole_range(text: &str) -> Range {
+    let n_lines = text.split('\n').count() as u32;
+    let last_line = text.rsplit('\n').next().unwrap_or("");
+    Range::new(
+        Position::new(0, 0),
+        Position::new(n_lines - 1, last_line.encode_utf16().count() as u32),
+    )
+}
+
+/// Convert 1-origin line and column to `Position`
+fn position(line: usize, column: usize) -> Position {
+    Position::new(line as u32 - 1, column as u32 - 1)
+}
 
-    fn formatting(&mut self, url: &Url) {}
+/// Load builtin/exports.json
+fn load_builtin_exports() -> anyhow::Result<LibraryExports> {
+    let root = env::var("SHIIKA_ROOT").unwrap_or_else(|_| ".".to_string());
+    let json_path = Path::new(&root).join("builtin/exports.json");
+    let contents = fs::read_to_string(&json_path)
+        .with_context(|| format!("{} not found", json_path.display()))?;
+    let exports = serde_json::from_str(&contents).context("builtin exports is broken")?;
+    Ok(exports)
 }
@@ -4,6 +4,8 @@ use shiika_core::{names::ConstFullname, ty::TermTy};
 use skc_hir::SkTypes;
 use std::collections::HashMap;
 
+/// Types, vtables and constants which a library provides (saved as
+/// `exports.json`.) Private methods and constants are omitted.
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Default)]
 pub struct LibraryExports {
     pub sk_types: SkTypes,
@@ -24,9 +26,15 @@ impl LibraryExports {
     pub fn new(mir: &Mir) -> LibraryExports {
         LibraryExports {
             // PERF: how to generate json without cloning?
-            sk_types: mir.hir.sk_types.clone(),
+            sk_types: mir.hir.sk_types.without_private_methods(),
             vtables: mir.vtables.clone(),
-            constants: mir.hir.constants.clone(),
+            constants: mir
+                .hir
+                .constants
+                .iter()
+                .filter(|(name, _)| !mir.hir.private_consts.contains(name))
+                .map(|(name, ty)| (name.clone(), ty.clone()))
+                .collect(),
         }
     }
 }
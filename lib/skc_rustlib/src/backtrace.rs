@@ -0,0 +1,82 @@
+//! Shiika-level backtrace.
+//!
+//! Each Shiika method (and lambda) allocates a `Frame` on the machine stack
+//! and links it to `shiika_backtrace_top` on entry (see
+//! `skc_codegen::backtrace`.) `line` is updated before every method call
+//! so that it points to the call site.
+use std::ffi::{c_char, CStr};
+use std::io::{stderr, Write};
+use std::ptr;
+
+#[repr(C)]
+pub struct Frame {
+    prev: *const Frame,
+    /// Method name (eg. `Foo#bar`) as a null-terminated string
+    name: *const c_char,
+    /// Path of the source file as a null-terminated string (may be empty)
+    file: *const c_char,
+    /// Line number of the current position (0 if unknown)
+    line: i64,
+}
+
+/// The innermost frame
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static mut shiika_backtrace_top: *const Frame = ptr::null();
+
+impl Frame {
+    fn name(&self) -> String {
+        unsafe { CStr::from_ptr(self.name) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Returns a string like "main.sk:12:in `Foo#bar'"
+    fn describe(&self) -> String {
+        let file = unsafe { CStr::from_ptr(self.file) }.to_string_lossy();
+        match (file.is_empty(), self.line) {
+            (true, _) => format!("in `{}'", self.name()),
+            (false, 0) => format!("{}:in `{}'", file, self.name()),
+            (false, line) => format!("{}:{}:in `{}'", file, line, self.name()),
+        }
+    }
+}
+
+/// Returns the current frames (innermost first)
+pub fn capture() -> Vec<String> {
+    describe_frames(unsafe { shiika_backtrace_top })
+}
+
+/// Returns the current frames, excluding the ones of `initialize` on the
+/// top (ie. the frames of creating an `Error`)
+pub fn capture_for_error() -> Vec<String> {
+    let mut frame = unsafe { shiika_backtrace_top };
+    while let Some(f) = unsafe { frame.as_ref() } {
+        if !f.name().ends_with("#initialize") {
+            break;
+        }
+        frame = f.prev;
+    }
+    describe_frames(frame)
+}
+
+fn describe_frames(mut frame: *const Frame) -> Vec<String> {
+    let mut lines = vec![];
+    while let Some(f) = unsafe { frame.as_ref() } {
+        lines.push(f.describe());
+        frame = f.prev;
+    }
+    lines
+}
+
+/// Print `msg` with the backtrace and terminate the process.
+/// Used for `panic` and runtime errors like index out of bounds.
+pub fn fault(msg: &str) -> ! {
+    let mut err = stderr().lock();
+    let _ = writeln!(err, "panic: {}", msg);
+    for line in capture() {
+        let _ = writeln!(err, "\tfrom {}", line);
+    }
+    let _ = err.flush();
+    std::process::exit(1);
+}
@@ -5,12 +5,14 @@ mod error;
 mod file;
 pub mod float;
 mod random;
+mod regex;
 mod result;
 mod time;
 //mod fn_x;
 pub mod int;
 mod math;
 pub mod object;
+mod process;
 mod shiika_internal_memory;
 pub mod shiika_internal_ptr;
 //pub mod shiika_internal_ptr_typed;
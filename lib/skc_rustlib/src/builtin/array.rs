@@ -1,3 +1,4 @@
+use crate::backtrace;
 use crate::builtin::{SkClass, SkInt, SkObj};
 use crate::sk_cls::SkCls;
 use shiika_ffi_macro::{shiika_const_ref, shiika_method, shiika_method_ref};
@@ -63,14 +64,27 @@ pub extern "C" fn array__initialize_rustlib(receiver: SkAry<SkObj>) {
 pub extern "C" fn array_get(receiver: SkAry<SkObj>, idx: SkInt) -> SkObj {
     let v: &Vec<SkObj> = receiver.as_vec();
     v.get(idx.val() as usize)
-        .unwrap_or_else(|| panic!("Array#[]: idx too large (len: {}, idx: {})", v.len(), idx))
+        .unwrap_or_else(|| {
+            backtrace::fault(&format!(
+                "Array#[]: index out of bounds (len: {}, idx: {})",
+                v.len(),
+                idx
+            ))
+        })
         .dup()
 }
 
 #[shiika_method("Array#[]=")]
 pub extern "C" fn array_set(receiver: SkAry<SkObj>, idx: SkInt, obj: SkObj) {
     let v = receiver.as_vec_mut();
-    v[idx.val() as usize] = obj;
+    let len = v.len();
+    let item = v.get_mut(idx.val() as usize).unwrap_or_else(|| {
+        backtrace::fault(&format!(
+            "Array#[]=: index out of bounds (len: {}, idx: {})",
+            len, idx
+        ))
+    });
+    *item = obj;
 }
 
 #[shiika_method("Array#clear")]
@@ -90,7 +104,11 @@ pub extern "C" fn array_push(receiver: SkAry<SkObj>, item: SkObj) {
 
 #[shiika_method("Array#pop")]
 pub extern "C" fn array_pop(receiver: SkAry<SkObj>) -> SkObj {
-    receiver.as_vec_mut().pop().unwrap().dup()
+    receiver
+        .as_vec_mut()
+        .pop()
+        .unwrap_or_else(|| backtrace::fault("Array#pop: array is empty"))
+        .dup()
 }
 
 #[shiika_method("Array#reserve")]
@@ -1,5 +1,6 @@
-use crate::builtin::{SkClass, SkStr};
-use shiika_ffi_macro::{shiika_const_ref, shiika_method_ref};
+use crate::backtrace;
+use crate::builtin::{SkAry, SkClass, SkStr};
+use shiika_ffi_macro::{shiika_const_ref, shiika_method, shiika_method_ref};
 
 shiika_const_ref!("::Error", SkClass, "sk_Error");
 shiika_method_ref!(
@@ -17,6 +18,7 @@ struct ShiikaError {
     vtable: *const u8,
     class_obj: *const u8,
     msg: SkStr,
+    backtrace: SkAry<SkStr>,
 }
 
 impl SkError {
@@ -24,3 +26,16 @@ impl SkError {
         meta_error_new(sk_Error(), msg.into())
     }
 }
+
+/// Called from `Error#initialize` and returns the frames of the caller.
+#[shiika_method("Error#_capture_backtrace")]
+#[allow(non_snake_case)]
+pub extern "C" fn error__capture_backtrace(_receiver: SkError) -> SkAry<SkStr> {
+    let ary = SkAry::<SkStr>::new();
+    let v = backtrace::capture_for_error()
+        .into_iter()
+        .map(|s| s.into())
+        .collect::<Vec<SkStr>>();
+    ary.set_vec(v);
+    ary
+}
@@ -1,4 +1,6 @@
 //! Instance of `::Float`
+use crate::backtrace;
+use crate::builtin::object::mix_hash;
 use crate::builtin::{SkBool, SkInt, SkStr};
 use shiika_ffi_macro::shiika_method;
 
@@ -37,6 +39,19 @@ impl SkFloat {
     }
 }
 
+/// Convert the integral value `f` to Int. Terminates the process if it is
+/// NaN or too large.
+fn to_int(f: f64, method_name: &str) -> SkInt {
+    // Note: `i64::MAX as f64` is 2^63, which is not in the range
+    if f.is_nan() || f < i64::MIN as f64 || f >= i64::MAX as f64 {
+        backtrace::fault(&format!(
+            "{}: {} is out of the range of Int",
+            method_name, f
+        ))
+    }
+    (f as i64).into()
+}
+
 #[shiika_method("Float#-@")]
 pub extern "C" fn float_inv(receiver: SkFloat) -> SkFloat {
     (-receiver.val()).into()
@@ -62,6 +77,13 @@ pub extern "C" fn float_div(receiver: SkFloat, other: SkFloat) -> SkFloat {
     (receiver.val() / other.val()).into()
 }
 
+#[shiika_method("Float#%")]
+pub extern "C" fn float_mod(receiver: SkFloat, other: SkFloat) -> SkFloat {
+    let (a, b) = (receiver.val(), other.val());
+    // The result has the same sign as `other`, like `Int#%`
+    (a - b * (a / b).floor()).into()
+}
+
 #[shiika_method("Float#<")]
 pub extern "C" fn float_lt(receiver: SkFloat, other: SkFloat) -> SkBool {
     (receiver.val() < other.val()).into()
@@ -87,19 +109,51 @@ pub extern "C" fn float_eq(receiver: SkFloat, other: SkFloat) -> SkBool {
     (receiver.val() == other.val()).into()
 }
 
+#[shiika_method("Float#hash")]
+pub extern "C" fn float_hash(receiver: SkFloat) -> SkInt {
+    // `0.0 == -0.0` so they must have the same hash
+    let f = if receiver.val() == 0.0 {
+        0.0
+    } else {
+        receiver.val()
+    };
+    mix_hash(f.to_bits()).into()
+}
+
 #[shiika_method("Float#abs")]
 pub extern "C" fn float_abs(receiver: SkFloat) -> SkFloat {
     receiver.val().abs().into()
 }
 
+#[shiika_method("Float#ceil")]
+pub extern "C" fn float_ceil(receiver: SkFloat) -> SkInt {
+    to_int(receiver.val().ceil(), "Float#ceil")
+}
+
 #[shiika_method("Float#floor")]
-pub extern "C" fn float_floor(receiver: SkFloat) -> SkFloat {
-    receiver.val().floor().into()
+pub extern "C" fn float_floor(receiver: SkFloat) -> SkInt {
+    to_int(receiver.val().floor(), "Float#floor")
+}
+
+#[shiika_method("Float#infinite?")]
+pub extern "C" fn float_infinite(receiver: SkFloat) -> SkBool {
+    receiver.val().is_infinite().into()
+}
+
+#[shiika_method("Float#nan?")]
+pub extern "C" fn float_nan(receiver: SkFloat) -> SkBool {
+    receiver.val().is_nan().into()
+}
+
+/// Rounds half away from zero.
+#[shiika_method("Float#round")]
+pub extern "C" fn float_round(receiver: SkFloat) -> SkInt {
+    to_int(receiver.val().round(), "Float#round")
 }
 
 #[shiika_method("Float#to_i")]
 pub extern "C" fn float_to_i(receiver: SkFloat) -> SkInt {
-    (receiver.val().trunc() as i64).into()
+    to_int(receiver.val().trunc(), "Float#to_i")
 }
 
 #[shiika_method("Float#to_s")]
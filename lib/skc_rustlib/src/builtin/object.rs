@@ -1,3 +1,4 @@
+use crate::backtrace;
 use crate::builtin::class::SkClass;
 use crate::builtin::{SkBool, SkFloat, SkInt, SkResult, SkStr};
 use plain::Plain;
@@ -57,6 +58,19 @@ pub extern "C" fn object_eq(receiver: *const u8, other: *const u8) -> SkBool {
     (receiver == other).into()
 }
 
+/// Scramble the bits of `x` so that similar values (eg. addresses) have
+/// different lower bits (splitmix64 finalizer.)
+pub fn mix_hash(x: u64) -> i64 {
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (x ^ (x >> 31)) as i64
+}
+
+#[shiika_method("Object#hash")]
+pub extern "C" fn object_hash(receiver: *const u8) -> SkInt {
+    mix_hash(receiver as u64).into()
+}
+
 #[shiika_method("Object#class")]
 pub extern "C" fn object_class(receiver: SkObj) -> SkClass {
     receiver.class()
@@ -87,7 +101,7 @@ pub extern "C" fn object_object_id(receiver: SkObj) -> SkInt {
 
 #[shiika_method("Object#panic")]
 pub extern "C" fn object_panic(_receiver: *const u8, s: SkStr) {
-    panic!("{}", s.as_str());
+    backtrace::fault(s.as_str());
 }
 
 #[shiika_method("Object#print")]
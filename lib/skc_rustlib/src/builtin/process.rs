@@ -0,0 +1,225 @@
+//! `Process` and `Env`
+use crate::builtin::object::ShiikaObject;
+use crate::builtin::{SkAry, SkBool, SkClass, SkInt, SkObj, SkPtr, SkResult, SkStr, SkVoid};
+use libc::c_void;
+use shiika_ffi_macro::{shiika_const_ref, shiika_method, shiika_method_ref};
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use std::ptr;
+
+shiika_const_ref!("::ProcessOutput", SkClass, "sk_ProcessOutput");
+shiika_const_ref!("::Process::Child", SkClass, "sk_Process_Child");
+shiika_method_ref!(
+    "Meta:ProcessOutput#new",
+    fn(receiver: SkClass, stdout: SkStr, stderr: SkStr, status: SkInt) -> SkProcessOutput,
+    "meta_process_output_new"
+);
+shiika_method_ref!(
+    "Meta:Process::Child#new",
+    fn(receiver: SkClass, state: SkPtr) -> SkChild,
+    "meta_process_child_new"
+);
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkProcessOutput(*const ShiikaObject);
+
+impl From<SkProcessOutput> for SkObj {
+    fn from(s: SkProcessOutput) -> SkObj {
+        SkObj::new(s.0)
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkChild(*mut ShiikaChild);
+
+// Must match `builtin/process.sk`.
+#[repr(C)]
+#[derive(Debug)]
+struct ShiikaChild {
+    vtable: *const u8,
+    class_obj: *const u8,
+    state: SkPtr,
+}
+
+impl From<SkChild> for SkObj {
+    fn from(s: SkChild) -> SkObj {
+        SkObj::new(s.0 as *const ShiikaObject)
+    }
+}
+
+/// The value of `@_state` of `Process::Child`.
+struct ChildState {
+    child: Child,
+    /// `None` after `close_stdin`
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Drop for ChildState {
+    /// Kill the process if it is still running and reap it so that it
+    /// does not remain as a zombie.
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+impl SkChild {
+    fn state(&mut self) -> &mut ChildState {
+        unsafe { &mut *((*self.0).state.unbox_mut() as *mut ChildState) }
+    }
+}
+
+/// Frees the `ChildState` when the `Process::Child` is garbage collected.
+extern "C" fn child_finalizer(obj: *mut c_void, _data: *mut c_void) {
+    let state = SkChild(obj as *mut ShiikaChild).state() as *mut ChildState;
+    unsafe { drop(Box::from_raw(state)) };
+}
+
+/// Returns the command line arguments (without the program name.)
+#[shiika_method("Meta:Process#args")]
+pub extern "C" fn meta_process_args(_receiver: SkClass) -> SkAry<SkStr> {
+    let ary = SkAry::<SkStr>::new();
+    ary.set_vec(env::args().skip(1).map(|s| s.into()).collect());
+    ary
+}
+
+/// Terminates the process with the exit status `code`.
+#[shiika_method("Meta:Process#exit")]
+pub extern "C" fn meta_process_exit(_receiver: SkClass, code: SkInt) {
+    std::process::exit(code.val() as i32);
+}
+
+#[shiika_method("Meta:Process#pid")]
+pub extern "C" fn meta_process_pid(_receiver: SkClass) -> SkInt {
+    (std::process::id() as i64).into()
+}
+
+/// Run the command and wait for it to finish.
+#[shiika_method("Meta:Process#run")]
+pub extern "C" fn meta_process_run(
+    _receiver: SkClass,
+    cmd: SkStr,
+    args: SkAry<SkStr>,
+) -> SkResult<SkProcessOutput> {
+    command(cmd, args)
+        .output()
+        .map(|output| {
+            meta_process_output_new(
+                sk_ProcessOutput(),
+                SkStr::from_u8(output.stdout),
+                SkStr::from_u8(output.stderr),
+                exit_code(output.status),
+            )
+        })
+        .into()
+}
+
+/// Start the command with its stdin and stdout connected to the
+/// returned `Process::Child`.
+#[shiika_method("Meta:Process#spawn")]
+pub extern "C" fn meta_process_spawn(
+    _receiver: SkClass,
+    cmd: SkStr,
+    args: SkAry<SkStr>,
+) -> SkResult<SkChild> {
+    command(cmd, args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map(|mut child| {
+            let state = ChildState {
+                stdin: child.stdin.take(),
+                stdout: BufReader::new(child.stdout.take().unwrap()),
+                child,
+            };
+            let ptr = Box::into_raw(Box::new(state)) as *const u8;
+            let sk_child = meta_process_child_new(sk_Process_Child(), SkPtr::new(ptr));
+            unsafe {
+                bdwgc_alloc::Allocator::register_finalizer(
+                    sk_child.0 as *const c_void,
+                    child_finalizer,
+                    ptr::null(),
+                );
+            }
+            sk_child
+        })
+        .into()
+}
+
+fn command(cmd: SkStr, args: SkAry<SkStr>) -> Command {
+    let mut command = Command::new(cmd.as_str());
+    command.args(args.as_vec().iter().map(|arg| arg.as_str()));
+    command
+}
+
+/// Returns the exit status of the process (or -1 if killed by a signal.)
+fn exit_code(status: ExitStatus) -> SkInt {
+    (status.code().unwrap_or(-1) as i64).into()
+}
+
+#[shiika_method("Process::Child#_fill_buf")]
+#[allow(non_snake_case)]
+pub extern "C" fn process_child__fill_buf(mut receiver: SkChild) -> SkResult<SkStr> {
+    match receiver.state().stdout.fill_buf() {
+        Ok(u8slice) => SkResult::ok(SkStr::from_u8(u8slice.to_vec())),
+        Err(e) => SkResult::fail(format!("{}", e)),
+    }
+}
+
+#[shiika_method("Process::Child#_consume")]
+#[allow(non_snake_case)]
+pub extern "C" fn process_child__consume(mut receiver: SkChild, n_bytes: SkInt) {
+    receiver.state().stdout.consume(n_bytes.into());
+}
+
+/// Write `s` to the stdin of the process.
+#[shiika_method("Process::Child#write")]
+pub extern "C" fn process_child_write(mut receiver: SkChild, s: SkStr) -> SkResult<SkVoid> {
+    match receiver.state().stdin.as_mut() {
+        Some(stdin) => stdin
+            .write_all(s.as_byteslice())
+            .and_then(|_| stdin.flush())
+            .into(),
+        None => SkResult::fail("stdin is closed".to_string()),
+    }
+}
+
+/// Close the stdin of the process. Does nothing if already closed.
+#[shiika_method("Process::Child#close_stdin")]
+pub extern "C" fn process_child_close_stdin(mut receiver: SkChild) {
+    receiver.state().stdin = None;
+}
+
+/// Close the stdin and wait for the process to finish. Returns the exit
+/// status.
+#[shiika_method("Process::Child#wait")]
+pub extern "C" fn process_child_wait(mut receiver: SkChild) -> SkResult<SkInt> {
+    let state = receiver.state();
+    state.stdin = None;
+    state.child.wait().map(exit_code).into()
+}
+
+/// Returns the value of the environment variable (or an empty string if
+/// it is not set or not valid as utf-8.)
+#[shiika_method("Meta:Env#_get")]
+#[allow(non_snake_case)]
+pub extern "C" fn meta_env__get(_receiver: SkClass, name: SkStr) -> SkStr {
+    env::var(name.as_str()).unwrap_or_default().into()
+}
+
+#[shiika_method("Meta:Env#_has?")]
+#[allow(non_snake_case)]
+pub extern "C" fn meta_env__has(_receiver: SkClass, name: SkStr) -> SkBool {
+    env::var(name.as_str()).is_ok().into()
+}
+
+#[shiika_method("Meta:Env#set")]
+pub extern "C" fn meta_env_set(_receiver: SkClass, name: SkStr, value: SkStr) {
+    env::set_var(name.as_str(), value.as_str());
+}
@@ -0,0 +1,129 @@
+//! Instance of `::Regex`
+use crate::backtrace;
+use crate::builtin::{SkAry, SkBool, SkClass, SkPtr, SkStr};
+use regex::Regex;
+use shiika_ffi_macro::shiika_method;
+use std::cell::RefCell;
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct SkRegex(*const ShiikaRegex);
+
+// Must match `builtin/regex.sk`.
+#[repr(C)]
+#[derive(Debug)]
+struct ShiikaRegex {
+    vtable: *const u8,
+    class_obj: *const u8,
+    source: SkStr,
+    state: SkPtr,
+}
+
+impl SkRegex {
+    /// Returns the compiled regex (the value of `@_state`).
+    fn regex(&self) -> &Regex {
+        unsafe { &*((*self.0).state.unbox() as *const Regex) }
+    }
+}
+
+fn str_ary(strs: Vec<String>) -> SkAry<SkStr> {
+    let ary = SkAry::<SkStr>::new();
+    ary.set_vec(strs.into_iter().map(|s| s.into()).collect());
+    ary
+}
+
+thread_local! {
+    /// The regex compiled by `Regex._error_message`, to be used by the
+    /// following `Regex.new` in `Regex.compile`
+    static LAST_COMPILED: RefCell<Option<(String, Regex)>> = const { RefCell::new(None) };
+}
+
+/// Compile `source`. Terminates the process if it is not a valid regex.
+#[shiika_method("Meta:Regex#_compile")]
+#[allow(non_snake_case)]
+pub extern "C" fn meta_regex__compile(_receiver: SkClass, source: SkStr) -> SkPtr {
+    let source = source.as_str();
+    let cached = LAST_COMPILED.with_borrow_mut(|last| match last.take() {
+        Some((s, re)) if s == source => Some(re),
+        _ => None,
+    });
+    let re = cached.unwrap_or_else(|| {
+        Regex::new(source).unwrap_or_else(|e| backtrace::fault(&format!("Regex.new: {}", e)))
+    });
+    SkPtr::new(Box::into_raw(Box::new(re)) as *const u8)
+}
+
+/// Returns the reason why `source` is not a valid regex (or an empty string
+/// if it is valid.) The compiled regex is kept for the next `_compile`.
+#[shiika_method("Meta:Regex#_error_message")]
+#[allow(non_snake_case)]
+pub extern "C" fn meta_regex__error_message(_receiver: SkClass, source: SkStr) -> SkStr {
+    match Regex::new(source.as_str()) {
+        Ok(re) => {
+            LAST_COMPILED.set(Some((source.as_str().to_string(), re)));
+            "".to_string().into()
+        }
+        Err(e) => e.to_string().into(),
+    }
+}
+
+#[shiika_method("Regex#match?")]
+pub extern "C" fn regex_match_p(receiver: SkRegex, s: SkStr) -> SkBool {
+    receiver.regex().is_match(s.as_str()).into()
+}
+
+/// Returns the strings matched by the groups of the first match (or an
+/// empty array if not matched.) The first one is the whole match and
+/// a group which did not participate in the match is an empty string.
+#[shiika_method("Regex#_captures")]
+#[allow(non_snake_case)]
+pub extern "C" fn regex__captures(receiver: SkRegex, s: SkStr) -> SkAry<SkStr> {
+    let strs = match receiver.regex().captures(s.as_str()) {
+        Some(caps) => caps
+            .iter()
+            .map(|m| m.map(|m| m.as_str()).unwrap_or_default().to_string())
+            .collect(),
+        None => vec![],
+    };
+    str_ary(strs)
+}
+
+/// Returns the names of the groups (an empty string for unnamed ones.)
+#[shiika_method("Regex#_capture_names")]
+#[allow(non_snake_case)]
+pub extern "C" fn regex__capture_names(receiver: SkRegex) -> SkAry<SkStr> {
+    let names = receiver
+        .regex()
+        .capture_names()
+        .map(|name| name.unwrap_or_default().to_string())
+        .collect();
+    str_ary(names)
+}
+
+#[shiika_method("Regex#match_all")]
+pub extern "C" fn regex_match_all(receiver: SkRegex, s: SkStr) -> SkAry<SkStr> {
+    let strs = receiver
+        .regex()
+        .find_iter(s.as_str())
+        .map(|m| m.as_str().to_string())
+        .collect();
+    str_ary(strs)
+}
+
+#[shiika_method("Regex#replace")]
+pub extern "C" fn regex_replace(receiver: SkRegex, s: SkStr, replacement: SkStr) -> SkStr {
+    receiver
+        .regex()
+        .replace(s.as_str(), replacement.as_str())
+        .into_owned()
+        .into()
+}
+
+#[shiika_method("Regex#replace_all")]
+pub extern "C" fn regex_replace_all(receiver: SkRegex, s: SkStr, replacement: SkStr) -> SkStr {
+    receiver
+        .regex()
+        .replace_all(s.as_str(), replacement.as_str())
+        .into_owned()
+        .into()
+}
@@ -1,9 +1,10 @@
 //! Instance of `::String`
 use crate::builtin::object::ShiikaObject;
-use crate::builtin::{SkAry, SkClass, SkInt, SkObj, SkPtr};
+use crate::builtin::{SkAry, SkClass, SkFloat, SkInt, SkObj, SkPtr};
 use crate::sk_cls::SkCls;
 use shiika_ffi_macro::{shiika_const_ref, shiika_method};
 use std::ffi::CString;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use unicode_segmentation::UnicodeSegmentation;
 
 shiika_const_ref!("::String", SkClass, "sk_String");
@@ -90,6 +91,52 @@ impl SkStr {
     }
 }
 
+/// Parse the longest prefix of `s` which looks like a float (e.g. "-1.5e3").
+/// Returns 0.0 if there is no such prefix.
+fn parse_float_prefix(s: &[u8]) -> f64 {
+    let skip_digits = |mut i: usize| {
+        while i < s.len() && s[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    let start = if matches!(s.first(), Some(b'+' | b'-')) {
+        1
+    } else {
+        0
+    };
+    let mut end = skip_digits(start);
+    if end == start {
+        return 0.0;
+    }
+    if s.get(end) == Some(&b'.') {
+        let frac_end = skip_digits(end + 1);
+        if frac_end > end + 1 {
+            end = frac_end;
+        }
+    }
+    if matches!(s.get(end), Some(b'e' | b'E')) {
+        let mut i = end + 1;
+        if matches!(s.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        let exp_end = skip_digits(i);
+        if exp_end > i {
+            end = exp_end;
+        }
+    }
+    // Safe because `s[..end]` consists of ASCII characters
+    std::str::from_utf8(&s[..end])
+        .unwrap()
+        .parse()
+        .unwrap_or(0.0)
+}
+
+#[shiika_method("String#<=>")]
+pub extern "C" fn string_cmp(receiver: SkStr, other: SkStr) -> SkInt {
+    (receiver.as_byteslice().cmp(other.as_byteslice()) as i64).into()
+}
+
 #[shiika_method("String#chars")]
 pub extern "C" fn string_chars(receiver: SkStr) -> SkAry<SkStr> {
     let ary = SkAry::<SkStr>::new();
@@ -100,6 +147,18 @@ pub extern "C" fn string_chars(receiver: SkStr) -> SkAry<SkStr> {
     ary
 }
 
+#[shiika_method("String#hash")]
+pub extern "C" fn string_hash(receiver: SkStr) -> SkInt {
+    let mut hasher = DefaultHasher::new();
+    receiver.as_byteslice().hash(&mut hasher);
+    (hasher.finish() as i64).into()
+}
+
+#[shiika_method("String#to_f")]
+pub extern "C" fn string_to_f(receiver: SkStr) -> SkFloat {
+    parse_float_prefix(receiver.as_byteslice()).into()
+}
+
 // TODO: How to support `break`
 //#[shiika_method("String#each_char")]
 //pub extern "C" fn string_each_char(receiver: SkStr, block: SkFn1<SkStr, SkVoid>) {
@@ -1,4 +1,5 @@
 mod allocator;
+mod backtrace;
 mod builtin;
 mod sk_cls;
 mod sk_methods;
@@ -1,10 +1,17 @@
 mod array;
+mod channel;
 mod class;
+mod file;
 mod float;
 mod int;
+mod listener;
 mod mutable_string;
+mod mutex;
 mod object;
+mod process;
 mod random;
 mod shiika_internal;
+mod socket;
 mod string;
+mod task;
 mod time;
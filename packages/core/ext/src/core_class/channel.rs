@@ -0,0 +1,66 @@
+use crate::runtime::fault;
+use shiika_ffi::async_::ChiikaValue;
+use shiika_ffi::core_class::{SkBool, SkChannel, SkInt, SkObject};
+use shiika_ffi::SkValue;
+use shiika_ffi_macro::{async_shiika_method, shiika_const_ref, shiika_method};
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+shiika_const_ref!("::Maybe::None", SkObject, "sk_None");
+
+/// The value of `Channel#@_state`.
+/// Items are `Maybe::Some` objects so that `recv` can return them as is.
+struct ChannelState {
+    /// Becomes `None` when the channel is closed
+    tx: Mutex<Option<mpsc::Sender<ChiikaValue>>>,
+    rx: tokio::sync::Mutex<mpsc::Receiver<ChiikaValue>>,
+}
+
+fn channel_state(channel: &SkChannel) -> &'static ChannelState {
+    unsafe { &*(channel.state() as *const ChannelState) }
+}
+
+/// Returns the initial value of `@_state`.
+#[shiika_method("Channel#_initialize_rustlib")]
+pub extern "C" fn channel_initialize_rustlib(_receiver: SkChannel, capacity: SkInt) -> *mut u8 {
+    if capacity.val() <= 0 {
+        fault(&format!(
+            "Channel.new: capacity must be positive (got {})",
+            capacity
+        ));
+    }
+    let (tx, rx) = mpsc::channel(capacity.val() as usize);
+    let state = ChannelState {
+        tx: Mutex::new(Some(tx)),
+        rx: tokio::sync::Mutex::new(rx),
+    };
+    Box::into_raw(Box::new(state)) as *mut u8
+}
+
+/// Send `item` to the channel. Waits while the buffer is full.
+/// Returns false if the channel is closed.
+#[async_shiika_method("Channel#_send")]
+async fn channel_send(receiver: SkChannel, item: SkObject) -> SkBool {
+    let tx = channel_state(&receiver).tx.lock().unwrap().clone();
+    match tx {
+        Some(tx) => tx.send(item.as_raw_u64()).await.is_ok().into(),
+        None => false.into(),
+    }
+}
+
+/// Receive an item from the channel. Waits while the buffer is empty.
+/// Returns `None` if the channel is closed and has no more items.
+#[async_shiika_method("Channel#recv")]
+async fn channel_recv(receiver: SkChannel) -> SkObject {
+    let mut rx = channel_state(&receiver).rx.lock().await;
+    match rx.recv().await {
+        Some(item) => SkObject::from_raw_u64(item),
+        None => sk_None(),
+    }
+}
+
+/// Close the channel. Items already sent can still be received.
+#[shiika_method("Channel#close")]
+pub extern "C" fn channel_close(receiver: SkChannel) {
+    channel_state(&receiver).tx.lock().unwrap().take();
+}
@@ -2,6 +2,7 @@ use shiika_ffi::core_class::class::{ShiikaClass, WitnessTable};
 use shiika_ffi::core_class::{SkArray, SkBool, SkClass, SkInt, SkString};
 use shiika_ffi_macro::{async_shiika_method, shiika_method};
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 #[async_shiika_method("Class#==")]
 async fn class_eq(receiver: SkClass, other: SkClass) -> SkBool {
@@ -64,6 +65,7 @@ async fn class_type_argument(receiver: SkClass, nth: SkInt) -> SkClass {
 
 #[async_shiika_method("Class#<>")]
 async fn class_specialize_sym(receiver: SkClass, tyargs: SkArray<SkClass>) -> SkClass {
+    let _lock = SPECIALIZE_LOCK.lock().unwrap();
     class_specialize(receiver, tyargs.into_vec())
 }
 
@@ -71,9 +73,14 @@ async fn class_specialize_sym(receiver: SkClass, tyargs: SkArray<SkClass>) -> Sk
 /// Used for solving bootstrap problem
 #[async_shiika_method("Class#_specialize1")]
 async fn class_specialize1(receiver: SkClass, tyarg: SkClass) -> SkClass {
+    let _lock = SPECIALIZE_LOCK.lock().unwrap();
     class_specialize(receiver, vec![tyarg])
 }
 
+/// Guards `specialized_classes` of all classes because tasks may specialize
+/// a class at the same time with the multi-threaded runtime.
+static SPECIALIZE_LOCK: Mutex<()> = Mutex::new(());
+
 /// Create a specialized class from a generic class
 /// eg. make `Array<Int>` from `Array` and `Int`
 fn class_specialize(mut receiver: SkClass, tyargs: Vec<SkClass>) -> SkClass {
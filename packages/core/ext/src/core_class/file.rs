@@ -0,0 +1,63 @@
+use crate::io::StreamState;
+use shiika_ffi::core_class::{SkBool, SkFile, SkInt, SkString};
+use shiika_ffi_macro::{async_shiika_method, shiika_method};
+use tokio::fs::{File, OpenOptions};
+
+fn file_state(file: &SkFile) -> &'static StreamState<File> {
+    unsafe { StreamState::from_raw(file.state()) }
+}
+
+/// Returns the initial value of `@_state`.
+#[shiika_method("File#_initialize_rustlib")]
+pub extern "C" fn file_initialize_rustlib(receiver: SkFile) -> *mut u8 {
+    StreamState::<File>::new_raw(receiver.as_ptr())
+}
+
+/// Open the file at `path`. If `write` is true, the file is created or
+/// truncated to be written.
+#[async_shiika_method("File#_open_path")]
+async fn file_open_path(receiver: SkFile, path: SkString, write: SkBool) -> SkBool {
+    let path = path.as_str().to_string();
+    let result = if bool::from(write) {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .await
+    } else {
+        File::open(path).await
+    };
+    file_state(&receiver).open(result).await.into()
+}
+
+#[async_shiika_method("File#_fill_buf")]
+async fn file_fill_buf(receiver: SkFile) -> SkBool {
+    file_state(&receiver).fill_buf().await.into()
+}
+
+#[async_shiika_method("File#_buffer")]
+async fn file_buffer(receiver: SkFile) -> SkString {
+    SkString::from_vec(file_state(&receiver).take_buffer().await)
+}
+
+#[async_shiika_method("File#consume")]
+async fn file_consume(receiver: SkFile, n_bytes: SkInt) {
+    file_state(&receiver).consume(n_bytes.val() as usize).await;
+}
+
+#[async_shiika_method("File#_write")]
+async fn file_write(receiver: SkFile, s: SkString) -> SkBool {
+    let bytes = s.value().to_vec();
+    file_state(&receiver).write(&bytes, false).await.into()
+}
+
+#[async_shiika_method("File#_close")]
+async fn file_close(receiver: SkFile) -> SkBool {
+    file_state(&receiver).close().await.into()
+}
+
+#[async_shiika_method("File#_error_message")]
+async fn file_error_message(receiver: SkFile) -> SkString {
+    SkString::from_rust_string(file_state(&receiver).error_message().await)
+}
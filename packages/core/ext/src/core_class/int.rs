@@ -1,36 +1,66 @@
+use crate::core_class::object::mix_hash;
+use crate::runtime::fault_at_call_site;
 use shiika_ffi::core_class::{SkBool, SkFloat, SkInt};
 use shiika_ffi_macro::{async_shiika_method, shiika_method};
 
+/// Returns the result of a checked arithmetic or terminates the process if
+/// it overflowed.
+fn checked(result: Option<i64>, method_name: &str) -> SkInt {
+    match result {
+        Some(n) => n.into(),
+        None => fault_at_call_site(&format!("{}: integer overflow", method_name)),
+    }
+}
+
 #[shiika_method("Int#-@")]
 pub extern "C" fn int_uminus(receiver: SkInt) -> SkInt {
-    (-receiver.val()).into()
+    checked(receiver.val().checked_neg(), "Int#-@")
 }
 
 #[shiika_method("Int#+")]
 pub extern "C" fn int_add(receiver: SkInt, other: SkInt) -> SkInt {
-    (receiver.val() + other.val()).into()
+    checked(receiver.val().checked_add(other.val()), "Int#+")
 }
 
 #[shiika_method("Int#-")]
 pub extern "C" fn int_sub(receiver: SkInt, other: SkInt) -> SkInt {
-    (receiver.val() - other.val()).into()
+    checked(receiver.val().checked_sub(other.val()), "Int#-")
 }
 
 #[shiika_method("Int#*")]
 pub extern "C" fn int_mul(receiver: SkInt, other: SkInt) -> SkInt {
-    (receiver.val() * other.val()).into()
+    checked(receiver.val().checked_mul(other.val()), "Int#*")
+}
+
+/// Returns the quotient and the remainder of the division rounded toward
+/// negative infinity (i.e. the remainder has the same sign as `b`.)
+fn floor_divmod(a: i64, b: i64, method_name: &str) -> (i64, i64) {
+    if b == 0 {
+        fault_at_call_site(&format!("{}: division by zero", method_name))
+    }
+    let Some(q) = a.checked_div(b) else {
+        fault_at_call_site(&format!("{}: integer overflow", method_name))
+    };
+    let r = a % b;
+    if r != 0 && (r < 0) != (b < 0) {
+        (q - 1, r + b)
+    } else {
+        (q, r)
+    }
 }
 
 #[shiika_method("Int#/")]
-pub extern "C" fn int_div(receiver: SkInt, other: SkInt) -> SkFloat {
-    let a = receiver.val() as f64;
-    let b = other.val() as f64;
-    (a / b).into()
+pub extern "C" fn int_div(receiver: SkInt, other: SkInt) -> SkInt {
+    floor_divmod(receiver.val(), other.val(), "Int#/").0.into()
 }
 
 #[shiika_method("Int#%")]
 pub extern "C" fn int_mod(receiver: SkInt, other: SkInt) -> SkInt {
-    (receiver.val() % other.val()).into()
+    if receiver.val() == i64::MIN && other.val() == -1 {
+        // `i64::MIN % -1` is 0 (not an overflow)
+        return 0i64.into();
+    }
+    floor_divmod(receiver.val(), other.val(), "Int#%").1.into()
 }
 
 #[shiika_method("Int#and")]
@@ -83,6 +113,13 @@ async fn int_eq(receiver: SkInt, other: SkInt) -> SkBool {
     (receiver.val() == other.val()).into()
 }
 
+/// Mix `other` into the hash value `self`.
+#[shiika_method("Int#_hash_combine")]
+pub extern "C" fn int_hash_combine(receiver: SkInt, other: SkInt) -> SkInt {
+    let h = receiver.val() as u64;
+    mix_hash(h.rotate_left(5) ^ (other.val() as u64)).into()
+}
+
 #[shiika_method("Int#to_f")]
 pub extern "C" fn int_to_f(receiver: SkInt) -> SkFloat {
     (receiver.val() as f64).into()
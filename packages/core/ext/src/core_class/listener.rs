@@ -0,0 +1,115 @@
+use crate::core_class::socket::socket_state;
+use bdwgc_alloc::Allocator;
+use shiika_ffi::core_class::{SkBool, SkInt, SkListener, SkSocket, SkString};
+use shiika_ffi_macro::{async_shiika_method, shiika_method};
+use std::ffi::c_void;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, Notify};
+
+/// The value of `Listener#@_state`.
+struct ListenerState {
+    /// `None` until bound and after closed. `_accept` waits on a clone so
+    /// that `close` does not need to wait for it.
+    listener: Mutex<Option<Arc<TcpListener>>>,
+    /// Wakes up the pending `_accept`s on `close`
+    closed: Notify,
+    error: std::sync::Mutex<String>,
+}
+
+impl ListenerState {
+    fn fail(&self, e: impl std::fmt::Display) -> SkBool {
+        *self.error.lock().unwrap() = e.to_string();
+        false.into()
+    }
+}
+
+fn listener_state(listener: &SkListener) -> &'static ListenerState {
+    unsafe { &*(listener.state() as *const ListenerState) }
+}
+
+/// Frees the `ListenerState` (given as `data`) when the `Listener` is
+/// garbage collected.
+extern "C" fn listener_finalizer(_obj: *mut c_void, data: *mut c_void) {
+    unsafe { drop(Box::from_raw(data as *mut ListenerState)) };
+}
+
+/// Returns the initial value of `@_state`.
+#[shiika_method("Listener#_initialize_rustlib")]
+pub extern "C" fn listener_initialize_rustlib(receiver: SkListener) -> *mut u8 {
+    let state = ListenerState {
+        listener: Mutex::new(None),
+        closed: Notify::new(),
+        error: std::sync::Mutex::new(String::new()),
+    };
+    let state = Box::into_raw(Box::new(state));
+    unsafe {
+        Allocator::register_finalizer(
+            receiver.as_ptr() as *const c_void,
+            listener_finalizer,
+            state as *const c_void,
+        );
+    }
+    state as *mut u8
+}
+
+/// Start listening on `host`:`port`. Port 0 means any free port.
+#[async_shiika_method("Listener#_bind")]
+async fn listener_bind(receiver: SkListener, host: SkString, port: SkInt) -> SkBool {
+    let state = listener_state(&receiver);
+    let Ok(port) = u16::try_from(port.val()) else {
+        return state.fail(format!("invalid port: {}", port));
+    };
+    let host = host.as_str().to_string();
+    match TcpListener::bind((host, port)).await {
+        Ok(listener) => {
+            *state.listener.lock().await = Some(Arc::new(listener));
+            true.into()
+        }
+        Err(e) => state.fail(e),
+    }
+}
+
+/// Returns the port number the listener is bound to.
+#[async_shiika_method("Listener#port")]
+async fn listener_port(receiver: SkListener) -> SkInt {
+    let listener = listener_state(&receiver).listener.lock().await;
+    match listener.as_ref().map(|l| l.local_addr()) {
+        Some(Ok(addr)) => (addr.port() as i64).into(),
+        _ => 0.into(),
+    }
+}
+
+/// Wait for a new connection and let `socket` use it.
+#[async_shiika_method("Listener#_accept")]
+async fn listener_accept(receiver: SkListener, socket: SkSocket) -> SkBool {
+    let state = listener_state(&receiver);
+    // Register before checking `listener` so that a `close` in between is
+    // not missed
+    let closed = state.closed.notified();
+    tokio::pin!(closed);
+    closed.as_mut().enable();
+    let Some(l) = state.listener.lock().await.clone() else {
+        return state.fail("the listener is closed");
+    };
+    tokio::select! {
+        result = l.accept() => match result {
+            Ok((stream, _)) => socket_state(&socket).open(Ok(stream)).await.into(),
+            Err(e) => state.fail(e),
+        },
+        _ = closed => state.fail("the listener is closed"),
+    }
+}
+
+/// Stop listening. Does nothing if already closed.
+#[async_shiika_method("Listener#close")]
+async fn listener_close(receiver: SkListener) {
+    let state = listener_state(&receiver);
+    state.listener.lock().await.take();
+    state.closed.notify_waiters();
+}
+
+#[shiika_method("Listener#_error_message")]
+pub extern "C" fn listener_error_message(receiver: SkListener) -> SkString {
+    SkString::from_rust_string(listener_state(&receiver).error.lock().unwrap().clone())
+}
@@ -59,8 +59,3 @@ pub extern "C" fn mutable_string_write_byte(receiver: SkMutableString, nth: SkIn
     }
     vec[idx] = b as u8;
 }
-
-#[shiika_method("MutableString#_unsafe_to_s")]
-pub extern "C" fn mutable_string_unsafe_to_s(receiver: SkMutableString) -> SkString {
-    SkString::from_vec(receiver.value().to_vec())
-}
@@ -0,0 +1,34 @@
+use crate::runtime::{hold_lock, release_lock};
+use shiika_ffi::core_class::SkMutex;
+use shiika_ffi_macro::{async_shiika_method, shiika_method};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+// `Mutex#@_state` is a semaphore with a single permit. The guarded value
+// itself is held in `Mutex#@value` on the Shiika side.
+fn mutex_state(mutex: &SkMutex) -> &'static Arc<Semaphore> {
+    unsafe { &*(mutex.state() as *const Arc<Semaphore>) }
+}
+
+/// Returns the initial value of `@_state`.
+#[shiika_method("Mutex#_initialize_rustlib")]
+pub extern "C" fn mutex_initialize_rustlib(_receiver: SkMutex) -> *mut u8 {
+    Box::into_raw(Box::new(Arc::new(Semaphore::new(1)))) as *mut u8
+}
+
+/// Wait until the lock is available and take it.
+/// The lock is held by the current task until `_release` is called (or the
+/// task is stopped.)
+#[async_shiika_method("Mutex#_acquire")]
+async fn mutex_acquire(receiver: SkMutex) {
+    let permit = Arc::clone(mutex_state(&receiver))
+        .acquire_owned()
+        .await
+        .expect("[BUG] Mutex#_acquire: semaphore closed");
+    hold_lock(permit);
+}
+
+#[shiika_method("Mutex#_release")]
+pub extern "C" fn mutex_release(receiver: SkMutex) {
+    release_lock(mutex_state(&receiver));
+}
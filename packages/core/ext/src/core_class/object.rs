@@ -9,6 +9,19 @@ async fn object_eq(receiver: SkObject, other: SkObject) -> SkBool {
     (receiver.as_raw_u64() == other.as_raw_u64()).into()
 }
 
+/// Scramble the bits of `x` so that similar values (eg. addresses) have
+/// different lower bits (splitmix64 finalizer.)
+pub fn mix_hash(x: u64) -> i64 {
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    (x ^ (x >> 31)) as i64
+}
+
+#[async_shiika_method("Object#hash")]
+async fn object_hash(receiver: SkObject) -> SkInt {
+    mix_hash(receiver.as_raw_u64()).into()
+}
+
 #[async_shiika_method("Object#class")]
 async fn object_class(receiver: SkObject) -> SkClass {
     receiver.class()
@@ -0,0 +1,264 @@
+//! `Process` and `Env`
+use bdwgc_alloc::Allocator;
+use shiika_ffi::core_class::{SkArray, SkBool, SkChild, SkClass, SkInt, SkString};
+use shiika_ffi_macro::{async_shiika_method, shiika_method};
+use std::env;
+use std::ffi::c_void;
+use std::fmt::Display;
+use std::process::{ExitStatus, Stdio};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// The value of `@_state` of `Process::Child`.
+/// Methods return false on failure and the message is kept for
+/// `_error_message`.
+struct ChildState {
+    inner: Mutex<Inner>,
+}
+
+struct Inner {
+    /// `None` until spawned
+    child: Option<Child>,
+    /// `None` after `close_stdin`
+    stdin: Option<ChildStdin>,
+    stdout: Option<BufReader<ChildStdout>>,
+    /// The bytes read by the last `_fill_buf`
+    buffer: Vec<u8>,
+    /// The outputs collected by `_wait` (only for `Process.run`)
+    captured_stdout: Vec<u8>,
+    captured_stderr: Vec<u8>,
+    status: i64,
+    error: String,
+}
+
+impl Inner {
+    fn fail(&mut self, e: impl Display) -> bool {
+        self.error = e.to_string();
+        false
+    }
+}
+
+/// Frees the `ChildState` (given as `data`) when the `Process::Child` is
+/// garbage collected. The process is killed if it is still running.
+extern "C" fn child_finalizer(_obj: *mut c_void, data: *mut c_void) {
+    unsafe { drop(Box::from_raw(data as *mut ChildState)) };
+}
+
+fn child_state(child: &SkChild) -> &'static ChildState {
+    unsafe { &*(child.state() as *const ChildState) }
+}
+
+/// Returns the exit status of the process (or -1 if killed by a signal.)
+fn exit_code(status: ExitStatus) -> i64 {
+    status.code().unwrap_or(-1) as i64
+}
+
+/// Returns the number of the command line arguments (without the program
+/// name.)
+#[shiika_method("Meta:Process#_n_args")]
+pub extern "C" fn meta_process_n_args(_receiver: SkClass) -> SkInt {
+    (env::args().count().saturating_sub(1) as i64).into()
+}
+
+#[shiika_method("Meta:Process#_arg")]
+pub extern "C" fn meta_process_arg(_receiver: SkClass, idx: SkInt) -> SkString {
+    let arg = env::args().nth(idx.val() as usize + 1).unwrap_or_default();
+    SkString::from_rust_string(arg)
+}
+
+/// Terminates the process with the exit status `code`.
+#[shiika_method("Meta:Process#exit")]
+pub extern "C" fn meta_process_exit(_receiver: SkClass, code: SkInt) {
+    std::process::exit(code.val() as i32);
+}
+
+#[shiika_method("Meta:Process#pid")]
+pub extern "C" fn meta_process_pid(_receiver: SkClass) -> SkInt {
+    (std::process::id() as i64).into()
+}
+
+/// Returns the value of the environment variable (or an empty string if
+/// it is not set or not valid as utf-8.)
+#[shiika_method("Meta:Env#_get")]
+pub extern "C" fn meta_env_get(_receiver: SkClass, name: SkString) -> SkString {
+    SkString::from_rust_string(env::var(name.as_str()).unwrap_or_default())
+}
+
+#[shiika_method("Meta:Env#_has?")]
+pub extern "C" fn meta_env_has(_receiver: SkClass, name: SkString) -> SkBool {
+    env::var(name.as_str()).is_ok().into()
+}
+
+#[shiika_method("Meta:Env#set")]
+pub extern "C" fn meta_env_set(_receiver: SkClass, name: SkString, value: SkString) {
+    env::set_var(name.as_str(), value.as_str());
+}
+
+/// Returns the initial value of `@_state`.
+#[shiika_method("Process::Child#_initialize_rustlib")]
+pub extern "C" fn process_child_initialize_rustlib(receiver: SkChild) -> *mut u8 {
+    let state = ChildState {
+        inner: Mutex::new(Inner {
+            child: None,
+            stdin: None,
+            stdout: None,
+            buffer: Vec::new(),
+            captured_stdout: Vec::new(),
+            captured_stderr: Vec::new(),
+            status: 0,
+            error: String::new(),
+        }),
+    };
+    let state = Box::into_raw(Box::new(state));
+    unsafe {
+        Allocator::register_finalizer(
+            receiver.as_ptr() as *const c_void,
+            child_finalizer,
+            state as *const c_void,
+        );
+    }
+    state as *mut u8
+}
+
+/// Start the command `cmd` with `args`. If `capture` is true, its stdout
+/// and stderr are collected by `_wait`. Otherwise its stdin and stdout are
+/// connected to the receiver.
+#[async_shiika_method("Process::Child#_spawn")]
+async fn process_child_spawn(
+    receiver: SkChild,
+    cmd: SkString,
+    args: SkArray<SkString>,
+    capture: SkBool,
+) -> SkBool {
+    let capture = bool::from(capture);
+    let mut command = Command::new(cmd.as_str());
+    command
+        .args(args.as_vec().iter().map(|arg| arg.as_str()))
+        .kill_on_drop(true);
+    if capture {
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+    } else {
+        command.stdin(Stdio::piped()).stdout(Stdio::piped());
+    }
+    let mut inner = child_state(&receiver).inner.lock().await;
+    match command.spawn() {
+        Ok(mut child) => {
+            if !capture {
+                inner.stdin = child.stdin.take();
+                inner.stdout = child.stdout.take().map(BufReader::new);
+            }
+            inner.child = Some(child);
+            true
+        }
+        Err(e) => inner.fail(e),
+    }
+    .into()
+}
+
+#[async_shiika_method("Process::Child#_fill_buf")]
+async fn process_child_fill_buf(receiver: SkChild) -> SkBool {
+    let mut inner = child_state(&receiver).inner.lock().await;
+    let result = match inner.stdout.as_mut() {
+        Some(stdout) => stdout.fill_buf().await.map(|bytes| bytes.to_vec()),
+        None => return inner.fail("stdout is not available").into(),
+    };
+    match result {
+        Ok(bytes) => {
+            inner.buffer = bytes;
+            true
+        }
+        Err(e) => inner.fail(e),
+    }
+    .into()
+}
+
+#[async_shiika_method("Process::Child#_buffer")]
+async fn process_child_buffer(receiver: SkChild) -> SkString {
+    let mut inner = child_state(&receiver).inner.lock().await;
+    SkString::from_vec(std::mem::take(&mut inner.buffer))
+}
+
+#[async_shiika_method("Process::Child#consume")]
+async fn process_child_consume(receiver: SkChild, n_bytes: SkInt) {
+    let mut inner = child_state(&receiver).inner.lock().await;
+    if let Some(stdout) = inner.stdout.as_mut() {
+        stdout.consume(n_bytes.val() as usize);
+    }
+}
+
+#[async_shiika_method("Process::Child#_write")]
+async fn process_child_write(receiver: SkChild, s: SkString) -> SkBool {
+    let bytes = s.value().to_vec();
+    let mut inner = child_state(&receiver).inner.lock().await;
+    let result = match inner.stdin.as_mut() {
+        Some(stdin) => match stdin.write_all(&bytes).await {
+            Ok(()) => stdin.flush().await,
+            Err(e) => Err(e),
+        },
+        None => return inner.fail("stdin is closed").into(),
+    };
+    match result {
+        Ok(()) => true,
+        Err(e) => inner.fail(e),
+    }
+    .into()
+}
+
+#[async_shiika_method("Process::Child#close_stdin")]
+async fn process_child_close_stdin(receiver: SkChild) {
+    child_state(&receiver).inner.lock().await.stdin = None;
+}
+
+/// Close the stdin and wait for the process to finish.
+#[async_shiika_method("Process::Child#_wait")]
+async fn process_child_wait(receiver: SkChild) -> SkBool {
+    let mut inner = child_state(&receiver).inner.lock().await;
+    inner.stdin = None;
+    let Some(mut child) = inner.child.take() else {
+        return inner.fail("the process is not running").into();
+    };
+    let result = if child.stdout.is_some() {
+        // Started by `Process.run`
+        child.wait_with_output().await.map(|output| {
+            inner.captured_stdout = output.stdout;
+            inner.captured_stderr = output.stderr;
+            output.status
+        })
+    } else {
+        child.wait().await
+    };
+    match result {
+        Ok(status) => {
+            inner.status = exit_code(status);
+            true
+        }
+        Err(e) => inner.fail(e),
+    }
+    .into()
+}
+
+#[async_shiika_method("Process::Child#_status")]
+async fn process_child_status(receiver: SkChild) -> SkInt {
+    child_state(&receiver).inner.lock().await.status.into()
+}
+
+#[async_shiika_method("Process::Child#_captured_stdout")]
+async fn process_child_captured_stdout(receiver: SkChild) -> SkString {
+    let mut inner = child_state(&receiver).inner.lock().await;
+    SkString::from_vec(std::mem::take(&mut inner.captured_stdout))
+}
+
+#[async_shiika_method("Process::Child#_captured_stderr")]
+async fn process_child_captured_stderr(receiver: SkChild) -> SkString {
+    let mut inner = child_state(&receiver).inner.lock().await;
+    SkString::from_vec(std::mem::take(&mut inner.captured_stderr))
+}
+
+#[async_shiika_method("Process::Child#_error_message")]
+async fn process_child_error_message(receiver: SkChild) -> SkString {
+    SkString::from_rust_string(child_state(&receiver).inner.lock().await.error.clone())
+}
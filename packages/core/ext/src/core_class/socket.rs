@@ -0,0 +1,58 @@
+use crate::io::StreamState;
+use shiika_ffi::core_class::{SkBool, SkInt, SkSocket, SkString};
+use shiika_ffi_macro::{async_shiika_method, shiika_method};
+use tokio::net::TcpStream;
+
+pub fn socket_state(socket: &SkSocket) -> &'static StreamState<TcpStream> {
+    unsafe { StreamState::from_raw(socket.state()) }
+}
+
+/// Returns the initial value of `@_state`.
+#[shiika_method("Socket#_initialize_rustlib")]
+pub extern "C" fn socket_initialize_rustlib(receiver: SkSocket) -> *mut u8 {
+    StreamState::<TcpStream>::new_raw(receiver.as_ptr())
+}
+
+/// Connect to `host`:`port` with TCP.
+#[async_shiika_method("Socket#_connect")]
+async fn socket_connect(receiver: SkSocket, host: SkString, port: SkInt) -> SkBool {
+    let host = host.as_str().to_string();
+    let result = match u16::try_from(port.val()) {
+        Ok(port) => TcpStream::connect((host, port)).await,
+        Err(_) => Err(std::io::Error::other(format!("invalid port: {}", port))),
+    };
+    socket_state(&receiver).open(result).await.into()
+}
+
+#[async_shiika_method("Socket#_fill_buf")]
+async fn socket_fill_buf(receiver: SkSocket) -> SkBool {
+    socket_state(&receiver).fill_buf().await.into()
+}
+
+#[async_shiika_method("Socket#_buffer")]
+async fn socket_buffer(receiver: SkSocket) -> SkString {
+    SkString::from_vec(socket_state(&receiver).take_buffer().await)
+}
+
+#[async_shiika_method("Socket#consume")]
+async fn socket_consume(receiver: SkSocket, n_bytes: SkInt) {
+    socket_state(&receiver)
+        .consume(n_bytes.val() as usize)
+        .await;
+}
+
+#[async_shiika_method("Socket#_write")]
+async fn socket_write(receiver: SkSocket, s: SkString) -> SkBool {
+    let bytes = s.value().to_vec();
+    socket_state(&receiver).write(&bytes, true).await.into()
+}
+
+#[async_shiika_method("Socket#_close")]
+async fn socket_close(receiver: SkSocket) -> SkBool {
+    socket_state(&receiver).close().await.into()
+}
+
+#[async_shiika_method("Socket#_error_message")]
+async fn socket_error_message(receiver: SkSocket) -> SkString {
+    SkString::from_rust_string(socket_state(&receiver).error_message().await)
+}
@@ -1,5 +1,47 @@
-use shiika_ffi::core_class::{SkBool, SkInt, SkString};
+use shiika_ffi::core_class::{SkBool, SkFloat, SkInt, SkString};
 use shiika_ffi_macro::{async_shiika_method, shiika_method};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Parse the longest prefix of `s` which looks like a float (e.g. "-1.5e3").
+/// Returns 0.0 if there is no such prefix.
+fn parse_float_prefix(s: &[u8]) -> f64 {
+    let skip_digits = |mut i: usize| {
+        while i < s.len() && s[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    let start = if matches!(s.first(), Some(b'+' | b'-')) {
+        1
+    } else {
+        0
+    };
+    let mut end = skip_digits(start);
+    if end == start {
+        return 0.0;
+    }
+    if s.get(end) == Some(&b'.') {
+        let frac_end = skip_digits(end + 1);
+        if frac_end > end + 1 {
+            end = frac_end;
+        }
+    }
+    if matches!(s.get(end), Some(b'e' | b'E')) {
+        let mut i = end + 1;
+        if matches!(s.get(i), Some(b'+' | b'-')) {
+            i += 1;
+        }
+        let exp_end = skip_digits(i);
+        if exp_end > i {
+            end = exp_end;
+        }
+    }
+    // Safe because `s[..end]` consists of ASCII characters
+    std::str::from_utf8(&s[..end])
+        .unwrap()
+        .parse()
+        .unwrap_or(0.0)
+}
 
 #[shiika_method("String#initialize")]
 pub extern "C" fn string_initialize(mut receiver: SkString, bytes: *const u8, n_bytes: u64) {
@@ -32,6 +74,11 @@ async fn string_eq(receiver: SkString, other: SkString) -> SkBool {
     (receiver.value() == other.value()).into()
 }
 
+#[async_shiika_method("String#<=>")]
+async fn string_cmp(receiver: SkString, other: SkString) -> SkInt {
+    (receiver.value().cmp(other.value()) as i64).into()
+}
+
 #[shiika_method("String#bytesize")]
 pub extern "C" fn string_bytesize(receiver: SkString) -> SkInt {
     (receiver.value().len() as i64).into()
@@ -87,6 +134,11 @@ pub extern "C" fn string_slice_bytes(receiver: SkString, from: SkInt, bytes: SkI
     SkString::from_vec(slice.to_vec())
 }
 
+#[shiika_method("String#to_f")]
+pub extern "C" fn string_to_f(receiver: SkString) -> SkFloat {
+    parse_float_prefix(receiver.value()).into()
+}
+
 #[shiika_method("String#to_i")]
 pub extern "C" fn string_to_i(receiver: SkString) -> SkInt {
     let val = receiver.value();
@@ -116,6 +168,13 @@ pub extern "C" fn string_to_i(receiver: SkString) -> SkInt {
     }
 }
 
+#[async_shiika_method("String#hash")]
+async fn string_hash(receiver: SkString) -> SkInt {
+    let mut hasher = DefaultHasher::new();
+    receiver.value().hash(&mut hasher);
+    (hasher.finish() as i64).into()
+}
+
 #[async_shiika_method("String#to_s")]
 async fn string_to_s(receiver: SkString) -> SkString {
     receiver
@@ -0,0 +1,88 @@
+use crate::runtime::spawn_lambda0;
+use shiika_ffi::async_::ChiikaValue;
+use shiika_ffi::core_class::{SkBool, SkFn, SkObject, SkString, SkTask};
+use shiika_ffi_macro::{async_shiika_method, shiika_method};
+use std::sync::OnceLock;
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// The value of `Task#@_state`.
+struct TaskState {
+    abort_handle: AbortHandle,
+    /// Becomes `None` when the task is stopped and `outcome` is set
+    handle: tokio::sync::Mutex<Option<JoinHandle<ChiikaValue>>>,
+    outcome: OnceLock<Outcome>,
+}
+
+enum Outcome {
+    Finished(ChiikaValue),
+    Cancelled,
+    Panicked,
+}
+
+fn task_state(task: &SkTask) -> &'static TaskState {
+    unsafe { &*(task.state() as *const TaskState) }
+}
+
+fn task_outcome(task: &SkTask) -> &'static Outcome {
+    task_state(task)
+        .outcome
+        .get()
+        .expect("[BUG] the task is not stopped")
+}
+
+/// Start running `f` concurrently. Returns the initial value of `@_state`.
+#[shiika_method("Task#_start")]
+pub extern "C" fn task_start(_receiver: SkTask, f: SkFn) -> *mut u8 {
+    let handle = spawn_lambda0(f);
+    let state = TaskState {
+        abort_handle: handle.abort_handle(),
+        handle: tokio::sync::Mutex::new(Some(handle)),
+        outcome: OnceLock::new(),
+    };
+    Box::into_raw(Box::new(state)) as *mut u8
+}
+
+/// Wait for the task to stop. Returns true if it has run to completion.
+/// This may be called from multiple tasks at the same time.
+#[async_shiika_method("Task#_wait")]
+async fn task_wait(receiver: SkTask) -> SkBool {
+    let state = task_state(&receiver);
+    let mut handle = state.handle.lock().await;
+    if let Some(h) = handle.as_mut() {
+        let outcome = match h.await {
+            Ok(value) => Outcome::Finished(value),
+            Err(e) if e.is_cancelled() => Outcome::Cancelled,
+            Err(_) => Outcome::Panicked,
+        };
+        let _ = state.outcome.set(outcome);
+        *handle = None;
+    }
+    matches!(state.outcome.get(), Some(Outcome::Finished(_))).into()
+}
+
+#[shiika_method("Task#_value")]
+pub extern "C" fn task_value(receiver: SkTask) -> SkObject {
+    match task_outcome(&receiver) {
+        Outcome::Finished(value) => SkObject::from_raw_u64(*value),
+        _ => panic!("[BUG] Task#_value: the task is not finished"),
+    }
+}
+
+#[shiika_method("Task#_error_message")]
+pub extern "C" fn task_error_message(receiver: SkTask) -> SkString {
+    match task_outcome(&receiver) {
+        Outcome::Cancelled => SkString::from_rust_string("the task was cancelled"),
+        Outcome::Panicked => SkString::from_rust_string("the task panicked"),
+        _ => panic!("[BUG] Task#_error_message: the task is not failed"),
+    }
+}
+
+#[shiika_method("Task#cancel")]
+pub extern "C" fn task_cancel(receiver: SkTask) {
+    task_state(&receiver).abort_handle.abort();
+}
+
+#[shiika_method("Task#finished?")]
+pub extern "C" fn task_finished(receiver: SkTask) -> SkBool {
+    task_state(&receiver).abort_handle.is_finished().into()
+}
@@ -0,0 +1,136 @@
+//! Buffered streams shared by `File` and `Socket`.
+use bdwgc_alloc::Allocator;
+use std::ffi::c_void;
+use std::fmt::Display;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufStream};
+use tokio::sync::Mutex;
+
+/// The value of `@_state` of `File` and `Socket`.
+/// Methods return false on failure and the message is kept for
+/// `error_message`.
+pub struct StreamState<T> {
+    inner: Mutex<Inner<T>>,
+}
+
+struct Inner<T> {
+    /// `None` until opened and after closed
+    stream: Option<BufStream<T>>,
+    /// The bytes read by the last `fill_buf`
+    buffer: Vec<u8>,
+    error: String,
+}
+
+impl<T> Inner<T> {
+    fn fail(&mut self, e: impl Display) -> bool {
+        self.error = e.to_string();
+        false
+    }
+}
+
+/// Frees the `StreamState` (given as `data`) when the owner object is
+/// garbage collected. The stream is closed without flushing.
+extern "C" fn stream_finalizer<T>(_obj: *mut c_void, data: *mut c_void) {
+    unsafe { drop(Box::from_raw(data as *mut StreamState<T>)) };
+}
+
+impl<T: AsyncRead + AsyncWrite + Unpin> StreamState<T> {
+    /// Create a state which is not opened yet. It is freed when `owner`
+    /// (the `File` or `Socket`) is garbage collected.
+    pub fn new_raw(owner: *const u8) -> *mut u8 {
+        let state = StreamState::<T> {
+            inner: Mutex::new(Inner {
+                stream: None,
+                buffer: Vec::new(),
+                error: String::new(),
+            }),
+        };
+        let state = Box::into_raw(Box::new(state));
+        unsafe {
+            Allocator::register_finalizer(
+                owner as *const c_void,
+                stream_finalizer::<T>,
+                state as *const c_void,
+            );
+        }
+        state as *mut u8
+    }
+
+    /// # Safety
+    /// `ptr` must be the one returned by `new_raw` with the same `T`.
+    pub unsafe fn from_raw(ptr: *mut u8) -> &'static Self {
+        &*(ptr as *const Self)
+    }
+
+    /// Start using the stream returned by `result`.
+    pub async fn open(&self, result: std::io::Result<T>) -> bool {
+        let mut inner = self.inner.lock().await;
+        match result {
+            Ok(stream) => {
+                inner.stream = Some(BufStream::new(stream));
+                true
+            }
+            Err(e) => inner.fail(e),
+        }
+    }
+
+    /// Read bytes into the buffer if it is empty. The content of the buffer
+    /// can be taken with `take_buffer`. It is empty at the end of the stream.
+    pub async fn fill_buf(&self) -> bool {
+        let mut inner = self.inner.lock().await;
+        let result = match inner.stream.as_mut() {
+            Some(stream) => stream.fill_buf().await.map(|bytes| bytes.to_vec()),
+            None => return inner.fail("the stream is closed"),
+        };
+        match result {
+            Ok(bytes) => {
+                inner.buffer = bytes;
+                true
+            }
+            Err(e) => inner.fail(e),
+        }
+    }
+
+    pub async fn take_buffer(&self) -> Vec<u8> {
+        std::mem::take(&mut self.inner.lock().await.buffer)
+    }
+
+    /// Mark the first `n_bytes` bytes of the buffer as read.
+    pub async fn consume(&self, n_bytes: usize) {
+        if let Some(stream) = self.inner.lock().await.stream.as_mut() {
+            stream.consume(n_bytes);
+        }
+    }
+
+    /// Write `bytes` to the stream. Unless `flush` is true, they may be
+    /// buffered until `close`.
+    pub async fn write(&self, bytes: &[u8], flush: bool) -> bool {
+        let mut inner = self.inner.lock().await;
+        let result = match inner.stream.as_mut() {
+            Some(stream) => match stream.write_all(bytes).await {
+                Ok(()) if flush => stream.flush().await,
+                r => r,
+            },
+            None => return inner.fail("the stream is closed"),
+        };
+        match result {
+            Ok(()) => true,
+            Err(e) => inner.fail(e),
+        }
+    }
+
+    /// Flush the buffer and close the stream. Does nothing if already closed.
+    pub async fn close(&self) -> bool {
+        let mut inner = self.inner.lock().await;
+        let Some(mut stream) = inner.stream.take() else {
+            return true;
+        };
+        match stream.shutdown().await {
+            Ok(()) => true,
+            Err(e) => inner.fail(e),
+        }
+    }
+
+    pub async fn error_message(&self) -> String {
+        self.inner.lock().await.error.clone()
+    }
+}
@@ -1,2 +1,3 @@
 mod core_class;
+mod io;
 mod runtime;
@@ -4,13 +4,27 @@ mod wtable;
 
 use bdwgc_alloc::Allocator;
 use shiika_ffi::async_::{ChiikaCont, ChiikaEnv, ChiikaValue, ContFuture};
+use shiika_ffi::core_class::SkFn;
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_char, CStr};
 use std::future::{poll_fn, Future};
+use std::io::Write;
 use std::pin::Pin;
+use std::ptr;
+use std::sync::Arc;
 use std::task::Poll;
+use tokio::runtime::{Builder, Runtime};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
 
 #[allow(improper_ctypes_definitions)]
 type ChiikaThunk = unsafe extern "C" fn(env: *mut ChiikaEnv, cont: ChiikaCont) -> ContFuture;
 
+/// The function held by a `Fn0` object (lambdas are always async.)
+#[allow(improper_ctypes_definitions)]
+type ChiikaLambda0 =
+    unsafe extern "C" fn(env: *mut ChiikaEnv, fn_obj: SkFn, cont: ChiikaCont) -> ContFuture;
+
 #[allow(improper_ctypes)]
 extern "C" {
     fn chiika_start_user(env: *mut ChiikaEnv, cont: ChiikaCont) -> ContFuture;
@@ -28,17 +42,66 @@ extern "C" fn chiika_finish(env: *mut ChiikaEnv, _v: ChiikaValue) -> ContFuture
 #[no_mangle]
 #[allow(improper_ctypes_definitions)]
 pub extern "C" fn chiika_spawn(f: ChiikaThunk) -> u64 {
-    let poller = make_poller(f);
+    let poller = make_poller(move |env| unsafe { f(env, chiika_finish) });
     tokio::spawn(poller);
     0
 }
 
+/// Run the Shiika lambda `f` as a new tokio task.
+/// The task is resolved with the return value of the lambda.
+pub fn spawn_lambda0(f: SkFn) -> JoinHandle<ChiikaValue> {
+    let func = unsafe { std::mem::transmute::<*const u8, ChiikaLambda0>(f.func()) };
+    let poller = make_poller(move |env| unsafe { func(env, f.dup(), chiika_finish) });
+    tokio::spawn(poller)
+}
+
+// Must match `RuntimeFlavor` in skc_async_experiment
+const FLAVOR_CURRENT_THREAD: u64 = 0;
+const FLAVOR_MULTI_THREAD: u64 = 1;
+
+/// Start the tokio runtime and run the user program on it.
+/// `flavor` and `worker_threads` (0 means tokio's default) come from
+/// package.json5 and can be overridden with the environment variables
+/// `SHIIKA_RUNTIME` and `SHIIKA_WORKER_THREADS`.
 #[no_mangle]
-pub extern "C" fn chiika_start_tokio() {
+pub extern "C" fn chiika_start_tokio(flavor: u64, worker_threads: u64) {
     unsafe { GC_allow_register_threads() };
-    let poller = make_poller(chiika_start_user);
-    tokio::runtime::Builder::new_current_thread()
+    let poller = make_poller(|env| unsafe { chiika_start_user(env, chiika_finish) });
+    build_runtime(flavor, worker_threads).block_on(poller);
+}
+
+fn build_runtime(default_flavor: u64, default_worker_threads: u64) -> Runtime {
+    let flavor = match std::env::var("SHIIKA_RUNTIME").as_deref() {
+        Ok("current_thread") => FLAVOR_CURRENT_THREAD,
+        Ok("multi_thread") => FLAVOR_MULTI_THREAD,
+        Ok(s) => config_error(&format!(
+            "SHIIKA_RUNTIME must be `current_thread' or `multi_thread' (got `{}')",
+            s
+        )),
+        Err(_) => default_flavor,
+    };
+    let worker_threads = match std::env::var("SHIIKA_WORKER_THREADS") {
+        Ok(s) => s.parse().unwrap_or_else(|_| {
+            config_error(&format!(
+                "SHIIKA_WORKER_THREADS must be a number (got `{}')",
+                s
+            ))
+        }),
+        Err(_) => default_worker_threads,
+    };
+    let mut builder = if flavor == FLAVOR_MULTI_THREAD {
+        let mut b = Builder::new_multi_thread();
+        if worker_threads > 0 {
+            b.worker_threads(worker_threads as usize);
+        }
+        b
+    } else {
+        Builder::new_current_thread()
+    };
+    builder
         .enable_all()
+        // The GC must know all the threads which may hold Shiika objects
+        // (i.e. worker threads and the blocking pool) to scan their stacks.
         .on_thread_start(|| {
             unsafe { Allocator::register_current_thread() }.expect("Failed to register GC thread");
         })
@@ -47,15 +110,85 @@ pub extern "C" fn chiika_start_tokio() {
         })
         .build()
         .unwrap()
-        .block_on(poller);
 }
 
-fn make_poller(f: ChiikaThunk) -> impl Future<Output = ()> {
+/// Print an error about the runtime configuration and terminate the process.
+fn config_error(msg: &str) -> ! {
+    eprintln!("error: {}", msg);
+    std::process::exit(1);
+}
+
+/// Print `msg` and terminate the process.
+/// Used for runtime errors like a bad argument (like `backtrace::fault` of
+/// skc_rustlib. The new runtime does not have Shiika-level backtraces yet.)
+pub fn fault(msg: &str) -> ! {
+    print_fault(msg, None)
+}
+
+thread_local! {
+    /// The location of the last call of the methods which use
+    /// `fault_at_call_site` (null if not known)
+    static CALL_SITE: Cell<*const c_char> = const { Cell::new(ptr::null()) };
+}
+
+/// Set the call site (eg. "main.sk:3:in `Foo#bar'") of the method about
+/// to be called. Compiled code calls this right before the methods listed in
+/// `FAULTING_METHODS` of skc_async_experiment's mirgen.
+#[no_mangle]
+pub extern "C" fn shiika_set_call_site(site: *const c_char) {
+    CALL_SITE.with(|s| s.set(site));
+}
+
+/// Like `fault` but also prints the call site of the current method.
+/// Only the methods listed in `FAULTING_METHODS` may call this.
+pub fn fault_at_call_site(msg: &str) -> ! {
+    let site = CALL_SITE.with(|s| s.get());
+    let site = (!site.is_null()).then(|| unsafe { CStr::from_ptr(site) }.to_string_lossy());
+    print_fault(msg, site.as_deref())
+}
+
+fn print_fault(msg: &str, site: Option<&str>) -> ! {
+    let mut err = std::io::stderr().lock();
+    let _ = writeln!(err, "panic: {}", msg);
+    if let Some(site) = site {
+        let _ = writeln!(err, "\tfrom {}", site);
+    }
+    let _ = err.flush();
+    std::process::exit(1);
+}
+
+tokio::task_local! {
+    /// Locks taken by the running task (see `Mutex#_acquire`.) This is
+    /// dropped with the task, so the locks are released even if the task is
+    /// cancelled or panicked while holding them.
+    static HELD_LOCKS: RefCell<Vec<OwnedSemaphorePermit>>;
+}
+
+/// Keep `permit` until `release_lock` is called or the current task stops.
+pub fn hold_lock(permit: OwnedSemaphorePermit) {
+    HELD_LOCKS.with(|locks| locks.borrow_mut().push(permit));
+}
+
+/// Release the lock of `semaphore` taken by the current task.
+pub fn release_lock(semaphore: &Arc<Semaphore>) {
+    HELD_LOCKS.with(|locks| {
+        let mut locks = locks.borrow_mut();
+        let i = locks
+            .iter()
+            .rposition(|p| Arc::ptr_eq(p.semaphore(), semaphore))
+            .expect("[BUG] release_lock: the lock is not held by this task");
+        drop(locks.remove(i));
+    });
+}
+
+/// Create a future which runs a Shiika async function with its own `ChiikaEnv`.
+/// `start` is called once to get the first future.
+fn make_poller(
+    mut start: impl FnMut(&mut ChiikaEnv) -> ContFuture + Send,
+) -> impl Future<Output = ChiikaValue> + Send {
     let mut env = ChiikaEnv::new();
-    poll_fn(move |context| loop {
-        let future = env
-            .pop_rust_frame()
-            .unwrap_or_else(|| unsafe { f(&mut env, chiika_finish) });
+    let poller = poll_fn(move |context| loop {
+        let future = env.pop_rust_frame().unwrap_or_else(|| start(&mut env));
         let mut pinned = Pin::new(future);
         let tmp = pinned.as_mut().poll(context);
         match tmp {
@@ -64,7 +197,7 @@ fn make_poller(f: ChiikaThunk) -> impl Future<Output = ()> {
                     let new_future = cont(&mut env, value);
                     env.push_rust_frame(new_future);
                 } else {
-                    return Poll::Ready(());
+                    return Poll::Ready(value);
                 }
             }
             Poll::Pending => {
@@ -72,5 +205,6 @@ fn make_poller(f: ChiikaThunk) -> impl Future<Output = ()> {
                 return Poll::Pending;
             }
         }
-    })
+    });
+    HELD_LOCKS.scope(RefCell::new(vec![]), poller)
 }
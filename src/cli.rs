@@ -10,9 +10,30 @@ pub struct Arguments {
 #[derive(Debug, Subcommand)]
 pub enum Command {
     /// Compile shiika program
-    Compile { filepath: String },
+    Compile {
+        filepath: String,
+        /// Emit DWARF debug info
+        #[clap(long)]
+        debug: bool,
+    },
     /// Compile and execute shiika program
-    Run { filepath: String },
+    Run {
+        filepath: String,
+        /// Emit DWARF debug info
+        #[clap(long)]
+        debug: bool,
+        /// Arguments passed to the program (eg. `shiika run a.sk -- foo bar`)
+        #[clap(last = true)]
+        args: Vec<String>,
+    },
+    /// Format shiika programs
+    Fmt {
+        #[clap(required = true)]
+        filepaths: Vec<String>,
+        /// Do not modify the files but exit with error if they are not formatted
+        #[clap(long)]
+        check: bool,
+    },
     /// Build corelib
     BuildCorelib,
 }
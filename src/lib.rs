@@ -1,4 +1,3 @@
 pub mod cli;
-mod loader;
 pub mod runner;
 mod targets;
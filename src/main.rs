@@ -7,12 +7,22 @@ fn main() -> Result<()> {
     let args = cli::parse_command_line_args();
 
     match &args.command {
-        cli::Command::Compile { filepath } => {
-            runner::compile(filepath)?;
+        cli::Command::Compile { filepath, debug } => {
+            compile(filepath, *debug)?;
         }
-        cli::Command::Run { filepath } => {
-            runner::compile(filepath)?;
-            runner::run(filepath)?;
+        cli::Command::Run {
+            filepath,
+            debug,
+            args,
+        } => {
+            compile(filepath, *debug)?;
+            let code = runner::run(filepath, args)?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        cli::Command::Fmt { filepaths, check } => {
+            runner::format_files(filepaths, *check)?;
         }
         cli::Command::BuildCorelib => {
             runner::build_corelib()?;
@@ -22,6 +32,14 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+fn compile(filepath: &str, debug: bool) -> Result<()> {
+    if debug {
+        runner::compile_with_debug_info(filepath)
+    } else {
+        runner::compile(filepath)
+    }
+}
+
 //fn print_err(err: Error) {
 //    println!("{}", err.msg);
 //    for frame in err.backtrace.frames() {
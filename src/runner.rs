@@ -1,7 +1,6 @@
-use crate::loader;
 use crate::targets;
 use anyhow::{anyhow, Context, Error, Result};
-use shiika_parser::{Parser, SourceFile};
+use shiika_parser::{loader, SourceFile};
 use skc_ast2hir;
 use skc_codegen;
 use skc_corelib;
@@ -14,23 +13,59 @@ use std::process::Command;
 
 /// Generate .ll from .sk
 pub fn compile<P: AsRef<Path>>(filepath: P) -> Result<()> {
+    compile_(filepath, false)
+}
+
+/// Generate .ll from .sk with DWARF debug info
+pub fn compile_with_debug_info<P: AsRef<Path>>(filepath: P) -> Result<()> {
+    compile_(filepath, true)
+}
+
+fn compile_<P: AsRef<Path>>(filepath: P, debug: bool) -> Result<()> {
     let path = filepath.as_ref();
-    let src = loader::load(path)?;
-    let ast = Parser::parse_files(&src)?;
+    let ast = loader::load(path, &package_dirs(path))?;
     log::debug!("created ast");
     let imports = load_builtin_exports()?;
     let hir = skc_ast2hir::make_hir(ast, &imports)?;
+    for warning in &hir.warnings {
+        eprint!("{}", warning);
+    }
     log::debug!("created hir");
     let mir = skc_mir::build(hir, imports);
     log::debug!("created mir");
     let bc_path = path.with_extension("bc");
     let ll_path = path.with_extension("ll");
     let triple = targets::default_triple();
-    skc_codegen::run(&mir, &bc_path, Some(&ll_path), true, Some(&triple))?;
+    skc_codegen::run(&mir, &bc_path, Some(&ll_path), true, Some(&triple), debug)?;
     log::debug!("created .bc");
     Ok(())
 }
 
+/// Format .sk files in place. If `check` is true, just print the files
+/// which are not formatted and return an error if any
+pub fn format_files<P: AsRef<Path>>(filepaths: &[P], check: bool) -> Result<()> {
+    let mut n_unformatted = 0;
+    for filepath in filepaths {
+        let path = filepath.as_ref();
+        let src = fs::read_to_string(path).context(format!("failed to read {}", path.display()))?;
+        let file = SourceFile::new(path.to_path_buf(), src);
+        let formatted = shiika_fmt::format(&file)?;
+        if formatted == file.content {
+            continue;
+        }
+        if check {
+            println!("{}", path.display());
+            n_unformatted += 1;
+        } else {
+            fs::write(path, formatted).context(format!("failed to write {}", path.display()))?;
+        }
+    }
+    if n_unformatted > 0 {
+        return Err(anyhow!("{} file(s) are not formatted", n_unformatted));
+    }
+    Ok(())
+}
+
 /// Load builtin/exports.json
 fn load_builtin_exports() -> Result<LibraryExports, Error> {
     let json_path = from_shiika_root("builtin/exports.json");
@@ -45,8 +80,7 @@ fn load_builtin_exports() -> Result<LibraryExports, Error> {
 
 /// Create builtin.bc and exports.json from builtin/*.sk and skc_corelib
 pub fn build_corelib() -> Result<(), Error> {
-    let builtin = load_builtin()?;
-    let ast = Parser::parse_files(&builtin)?;
+    let ast = loader::load(&from_shiika_root("builtin/index.sk"), &[])?;
     log::debug!("created ast");
     let corelib = skc_corelib::create();
     log::debug!("loaded corelib");
@@ -63,6 +97,7 @@ pub fn build_corelib() -> Result<(), Error> {
         Some(&from_shiika_root("builtin/builtin.ll")),
         false,
         Some(&triple),
+        false,
     )?;
     log::debug!("created .bc");
 
@@ -74,23 +109,30 @@ pub fn build_corelib() -> Result<(), Error> {
     Ok(())
 }
 
-/// Load ./builtin/*.sk
-fn load_builtin() -> Result<Vec<SourceFile>> {
-    loader::load(&from_shiika_root("builtin/index.sk"))
+/// Directories to search packages imported by the program (`import "foo"`)
+fn package_dirs(path: &Path) -> Vec<PathBuf> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    vec![dir.join("packages")]
 }
 
-/// Execute compiled .ll
-pub fn run<P: AsRef<Path>>(sk_path: P) -> Result<()> {
-    run_(sk_path, false)?;
-    Ok(())
+/// Execute compiled .ll with the command line arguments and return the
+/// exit status
+pub fn run<P: AsRef<Path>>(sk_path: P, args: &[String]) -> Result<i32> {
+    let (_, _, code) = run_(sk_path, args, false)?;
+    Ok(code)
 }
 
 /// Execute compiled .ll and return the outputs (for tests)
 pub fn run_and_capture<P: AsRef<Path>>(sk_path: P) -> Result<(String, String)> {
-    run_(sk_path, true)
+    let (stdout, stderr, _) = run_(sk_path, &[], true)?;
+    Ok((stdout, stderr))
 }
 
-fn run_<P: AsRef<Path>>(sk_path_: P, capture_out: bool) -> Result<(String, String)> {
+fn run_<P: AsRef<Path>>(
+    sk_path_: P,
+    args: &[String],
+    capture_out: bool,
+) -> Result<(String, String, i32)> {
     let triple = targets::default_triple();
     let sk_path = sk_path_.as_ref();
     let bc_path = sk_path.with_extension("bc");
@@ -155,18 +197,25 @@ fn run_<P: AsRef<Path>>(sk_path_: P, capture_out: bool) -> Result<(String, Strin
     fs::remove_file(bc_path)?;
 
     let mut cmd = Command::new(&exe_path);
+    cmd.args(args);
     if capture_out {
         let output = cmd.output().context("failed to execute process")?;
         let stdout = String::from_utf8(output.stdout).expect("invalid utf8 in stdout");
         let stderr = String::from_utf8(output.stderr).expect("invalid utf8 in stderr");
-        Ok((stdout, stderr))
+        Ok((stdout, stderr, exit_code(output.status)))
     } else {
-        cmd.status()
+        let status = cmd
+            .status()
             .context(format!("failed to run {}", exe_path.display()))?;
-        Ok(("".to_string(), "".to_string()))
+        Ok(("".to_string(), "".to_string(), exit_code(status)))
     }
 }
 
+/// Returns the exit code of the process (or 1 if killed by a signal)
+fn exit_code(status: std::process::ExitStatus) -> i32 {
+    status.code().unwrap_or(1)
+}
+
 /// Remove .bc and .out (used by unit tests)
 pub fn cleanup<P: AsRef<Path>>(sk_path_: P) -> Result<()> {
     let sk_path = sk_path_.as_ref();
@@ -0,0 +1,20 @@
+use anyhow::Result;
+use insta::{assert_snapshot, glob};
+use shiika_parser::SourceFile;
+use std::path::Path;
+
+#[test]
+fn test_fmt() -> Result<()> {
+    let base = Path::new(".").canonicalize()?;
+    glob!("fmt/*.sk", |sk_path_| {
+        let sk_path = sk_path_.strip_prefix(&base).unwrap();
+        let src = std::fs::read_to_string(sk_path).unwrap();
+        let file = SourceFile::new(sk_path.to_path_buf(), src);
+        let formatted = shiika_fmt::format(&file).unwrap();
+        // Formatting a formatted file should not change it
+        let file2 = SourceFile::new(sk_path.to_path_buf(), formatted.clone());
+        assert_eq!(shiika_fmt::format(&file2).unwrap(), formatted);
+        assert_snapshot!(formatted);
+    });
+    Ok(())
+}
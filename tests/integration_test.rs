@@ -33,6 +33,55 @@ fn test_no_panic() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_panic_backtrace() -> Result<()> {
+    let path = "tests/panic/backtrace.sk";
+    runner::compile(path)?;
+    let (stdout, stderr) = runner::run_and_capture(path)?;
+    assert_eq!(stdout, "");
+    let lines = stderr.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "panic: boom 1");
+    assert!(lines[1].ends_with("backtrace.sk:9:in `PanicTest.fail'"));
+    assert!(lines[2].ends_with("backtrace.sk:4:in `block in PanicTest.run'"));
+    assert!(lines
+        .last()
+        .unwrap()
+        .ends_with("backtrace.sk:12:in `<main>'"));
+    runner::cleanup(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_int_overflow() -> Result<()> {
+    let path = "tests/panic/int_overflow.sk";
+    runner::compile(path)?;
+    let (stdout, stderr) = runner::run_and_capture(path)?;
+    assert_eq!(stdout, "");
+    let lines = stderr.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "panic: Int#+: integer overflow");
+    assert!(lines[1].ends_with("int_overflow.sk:3:in `OverflowTest.double'"));
+    runner::cleanup(path)?;
+    Ok(())
+}
+
+#[test]
+fn test_unboxed_lvar() -> Result<()> {
+    let path = "tests/codegen/unboxed_lvar.sk";
+    runner::compile(path)?;
+    let ll = fs::read_to_string("tests/codegen/unboxed_lvar.ll")?;
+    let func = ll
+        .split("\ndefine ")
+        .find(|f| f.contains("@Meta_LoopTest_run("))
+        .ok_or_else(|| anyhow!("LoopTest.run not found"))?;
+    let body = &func[..func.find("\n}\n").unwrap()];
+    for boxing in ["@box_int", "@box_float", "@box_bool"] {
+        assert!(!body.contains(boxing), "found {} in the loop", boxing);
+    }
+    runner::cleanup(path)?;
+    fs::remove_file("tests/codegen/unboxed_lvar.ll")?;
+    Ok(())
+}
+
 /// Execute tests/sk/x.sk
 /// Fail if it prints something
 fn run_sk_test(path: &str) -> Result<()> {